@@ -0,0 +1,85 @@
+//! Frei konfigurierte Aktionen (`[actions.<name>]`, `/actions`).
+//!
+//! Argumente kommen als JSON-Liste, werden gegen das Schema der Aktion geprüft
+//! und einzeln als argv übergeben – ohne Shell, also ohne Quoting-Fallen.
+
+use serde_json::Value;
+use tracing::{info, instrument};
+
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::models::ActionInfo;
+use crate::scripts::runner::{self, ScriptOutput};
+
+#[must_use]
+pub fn list(config: &AppConfig) -> Vec<ActionInfo> {
+    config
+        .actions
+        .iter()
+        .map(|(name, action)| ActionInfo {
+            name: name.clone(),
+            description: action.description.clone(),
+            schema: action.schema.clone(),
+        })
+        .collect()
+}
+
+#[instrument(skip(config, args))]
+pub async fn run(config: &AppConfig, name: &str, args: &Value) -> Result<ScriptOutput, AppError> {
+    let action = config
+        .actions
+        .get(name)
+        .ok_or_else(|| AppError::not_found(format!("unknown action '{name}'")))?;
+
+    let errors: Vec<String> = action
+        .validator
+        .iter_errors(args)
+        .map(|err| err.to_string())
+        .collect();
+    if !errors.is_empty() {
+        return Err(AppError::bad_request(format!(
+            "invalid arguments for action '{name}': {}",
+            errors.join("; ")
+        )));
+    }
+    let argv = to_argv(args)?;
+
+    info!("running action {name} {argv:?}");
+    let argv: Vec<&str> = argv.iter().map(String::as_str).collect();
+    runner::run_configured(config, &action.script, &argv, None).await
+}
+
+/// Nur eine Liste aus Strings, Zahlen oder Booleans wird zu argv.
+fn to_argv(args: &Value) -> Result<Vec<String>, AppError> {
+    let items = args
+        .as_array()
+        .ok_or_else(|| AppError::bad_request("action arguments must be a list"))?;
+    items
+        .iter()
+        .map(|item| match item {
+            Value::String(text) => Ok(text.clone()),
+            Value::Number(number) => Ok(number.to_string()),
+            Value::Bool(flag) => Ok(flag.to_string()),
+            other => Err(AppError::bad_request(format!(
+                "action arguments must be strings, numbers or booleans, got {other}"
+            ))),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn to_argv_accepts_scalars_only() {
+        assert_eq!(
+            to_argv(&json!(["on", 3, 0.5, true])).unwrap(),
+            ["on", "3", "0.5", "true"]
+        );
+        assert!(to_argv(&json!({ "state": "on" })).is_err());
+        assert!(to_argv(&json!([["nested"]])).is_err());
+        assert!(to_argv(&json!([null])).is_err());
+    }
+}
@@ -0,0 +1,209 @@
+//! Bearer-/API-Key-Authentifizierung für die Steuer-Endpunkte.
+//!
+//! Tokens kommen aus `AuthConfig`; ohne Tokens bleibt die API offen (nur für
+//! Loopback-Binds gedacht). `/health` ist immer frei. SSE-Routen nehmen das
+//! Token auch als `access_token`-Query an, weil `EventSource` keine Header setzt.
+
+use std::borrow::Cow;
+
+use axum::extract::{Request, State};
+use axum::http::{header, Method};
+use axum::middleware::Next;
+use axum::response::Response;
+use subtle::ConstantTimeEq;
+
+use crate::config::{ApiToken, AuthConfig, Scope};
+use crate::error::AppError;
+use crate::AppState;
+
+pub async fn require(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    if let Some(scope) = required_scope(request.method(), request.uri().path()) {
+        authorize(&state.config().auth, &request, scope)?;
+    }
+    Ok(next.run(request).await)
+}
+
+fn authorize(auth: &AuthConfig, request: &Request, scope: Scope) -> Result<(), AppError> {
+    if !auth.enabled() {
+        return Ok(());
+    }
+    let presented = presented_token(request)
+        .ok_or_else(|| AppError::unauthorized("missing bearer token or X-API-Key"))?;
+    let token =
+        find_token(auth, &presented).ok_or_else(|| AppError::unauthorized("invalid token"))?;
+
+    if token.scopes.contains(&scope) {
+        Ok(())
+    } else {
+        Err(AppError::forbidden(format!(
+            "token lacks scope '{}'",
+            scope.as_str()
+        )))
+    }
+}
+
+/// Welcher Scope für eine Route nötig ist; `None` = öffentlich.
+/// Lesende Zugriffe brauchen `read`, alles Unbekannte fällt auf `rpc` zurück.
+#[must_use]
+pub fn required_scope(method: &Method, path: &str) -> Option<Scope> {
+    let section = path.trim_start_matches('/').split('/').next().unwrap_or("");
+    let read_only = method == Method::GET || method == Method::HEAD;
+
+    match section {
+        "health" => None,
+        "rpc" => Some(Scope::Rpc),
+        // Job-Ausgaben können Interna enthalten: auch Lesen nur mit `admin`.
+        "admin" | "jobs" => Some(Scope::Admin),
+        _ if read_only => Some(Scope::Read),
+        "playback" | "queue" | "playlists" | "discover" | "volume" | "mute" => {
+            Some(Scope::Playback)
+        }
+        "mode" => Some(Scope::Mode),
+        "recording" => Some(Scope::Recording),
+        "actions" => Some(Scope::Actions),
+        _ => Some(Scope::Rpc),
+    }
+}
+
+/// Bearer-Token, sonst `X-API-Key` (auch neben anderen `Authorization`-Schemata,
+/// etwa Basic von einem Reverse-Proxy), sonst `access_token` bei SSE-Routen.
+fn presented_token(request: &Request) -> Option<Cow<'_, str>> {
+    let headers = request.headers();
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split_once(' '))
+        .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("bearer"))
+        .map(|(_, token)| token.trim());
+    let api_key = || {
+        headers
+            .get("x-api-key")
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+    };
+    if let Some(token) = bearer.or_else(api_key) {
+        return Some(Cow::Borrowed(token));
+    }
+    if !is_event_stream(request.method(), request.uri().path()) {
+        return None;
+    }
+    url::form_urlencoded::parse(request.uri().query()?.as_bytes())
+        .find(|(name, _)| name == "access_token")
+        .map(|(_, token)| token)
+}
+
+/// SSE-Routen, die Browser per `EventSource` (ohne eigene Header) öffnen.
+fn is_event_stream(method: &Method, path: &str) -> bool {
+    let segments: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+    method == Method::GET && matches!(segments.as_slice(), ["events"] | ["jobs", _, "stream"])
+}
+
+fn find_token<'a>(auth: &'a AuthConfig, presented: &str) -> Option<&'a ApiToken> {
+    // Alle Tokens vergleichen, damit die Laufzeit nichts über Treffer verrät.
+    auth.tokens.iter().fold(None, |found, candidate| {
+        let matches: bool = candidate
+            .token
+            .as_bytes()
+            .ct_eq(presented.as_bytes())
+            .into();
+        found.or(matches.then_some(candidate))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn required_scope_maps_routes() {
+        assert_eq!(required_scope(&Method::GET, "/health"), None);
+        assert_eq!(required_scope(&Method::POST, "/rpc"), Some(Scope::Rpc));
+        assert_eq!(required_scope(&Method::GET, "/mode"), Some(Scope::Read));
+        assert_eq!(required_scope(&Method::POST, "/mode"), Some(Scope::Mode));
+        assert_eq!(
+            required_scope(&Method::DELETE, "/queue"),
+            Some(Scope::Playback)
+        );
+        assert_eq!(
+            required_scope(&Method::POST, "/recording/start"),
+            Some(Scope::Recording)
+        );
+        assert_eq!(
+            required_scope(&Method::POST, "/admin/reload"),
+            Some(Scope::Admin)
+        );
+        assert_eq!(required_scope(&Method::POST, "/jobs"), Some(Scope::Admin));
+        assert_eq!(
+            required_scope(&Method::GET, "/jobs/1/stream"),
+            Some(Scope::Admin)
+        );
+        assert_eq!(
+            required_scope(&Method::POST, "/actions/dac_power"),
+            Some(Scope::Actions)
+        );
+        assert_eq!(required_scope(&Method::GET, "/actions"), Some(Scope::Read));
+        assert_eq!(
+            required_scope(&Method::PUT, "/volume"),
+            Some(Scope::Playback)
+        );
+        assert_eq!(required_scope(&Method::GET, "/mute"), Some(Scope::Read));
+        assert_eq!(
+            required_scope(&Method::GET, "/library/browse"),
+            Some(Scope::Read)
+        );
+        assert_eq!(required_scope(&Method::POST, "/unknown"), Some(Scope::Rpc));
+    }
+
+    #[test]
+    fn presented_token_checks_headers_then_stream_query() {
+        let token = |method: Method, uri: &str, headers: &[(&str, &str)]| {
+            let mut builder = Request::builder().method(method).uri(uri);
+            for (name, value) in headers {
+                builder = builder.header(*name, *value);
+            }
+            let request = builder.body(axum::body::Body::empty()).unwrap();
+            presented_token(&request).map(Cow::into_owned)
+        };
+
+        assert_eq!(
+            token(Method::GET, "/mode", &[("authorization", "Bearer abc ")]),
+            Some("abc".into())
+        );
+        // Fremdes Schema (z. B. Basic vom Proxy): weiter mit X-API-Key.
+        assert_eq!(
+            token(
+                Method::GET,
+                "/mode",
+                &[
+                    ("authorization", "Basic dXNlcjpwdw=="),
+                    ("x-api-key", "key")
+                ]
+            ),
+            Some("key".into())
+        );
+        assert_eq!(
+            token(
+                Method::GET,
+                "/mode",
+                &[("authorization", "Basic dXNlcjpwdw==")]
+            ),
+            None
+        );
+        assert_eq!(
+            token(Method::GET, "/events?access_token=c2VjcmV0%3D%3D", &[]),
+            Some("c2VjcmV0==".into())
+        );
+        assert_eq!(
+            token(Method::GET, "/jobs/7/stream?access_token=abc", &[]),
+            Some("abc".into())
+        );
+        // Nur SSE-Routen akzeptieren das Token in der URL.
+        assert_eq!(token(Method::GET, "/mode?access_token=abc", &[]), None);
+        assert_eq!(token(Method::GET, "/jobs/7?access_token=abc", &[]), None);
+        assert_eq!(token(Method::POST, "/events?access_token=abc", &[]), None);
+    }
+}
@@ -0,0 +1,285 @@
+//! Client-Unterbefehle der `hauski-backend`-Binary.
+//!
+//! Mit `--url`/`HAUSKI_URL` gehen die Aufrufe an einen laufenden Dienst,
+//! sonst in-process durch den Router. `--json` gibt die API-Antwort unverändert aus.
+
+use clap::Subcommand;
+use hauski_backend::client::{BackendClient, ClientResponse};
+use hauski_backend::error::AppError;
+use hauski_backend::{AudioMode, StopSignal};
+use serde_json::{json, Value};
+use url::form_urlencoded;
+
+#[derive(Subcommand)]
+pub enum ClientCommand {
+    /// Backend- und Mopidy-Status.
+    Health,
+    /// Audio-Modus anzeigen oder wechseln.
+    #[command(subcommand)]
+    Mode(ModeCommand),
+    /// Wiedergabe starten; mit URIs werden diese vorher eingereiht.
+    Play {
+        uris: Vec<String>,
+        /// Tracklist-ID aus der Queue.
+        #[arg(long, conflicts_with = "uris")]
+        tlid: Option<u64>,
+    },
+    /// Queue bearbeiten.
+    #[command(subcommand)]
+    Queue(QueueCommand),
+    /// Ähnliche Titel zu einer Track-URI.
+    Similar {
+        uri: String,
+        #[arg(long)]
+        limit: Option<usize>,
+        /// Kommagetrennte Strategien (`artist,album,genre,year,co_artist,query`).
+        #[arg(long)]
+        strategy: Option<String>,
+        /// Live-/Remaster-/Karaoke-Fassungen behalten.
+        #[arg(long)]
+        variants: bool,
+    },
+    /// Aufnahme steuern.
+    #[command(subcommand)]
+    Rec(RecCommand),
+}
+
+#[derive(Subcommand)]
+pub enum ModeCommand {
+    Show,
+    Set {
+        #[arg(value_enum)]
+        mode: AudioMode,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum QueueCommand {
+    Add {
+        #[arg(required = true)]
+        uris: Vec<String>,
+        /// Einfügeposition (Default: ans Ende).
+        #[arg(long)]
+        at: Option<u64>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum RecCommand {
+    Start {
+        #[arg(long)]
+        rate: Option<u32>,
+        #[arg(long)]
+        channels: Option<u16>,
+        #[arg(long)]
+        format: Option<String>,
+        #[arg(long)]
+        device: Option<String>,
+        /// Zieldatei (Default: Zeitstempel im Aufnahmeverzeichnis).
+        #[arg(long)]
+        output: Option<String>,
+    },
+    Stop {
+        /// Erstes Signal; Groß-/Kleinschreibung egal.
+        #[arg(long, value_enum, ignore_case = true)]
+        signal: Option<StopSignal>,
+        /// Wartezeit in Sekunden pro Eskalationsstufe.
+        #[arg(long)]
+        timeout: Option<f64>,
+    },
+    Status,
+}
+
+/// Führt den Befehl aus; Antworten außerhalb von 2xx werden zum Fehler (Exit-Code 1).
+pub async fn run(
+    client: &BackendClient,
+    command: ClientCommand,
+    json_output: bool,
+) -> Result<(), AppError> {
+    let response = match command {
+        ClientCommand::Health => client.get("/health").await?,
+        ClientCommand::Mode(ModeCommand::Show) => client.get("/mode").await?,
+        ClientCommand::Mode(ModeCommand::Set { mode }) => {
+            client.post("/mode", json!({ "mode": mode })).await?
+        }
+        ClientCommand::Play { uris, tlid } => {
+            let tlid = if uris.is_empty() {
+                tlid
+            } else {
+                let added = expect_success(client.post("/queue", json!({ "uris": uris })).await?)?;
+                // Ohne tlid würde `/playback/play` den aktuellen statt des neuen Tracks spielen.
+                let tlid = added["added"][0]["tlid"].as_u64().ok_or_else(|| {
+                    AppError::upstream("queue add returned no tlid; not starting playback")
+                })?;
+                Some(tlid)
+            };
+            let body = tlid.map_or_else(|| json!({}), |tlid| json!({ "tlid": tlid }));
+            client.post("/playback/play", body).await?
+        }
+        ClientCommand::Queue(QueueCommand::Add { uris, at }) => {
+            let mut body = json!({ "uris": uris });
+            if let Some(at) = at {
+                body["at_position"] = json!(at);
+            }
+            client.post("/queue", body).await?
+        }
+        ClientCommand::Similar {
+            uri,
+            limit,
+            strategy,
+            variants,
+        } => {
+            let mut query = form_urlencoded::Serializer::new(String::new());
+            query.append_pair("seed", &uri);
+            if let Some(limit) = limit {
+                query.append_pair("limit", &limit.to_string());
+            }
+            if let Some(strategy) = &strategy {
+                query.append_pair("strategy", strategy);
+            }
+            if variants {
+                query.append_pair("variants", "true");
+            }
+            client
+                .get(&format!("/discover/similar?{}", query.finish()))
+                .await?
+        }
+        ClientCommand::Rec(RecCommand::Start {
+            rate,
+            channels,
+            format,
+            device,
+            output,
+        }) => {
+            let body = json!({
+                "rate": rate,
+                "channels": channels,
+                "format": format,
+                "device": device,
+                "output": output,
+            });
+            client.post("/recording/start", body).await?
+        }
+        ClientCommand::Rec(RecCommand::Stop { signal, timeout }) => {
+            let body = json!({
+                "signal": signal,
+                "timeout": timeout,
+            });
+            client.post("/recording/stop", body).await?
+        }
+        ClientCommand::Rec(RecCommand::Status) => client.get("/recording").await?,
+    };
+
+    if json_output {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&response.body).unwrap_or_default()
+        );
+        return expect_success(response).map(drop);
+    }
+    let body = expect_success(response)?;
+    print!("{}", render(&body));
+    Ok(())
+}
+
+fn expect_success(response: ClientResponse) -> Result<Value, AppError> {
+    if response.is_success() {
+        Ok(response.body)
+    } else {
+        Err(AppError::upstream(format!(
+            "{}: {}",
+            response.status,
+            response.error_message()
+        )))
+    }
+}
+
+/// Kurzform für Menschen; erkennt die Antwort an ihren Feldern.
+fn render(body: &Value) -> String {
+    let text = |value: &Value| value.as_str().unwrap_or("-").to_string();
+
+    if let Some(tracks) = body.get("tracks").and_then(Value::as_array) {
+        return tracks
+            .iter()
+            .map(|track| {
+                format!(
+                    "{:>5.2}  {}\t{}\n",
+                    track["score"].as_f64().unwrap_or_default(),
+                    track_label(track),
+                    text(&track["uri"])
+                )
+            })
+            .collect();
+    }
+    if let Some(added) = body.get("added").and_then(Value::as_array) {
+        return added
+            .iter()
+            .map(|entry| {
+                format!(
+                    "{}\t{}\t{}\n",
+                    entry["tlid"],
+                    track_label(&entry["track"]),
+                    text(&entry["track"]["uri"])
+                )
+            })
+            .collect();
+    }
+    if body.get("requested_mode").is_some() {
+        let mut line = format!("{} ({})", text(&body["mode"]), text(&body["output"]));
+        if body["rolled_back"].as_bool() == Some(true) {
+            line.push_str(", rolled back");
+        }
+        return format!("{line}\n");
+    }
+    if body.get("value").is_some() {
+        return format!("{}\t{}\n", text(&body["mode"]), text(&body["value"]));
+    }
+    if let Some(running) = body.get("running").and_then(Value::as_bool) {
+        return if running {
+            format!(
+                "recording pid {} → {}\n",
+                body["pid"],
+                text(&body["output"])
+            )
+        } else {
+            "not recording\n".into()
+        };
+    }
+    if body.get("killed").is_some() {
+        return format!("stopped pid {} ({})\n", body["pid"], text(&body["signal"]));
+    }
+    if body.get("pid").is_some() {
+        return format!(
+            "recording pid {} → {}\n",
+            body["pid"],
+            text(&body["output"])
+        );
+    }
+    if let Some(status) = body.get("status").and_then(Value::as_str) {
+        let mut line = format!("{status} (version {})", text(&body["version"]));
+        if let Some(mopidy) = body.get("mopidy") {
+            line.push_str(&format!(", mopidy {}", text(&mopidy["status"])));
+            if let Some(detail) = mopidy.get("detail").and_then(Value::as_str) {
+                line.push_str(&format!(": {detail}"));
+            }
+        }
+        return format!("{line}\n");
+    }
+    match body {
+        Value::Null => "ok\n".into(),
+        other => format!("{other}\n"),
+    }
+}
+
+fn track_label(track: &Value) -> String {
+    let artists: Vec<&str> = track["artists"]
+        .as_array()
+        .map(|artists| artists.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let name = track["name"].as_str().unwrap_or("?");
+    if artists.is_empty() {
+        name.to_string()
+    } else {
+        format!("{} – {name}", artists.join(", "))
+    }
+}
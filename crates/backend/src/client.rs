@@ -0,0 +1,173 @@
+//! Client für die eigene HTTP-API: gegen einen laufenden Dienst (`http`) oder
+//! ohne Server direkt durch den Router (`in_process`). Grundlage der
+//! CLI-Unterbefehle in `main.rs`.
+
+use std::time::Duration;
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use axum::Router;
+use serde_json::Value;
+use tower::ServiceExt;
+use url::Url;
+
+use crate::config::{AppConfig, AuthConfig};
+use crate::error::AppError;
+
+/// Obergrenze für in-process gelesene Antworten.
+const MAX_BODY: usize = 16 * 1024 * 1024;
+/// Verbindungsaufbau zum Dienst.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+/// Gesamte Anfrage; großzügig, weil z. B. `POST /mode` auf Skripte wartet.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+pub struct BackendClient {
+    transport: Transport,
+}
+
+enum Transport {
+    Http {
+        client: reqwest::Client,
+        base: Url,
+        token: Option<String>,
+    },
+    Router(Router),
+}
+
+/// Statuscode und JSON-Body (`Null` bei leerer Antwort, z. B. `204`).
+#[derive(Debug)]
+pub struct ClientResponse {
+    pub status: StatusCode,
+    pub body: Value,
+}
+
+impl ClientResponse {
+    #[must_use]
+    pub fn is_success(&self) -> bool {
+        self.status.is_success()
+    }
+
+    /// Fehlermeldung aus `{"error": …}` oder der Statuszeile.
+    #[must_use]
+    pub fn error_message(&self) -> String {
+        self.body
+            .get("error")
+            .and_then(Value::as_str)
+            .map_or_else(|| self.status.to_string(), String::from)
+    }
+}
+
+impl BackendClient {
+    /// Gegen einen laufenden Dienst, optional mit Bearer-Token.
+    #[must_use]
+    pub fn http(base: Url, token: Option<String>) -> Self {
+        let client = reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .expect("HTTP client can be built");
+        Self {
+            transport: Transport::Http {
+                client,
+                base,
+                token,
+            },
+        }
+    }
+
+    /// Ohne Server: Wer die lokale Konfiguration lesen kann, darf ohnehin alles,
+    /// daher entfällt hier die Token-Prüfung.
+    #[must_use]
+    pub fn in_process(mut config: AppConfig) -> Self {
+        config.auth = AuthConfig::default();
+        Self::from_router(crate::build_router(config))
+    }
+
+    #[must_use]
+    pub fn from_router(router: Router) -> Self {
+        Self {
+            transport: Transport::Router(router),
+        }
+    }
+
+    pub async fn get(&self, path: &str) -> Result<ClientResponse, AppError> {
+        self.send(Method::GET, path, None).await
+    }
+
+    pub async fn post(&self, path: &str, body: Value) -> Result<ClientResponse, AppError> {
+        self.send(Method::POST, path, Some(body)).await
+    }
+
+    /// `path` inklusive Query (`/discover/similar?seed=…`).
+    pub async fn send(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> Result<ClientResponse, AppError> {
+        match &self.transport {
+            Transport::Http {
+                client,
+                base,
+                token,
+            } => {
+                let url = base
+                    .join(path)
+                    .map_err(|err| AppError::bad_request(format!("invalid path {path}: {err}")))?;
+                let unreachable = |err: reqwest::Error| {
+                    AppError::upstream(format!("backend request failed: {err}"))
+                };
+                let mut request = client.request(method, url);
+                if let Some(token) = token {
+                    request = request.bearer_auth(token);
+                }
+                if let Some(body) = body {
+                    request = request.json(&body);
+                }
+                let response = request.send().await.map_err(unreachable)?;
+                let status = StatusCode::from_u16(response.status().as_u16())
+                    .map_err(|err| AppError::upstream(err.to_string()))?;
+                let bytes = response.bytes().await.map_err(unreachable)?;
+                Ok(ClientResponse {
+                    status,
+                    body: parse_body(&bytes),
+                })
+            }
+            Transport::Router(router) => {
+                let mut request = Request::builder().method(method).uri(path);
+                let body = match body {
+                    Some(body) => {
+                        request = request.header(header::CONTENT_TYPE, "application/json");
+                        Body::from(body.to_string())
+                    }
+                    None => Body::empty(),
+                };
+                let request = request
+                    .body(body)
+                    .map_err(|err| AppError::bad_request(format!("invalid request: {err}")))?;
+                let response = router
+                    .clone()
+                    .oneshot(request)
+                    .await
+                    .unwrap_or_else(|never| match never {});
+                let status = response.status();
+                let bytes = axum::body::to_bytes(response.into_body(), MAX_BODY)
+                    .await
+                    .map_err(|err| AppError::internal(format!("failed to read response: {err}")))?;
+                Ok(ClientResponse {
+                    status,
+                    body: parse_body(&bytes),
+                })
+            }
+        }
+    }
+}
+
+/// JSON, sonst Text (z. B. `/metrics`); leer → `Null`.
+fn parse_body(bytes: &[u8]) -> Value {
+    if bytes.is_empty() {
+        return Value::Null;
+    }
+    serde_json::from_slice(bytes)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(bytes).into_owned()))
+}
@@ -1,11 +1,21 @@
+mod layers;
+
+pub use layers::{ConfigEntry, ConfigSource, ConfigSources};
+
+use layers::{ConfigFile, Layers};
+
 use crate::scripts::constants::{
-    DEFAULT_AUDIO_MODE_CMD, DEFAULT_PLAYLIST_CMD, DEFAULT_REC_START_CMD, DEFAULT_REC_STOP_CMD,
+    DEFAULT_AUDIO_MODE_CMD, DEFAULT_HW_MIXER_CMD, DEFAULT_PLAYLIST_CMD,
 };
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use std::collections::{BTreeMap, BTreeSet};
 use std::env;
+use std::fmt;
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 use thiserror::Error;
+use toml::Value;
 use url::Url;
 
 #[derive(Debug, Clone)]
@@ -14,19 +24,70 @@ pub struct AppConfig {
     pub mopidy_rpc_url: Url,
     pub audio_mode_script: ScriptConfig,
     pub playlist_script: ScriptConfig,
-    pub rec_start_script: ScriptConfig,
-    pub rec_stop_script: ScriptConfig,
     pub script_workdir: PathBuf,
     pub command_timeout: Duration,
+    /// Obergrenze für asynchrone Jobs (`/jobs`).
+    pub job_timeout: Duration,
     pub check_mopidy_health: bool,
+    /// Timeouts, Retries und Circuit Breaker des Mopidy-Clients.
+    pub mopidy: MopidyConfig,
+    pub recorder: RecorderConfig,
+    pub mixer: MixerConfig,
+    pub auth: AuthConfig,
+    pub rpc: RpcPolicy,
+    /// Eigene Aktionen aus `[actions.<name>]` (`/actions`).
+    pub actions: BTreeMap<String, ActionConfig>,
+    /// Herkunft der effektiven Werte (für `config show`).
+    pub sources: ConfigSources,
 }
 
-#[derive(Debug, Clone)]
+/// Ein Skript samt Optionen aus `[scripts.<name>]`.
+#[derive(Debug, Clone, Default)]
 pub struct ScriptConfig {
     pub program: PathBuf,
+    /// Feste Argumente vor den Argumenten des Aufrufs.
+    pub args: Vec<String>,
+    /// Zusätzliche Umgebungsvariablen.
+    pub env: BTreeMap<String, String>,
+    /// Arbeitsverzeichnis statt `script_workdir` (relativ dazu aufgelöst).
+    pub workdir: Option<PathBuf>,
+    /// Überschreibt `command_timeout` bzw. `job_timeout`.
+    pub timeout: Option<Duration>,
+    /// Höchstzahl gleichzeitiger Läufe (je Programmpfad); `None` = unbegrenzt.
+    pub max_concurrency: Option<usize>,
+    pub on_busy: OnBusy,
+}
+
+/// Verhalten, wenn `max_concurrency` erreicht ist.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnBusy {
+    /// Warten, bis ein Lauf endet (zählt zum Timeout).
+    #[default]
+    Queue,
+    /// Sofort mit `409` ablehnen.
+    Reject,
+}
+
+impl OnBusy {
+    #[must_use]
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "queue" => Some(OnBusy::Queue),
+            "reject" => Some(OnBusy::Reject),
+            _ => None,
+        }
+    }
 }
 
 impl ScriptConfig {
+    #[must_use]
+    pub fn new(program: impl Into<PathBuf>) -> Self {
+        Self {
+            program: program.into(),
+            ..Self::default()
+        }
+    }
+
     #[must_use]
     pub fn resolve_with(&self, base: &Path) -> PathBuf {
         if self.program.is_absolute() {
@@ -35,6 +96,353 @@ impl ScriptConfig {
             base.join(&self.program)
         }
     }
+
+    #[must_use]
+    pub fn workdir_with(&self, base: &Path) -> PathBuf {
+        self.workdir
+            .as_ref()
+            .map_or_else(|| base.to_path_buf(), |dir| base.join(dir))
+    }
+}
+
+/// Frei konfigurierte Aktion: Programm plus JSON-Schema für die Argumentliste.
+#[derive(Debug, Clone)]
+pub struct ActionConfig {
+    pub script: ScriptConfig,
+    pub description: Option<String>,
+    pub schema: serde_json::Value,
+    pub validator: jsonschema::Validator,
+}
+
+impl ActionConfig {
+    /// Ohne Schema sind keine Argumente erlaubt.
+    #[must_use]
+    pub fn default_schema() -> serde_json::Value {
+        serde_json::json!({ "type": "array", "maxItems": 0 })
+    }
+
+    /// Aktion mit kompiliertem Schema.
+    pub fn new(
+        script: ScriptConfig,
+        description: Option<String>,
+        schema: serde_json::Value,
+    ) -> Result<Self, String> {
+        let validator = jsonschema::validator_for(&schema).map_err(|err| err.to_string())?;
+        Ok(Self {
+            script,
+            description,
+            schema,
+            validator,
+        })
+    }
+}
+
+/// Einstellungen für den nativen Recorder-Supervisor (`crate::recording`).
+#[derive(Debug, Clone)]
+pub struct RecorderConfig {
+    /// Capture-Binary, Default `pw-record` (über `PATH` aufgelöst).
+    pub binary: PathBuf,
+    pub record_dir: PathBuf,
+    pub extension: String,
+    /// Ablage für PID-Datei, Metadaten und Recorder-Log (kompatibel zu `rec-stop`).
+    pub state_dir: PathBuf,
+}
+
+/// Verhalten des HTTP-Clients gegenüber Mopidy (`[mopidy]`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MopidyConfig {
+    pub connect_timeout: Duration,
+    /// Obergrenze je Versuch, inklusive Antwort.
+    pub request_timeout: Duration,
+    /// Zusätzliche Versuche für lesende (idempotente) Methoden.
+    pub retries: u32,
+    /// Basis des exponentiellen Backoffs (mit Jitter).
+    pub retry_backoff: Duration,
+    /// Fehlschläge in Folge, ab denen der Breaker öffnet; `0` = aus.
+    pub breaker_threshold: u32,
+    /// Wie lange der offene Breaker Aufrufe sofort ablehnt.
+    pub breaker_cooldown: Duration,
+}
+
+impl Default for MopidyConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(2),
+            request_timeout: Duration::from_secs(10),
+            retries: 2,
+            retry_backoff: Duration::from_millis(100),
+            breaker_threshold: 5,
+            breaker_cooldown: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Lautstärke über Mopidy (`core.mixer.*`) oder das Hardware-Mixer-Skript.
+#[derive(Debug, Clone)]
+pub struct MixerConfig {
+    pub mode: MixerMode,
+    /// `hw-mixer`-Protokoll: `get`, `set-db <dB>`, `mute on|off`; Ausgabe
+    /// `{"db": …, "mute": …}` als abschließendes JSON-Objekt.
+    pub script: ScriptConfig,
+    pub curve: VolumeCurve,
+    /// dB-Wert für 0 %.
+    pub min_db: f64,
+    /// dB-Wert für 100 %.
+    pub max_db: f64,
+    /// Schrittweite in Prozent für `{"step": "up"|"down"}`.
+    pub step: u8,
+}
+
+impl Default for MixerConfig {
+    fn default() -> Self {
+        Self {
+            mode: MixerMode::Mopidy,
+            script: ScriptConfig::new(DEFAULT_HW_MIXER_CMD),
+            curve: VolumeCurve::Cubic,
+            min_db: -60.0,
+            max_db: 0.0,
+            step: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MixerMode {
+    /// Immer Mopidys Software-Mixer.
+    Mopidy,
+    /// Immer das Hardware-Mixer-Skript.
+    Hardware,
+    /// Im ALSA-Modus (bitperfect, Mopidy-Mixer aus) Hardware, sonst Mopidy.
+    Auto,
+}
+
+impl MixerMode {
+    #[must_use]
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "mopidy" => Some(MixerMode::Mopidy),
+            "hardware" => Some(MixerMode::Hardware),
+            "auto" => Some(MixerMode::Auto),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MixerMode::Mopidy => "mopidy",
+            MixerMode::Hardware => "hardware",
+            MixerMode::Auto => "auto",
+        }
+    }
+}
+
+/// Abbildung Prozent ↔ dB zwischen `min_db` und `max_db`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VolumeCurve {
+    /// Gleichmäßig in dB.
+    Linear,
+    /// Wie alsamixer: Amplitude ∝ (Prozent)³, auf `min_db`…`max_db` normiert.
+    Cubic,
+}
+
+impl VolumeCurve {
+    #[must_use]
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "linear" => Some(VolumeCurve::Linear),
+            "cubic" => Some(VolumeCurve::Cubic),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VolumeCurve::Linear => "linear",
+            VolumeCurve::Cubic => "cubic",
+        }
+    }
+}
+
+/// API-Tokens für die Auth-Middleware (`crate::auth`); leer = Auth deaktiviert.
+#[derive(Debug, Clone, Default)]
+pub struct AuthConfig {
+    pub tokens: Vec<ApiToken>,
+}
+
+impl AuthConfig {
+    #[must_use]
+    pub fn enabled(&self) -> bool {
+        !self.tokens.is_empty()
+    }
+}
+
+#[derive(Clone)]
+pub struct ApiToken {
+    pub token: String,
+    pub scopes: BTreeSet<Scope>,
+}
+
+impl fmt::Debug for ApiToken {
+    // Token nie im Klartext loggen
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApiToken")
+            .field("token", &"***")
+            .field("scopes", &self.scopes)
+            .finish()
+    }
+}
+
+impl ApiToken {
+    /// Parst `<token>` (alle Scopes) oder `<token>=<scope>,<scope>`; `*` steht für alle Scopes.
+    pub fn parse(raw: &str) -> Result<Self, ConfigError> {
+        let (token, scopes) = Self::split(raw);
+        let token = token.trim();
+        if token.is_empty() {
+            return Err(ConfigError::InvalidApiToken("empty token".into()));
+        }
+
+        let scopes = match scopes {
+            None => Scope::ALL.into_iter().collect(),
+            Some(list) => {
+                let mut scopes = BTreeSet::new();
+                for name in list.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+                    if name == "*" {
+                        scopes.extend(Scope::ALL);
+                    } else {
+                        scopes.insert(Scope::parse(name).ok_or_else(|| {
+                            ConfigError::InvalidApiToken(format!("unknown scope '{name}'"))
+                        })?);
+                    }
+                }
+                if scopes.is_empty() {
+                    return Err(ConfigError::InvalidApiToken("token without scopes".into()));
+                }
+                scopes
+            }
+        };
+
+        Ok(Self {
+            token: token.into(),
+            scopes,
+        })
+    }
+
+    /// Trennt am letzten `=`, aber nur, wenn dahinter mindestens ein bekannter
+    /// Scope steht; so bleiben Base64-Tokens mit Padding (`abc==`) ganz.
+    fn split(raw: &str) -> (&str, Option<&str>) {
+        match raw.rsplit_once('=') {
+            Some((token, scopes))
+                if scopes
+                    .split(',')
+                    .any(|name| name.trim() == "*" || Scope::parse(name).is_some()) =>
+            {
+                (token, Some(scopes))
+            }
+            _ => (raw, None),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Scope {
+    Read,
+    Playback,
+    Mode,
+    Recording,
+    Rpc,
+    Admin,
+    Actions,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 7] = [
+        Scope::Read,
+        Scope::Playback,
+        Scope::Mode,
+        Scope::Recording,
+        Scope::Rpc,
+        Scope::Admin,
+        Scope::Actions,
+    ];
+
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::Read => "read",
+            Scope::Playback => "playback",
+            Scope::Mode => "mode",
+            Scope::Recording => "recording",
+            Scope::Rpc => "rpc",
+            Scope::Admin => "admin",
+            Scope::Actions => "actions",
+        }
+    }
+
+    #[must_use]
+    pub fn parse(raw: &str) -> Option<Self> {
+        Scope::ALL
+            .into_iter()
+            .find(|scope| scope.as_str().eq_ignore_ascii_case(raw.trim()))
+    }
+}
+
+/// Erlaubte bzw. gesperrte Mopidy-Methoden für `/rpc` (Glob-Muster, Deny gewinnt).
+#[derive(Debug, Clone)]
+pub struct RpcPolicy {
+    pub allow: Vec<String>,
+    pub deny: Vec<String>,
+    allow_set: GlobSet,
+    deny_set: GlobSet,
+}
+
+impl RpcPolicy {
+    pub const DEFAULT_ALLOW: &'static [&'static str] = &["core.*"];
+    /// Zerstörende bzw. teure Methoden; die Queue leert `DELETE /queue` mit eigenem Scope.
+    pub const DEFAULT_DENY: &'static [&'static str] = &[
+        "core.library.refresh",
+        "core.playlists.refresh",
+        "core.playlists.delete",
+        "core.tracklist.clear",
+    ];
+
+    pub fn new(allow: Vec<String>, deny: Vec<String>) -> Result<Self, ConfigError> {
+        Ok(Self {
+            allow_set: build_globset(&allow)?,
+            deny_set: build_globset(&deny)?,
+            allow,
+            deny,
+        })
+    }
+
+    #[must_use]
+    pub fn permits(&self, method: &str) -> bool {
+        !self.deny_set.is_match(method) && self.allow_set.is_match(method)
+    }
+}
+
+impl Default for RpcPolicy {
+    fn default() -> Self {
+        let to_vec = |patterns: &[&str]| patterns.iter().map(|p| (*p).to_string()).collect();
+        Self::new(to_vec(Self::DEFAULT_ALLOW), to_vec(Self::DEFAULT_DENY))
+            .expect("default RPC patterns are valid")
+    }
+}
+
+fn build_globset(patterns: &[String]) -> Result<GlobSet, ConfigError> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        // `.` ist kein Pfadtrenner: `core.*` soll `core.playback.play` treffen.
+        let glob = GlobBuilder::new(pattern)
+            .literal_separator(false)
+            .build()
+            .map_err(|err| ConfigError::InvalidRpcPattern(format!("{pattern}: {err}")))?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|err| ConfigError::InvalidRpcPattern(err.to_string()))
 }
 
 #[derive(Debug, Error)]
@@ -45,6 +453,40 @@ pub enum ConfigError {
     InvalidMopidyUrl(String),
     #[error("failed to determine working directory: {0}")]
     WorkingDirectory(std::io::Error),
+    #[error("invalid API token: {0}")]
+    InvalidApiToken(String),
+    #[error("invalid RPC method pattern {0}")]
+    InvalidRpcPattern(String),
+    #[error("failed to read token file {path}: {source}")]
+    TokenFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to read config file {path}: {source}")]
+    File {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("invalid TOML in {path}: {message}")]
+    Syntax { path: PathBuf, message: String },
+    #[error("unknown key '{key}' in {path}")]
+    UnknownKey { key: String, path: PathBuf },
+    #[error("invalid value for {key} ({origin}): {reason}")]
+    InvalidValue {
+        key: String,
+        origin: ConfigSource,
+        reason: String,
+    },
+    #[error("{} configuration errors: {}", .0.len(), join_errors(.0))]
+    Multiple(Vec<ConfigError>),
+}
+
+fn join_errors(errors: &[ConfigError]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
 }
 
 impl AppConfig {
@@ -52,10 +494,25 @@ impl AppConfig {
     const DEFAULT_MOPIDY_RPC: &'static str = "http://127.0.0.1:6680/mopidy/rpc";
     /// Standard-Timeout in Millisekunden (klar benannt, keine versteckte Umrechnung)
     const DEFAULT_COMMAND_TIMEOUT_MS: u64 = 10_000;
+    /// Jobs (`/jobs`) dürfen deutlich länger laufen als synchrone Aufrufe.
+    const DEFAULT_JOB_TIMEOUT_MS: u64 = 600_000;
+    const DEFAULT_REC_BINARY: &'static str = "pw-record";
+    const DEFAULT_RECORD_DIR: &'static str = "~/Music/Recordings";
+    const DEFAULT_RECORD_EXT: &'static str = "wav";
+    const DEFAULT_STATE_DIR: &'static str = "~/.cache/hauski-audio";
 
     pub fn from_env() -> Result<Self, ConfigError> {
+        Self::load(None)
+    }
+
+    /// Lädt die Konfiguration; ohne `path` wird `HAUSKI_CONFIG` berücksichtigt.
+    /// Umgebungsvariablen überschreiben Werte aus der Datei.
+    pub fn load(path: Option<&Path>) -> Result<Self, ConfigError> {
         let get_env = |key: &str| env::var(key).ok();
-        Self::from_source(&get_env, env::current_dir)
+        match path {
+            Some(path) => Self::from_layers(&get_env, env::current_dir, Some(path)),
+            None => Self::from_source(&get_env, env::current_dir),
+        }
     }
 
     fn from_source<F, G>(get_env: &F, get_cwd: G) -> Result<Self, ConfigError>
@@ -63,72 +520,184 @@ impl AppConfig {
         F: Fn(&str) -> Option<String>,
         G: Fn() -> Result<PathBuf, std::io::Error>,
     {
-        let bind_raw = get_env("HAUSKI_BACKEND_BIND")
-            .or_else(|| get_env("HAUSKI_BIND"))
-            .unwrap_or_else(|| Self::DEFAULT_BIND.into());
-        let bind_addr: SocketAddr = bind_raw
-            .parse()
-            .map_err(|_| ConfigError::InvalidBindAddress(bind_raw.clone()))?;
-
-        let mopidy_rpc_url = resolve_mopidy_rpc_url(get_env)?;
-
-        let workdir = match get_env("HAUSKI_SCRIPT_WORKDIR") {
-            Some(value) => PathBuf::from(value),
-            None => get_cwd().map_err(ConfigError::WorkingDirectory)?,
-        };
+        let path = get_env("HAUSKI_CONFIG")
+            .filter(|path| !path.trim().is_empty())
+            .map(PathBuf::from);
+        Self::from_layers(get_env, get_cwd, path.as_deref())
+    }
 
-        let audio_mode_script = ScriptConfig {
-            program: PathBuf::from(
-                get_env("HAUSKI_AUDIO_MODE_CMD").unwrap_or_else(|| DEFAULT_AUDIO_MODE_CMD.into()),
-            ),
+    fn from_layers<F, G>(get_env: &F, get_cwd: G, path: Option<&Path>) -> Result<Self, ConfigError>
+    where
+        F: Fn(&str) -> Option<String>,
+        G: Fn() -> Result<PathBuf, std::io::Error>,
+    {
+        let file = path.map(ConfigFile::load).transpose()?;
+        let mut layers = Layers::new(get_env, file);
+
+        let bind_addr = layers.parse(
+            "bind",
+            Self::DEFAULT_BIND,
+            |raw| {
+                raw.parse::<SocketAddr>()
+                    .map_err(|_| ConfigError::InvalidBindAddress(raw.into()))
+            },
+            |addr| Value::String(addr.to_string()),
+        );
+
+        let mopidy_rpc_url = resolve_mopidy_rpc_url(&mut layers);
+
+        let script_workdir = match layers.raw("script_workdir") {
+            Some((raw, source)) => {
+                layers.record("script_workdir", raw.clone(), source);
+                PathBuf::from(raw)
+            }
+            None => {
+                let cwd = get_cwd().map_err(ConfigError::WorkingDirectory)?;
+                layers.record(
+                    "script_workdir",
+                    cwd.display().to_string(),
+                    ConfigSource::Default,
+                );
+                cwd
+            }
         };
 
-        let playlist_script = ScriptConfig {
-            program: PathBuf::from(
-                get_env("HAUSKI_PLAYLIST_FROM_LIST_CMD")
-                    .or_else(|| get_env("HAUSKI_PLAYLIST_CMD"))
-                    .unwrap_or_else(|| DEFAULT_PLAYLIST_CMD.into()),
-            ),
+        let mut millis = |key: &str, default: u64| {
+            let ms = layers.parse(
+                key,
+                &default.to_string(),
+                |raw| {
+                    raw.trim()
+                        .parse::<u64>()
+                        .map_err(|err| invalid_value(key, raw, err))
+                },
+                |ms| Value::Integer(i64::try_from(*ms).unwrap_or(i64::MAX)),
+            );
+            Duration::from_millis(ms)
         };
+        let command_timeout = millis("command_timeout_ms", Self::DEFAULT_COMMAND_TIMEOUT_MS);
+        let job_timeout = millis("job_timeout_ms", Self::DEFAULT_JOB_TIMEOUT_MS);
 
-        let rec_start_script = ScriptConfig {
-            program: PathBuf::from(
-                get_env("HAUSKI_REC_START_CMD").unwrap_or_else(|| DEFAULT_REC_START_CMD.into()),
-            ),
+        let check_mopidy_health = layers.parse(
+            "check_mopidy_health",
+            "true",
+            |raw| {
+                parse_bool(raw)
+                    .ok_or_else(|| invalid_value("check_mopidy_health", raw, "expected true/false"))
+            },
+            |flag| Value::Boolean(*flag),
+        );
+
+        let mopidy = load_mopidy(&mut layers);
+
+        let home = get_env("HOME").map(PathBuf::from);
+        let mut script = |name: &str, default: &str| {
+            load_script(&mut layers, name, default, home.as_deref(), &script_workdir)
         };
+        let audio_mode_script = script("audio_mode", DEFAULT_AUDIO_MODE_CMD);
+        let playlist_script = script("playlist_from_list", DEFAULT_PLAYLIST_CMD);
 
-        let rec_stop_script = ScriptConfig {
-            program: PathBuf::from(
-                get_env("HAUSKI_REC_STOP_CMD").unwrap_or_else(|| DEFAULT_REC_STOP_CMD.into()),
+        let path_setting = |layers: &mut Layers<'_, F>, key: &str, default: &str, expand: bool| {
+            layers.parse(
+                key,
+                default,
+                |raw| {
+                    Ok(if expand {
+                        expand_home(raw, home.as_deref(), &script_workdir)
+                    } else {
+                        PathBuf::from(raw)
+                    })
+                },
+                |path| Value::String(path.display().to_string()),
+            )
+        };
+        let recorder = RecorderConfig {
+            binary: path_setting(
+                &mut layers,
+                "recorder.binary",
+                Self::DEFAULT_REC_BINARY,
+                false,
+            ),
+            record_dir: path_setting(
+                &mut layers,
+                "recorder.record_dir",
+                Self::DEFAULT_RECORD_DIR,
+                true,
+            ),
+            extension: layers.parse(
+                "recorder.extension",
+                Self::DEFAULT_RECORD_EXT,
+                |raw| Ok(raw.to_string()),
+                |ext| Value::String(ext.clone()),
+            ),
+            state_dir: path_setting(
+                &mut layers,
+                "recorder.state_dir",
+                Self::DEFAULT_STATE_DIR,
+                true,
             ),
         };
 
-        let timeout_ms = get_env("HAUSKI_COMMAND_TIMEOUT_MS")
-            .and_then(|raw| raw.parse().ok())
-            .unwrap_or(Self::DEFAULT_COMMAND_TIMEOUT_MS);
+        let mixer = load_mixer(&mut layers, home.as_deref(), &script_workdir);
+        let actions = load_actions(&mut layers, home.as_deref(), &script_workdir);
+        let auth = load_auth(&mut layers);
+        let rpc = load_rpc_policy(&mut layers);
 
-        let check_mopidy_health = env_bool_source("HAUSKI_CHECK_MOPIDY_HEALTH", true, get_env);
+        let sources = layers.finish()?;
 
         Ok(Self {
             bind_addr,
             mopidy_rpc_url,
             audio_mode_script,
             playlist_script,
-            rec_start_script,
-            rec_stop_script,
-            script_workdir: workdir,
-            command_timeout: Duration::from_millis(timeout_ms),
+            script_workdir,
+            command_timeout,
+            job_timeout,
             check_mopidy_health,
+            mopidy,
+            recorder,
+            mixer,
+            auth,
+            rpc,
+            actions,
+            sources,
         })
     }
 
+    /// Leitet den Event-Endpunkt (`/mopidy/ws`) aus der RPC-URL ab:
+    /// `http(s)` → `ws(s)`, ein abschließendes `/rpc` wird zu `/ws`.
+    #[must_use]
+    pub fn mopidy_ws_url(&self) -> Url {
+        let mut url = self.mopidy_rpc_url.clone();
+        let scheme = if url.scheme() == "https" { "wss" } else { "ws" };
+        // http/https → ws/wss ist laut WHATWG erlaubt und schlägt nicht fehl.
+        let _ = url.set_scheme(scheme);
+
+        let path = match url.path().strip_suffix("/rpc") {
+            Some(prefix) => format!("{prefix}/ws"),
+            None => "/mopidy/ws".to_string(),
+        };
+        url.set_path(&path);
+        url
+    }
+
+    /// Skript nach Namen wie unter `[scripts]` (`audio_mode`, `playlist_from_list`, …).
+    #[must_use]
+    pub fn script(&self, name: &str) -> Option<&ScriptConfig> {
+        match name {
+            "audio_mode" => Some(&self.audio_mode_script),
+            "playlist_from_list" => Some(&self.playlist_script),
+            "hw_mixer" => Some(&self.mixer.script),
+            _ => None,
+        }
+    }
+
     pub fn validate(&self) -> Result<(), crate::error::AppError> {
-        let scripts = [
-            &self.audio_mode_script,
-            &self.playlist_script,
-            &self.rec_start_script,
-            &self.rec_stop_script,
-        ];
+        // `playlist_from_list` läuft nur als Job und scheitert dort mit
+        // `spawn_error`, wenn es fehlt.
+        let scripts = std::iter::once(&self.audio_mode_script)
+            .chain((self.mixer.mode != MixerMode::Mopidy).then_some(&self.mixer.script))
+            .chain(self.actions.values().map(|action| &action.script));
 
         for script_config in scripts {
             let p = script_config.resolve_with(&self.script_workdir);
@@ -161,31 +730,456 @@ impl AppConfig {
                     )));
                 }
             }
+            let workdir = script_config.workdir_with(&self.script_workdir);
+            if !workdir.is_dir() {
+                return Err(crate::error::AppError::Validation(format!(
+                    "script workdir not found: {}",
+                    workdir.display()
+                )));
+            }
         }
         Ok(())
     }
 }
-fn resolve_mopidy_rpc_url<F>(get_env: &F) -> Result<Url, ConfigError>
+/// Vorrang: direkte RPC-URL > `MOPIDY_HTTP_URL` (Umgebung) > Datei (`mopidy_rpc_url`
+/// vor `mopidy_http_url`) > Default.
+fn resolve_mopidy_rpc_url<F>(layers: &mut Layers<'_, F>) -> Url
 where
     F: Fn(&str) -> Option<String>,
 {
-    let direct = get_env("HAUSKI_MOPIDY_RPC_URL").or_else(|| get_env("MOPIDY_RPC_URL"));
+    let candidate = layers
+        .env_raw("mopidy_rpc_url")
+        .map(|raw| (raw, false))
+        .or_else(|| layers.env_raw("mopidy_http_url").map(|raw| (raw, true)))
+        .or_else(|| layers.file_raw("mopidy_rpc_url").map(|raw| (raw, false)))
+        .or_else(|| layers.file_raw("mopidy_http_url").map(|raw| (raw, true)));
+
+    let default = || Url::parse(AppConfig::DEFAULT_MOPIDY_RPC).expect("default Mopidy URL");
+    let Some(((raw, source), from_http_base)) = candidate else {
+        let url = default();
+        layers.record("mopidy_rpc_url", url.to_string(), ConfigSource::Default);
+        return url;
+    };
+
+    match Url::parse(&raw) {
+        Ok(mut url) => {
+            if from_http_base {
+                url.set_path("/mopidy/rpc");
+            }
+            layers.record("mopidy_rpc_url", url.to_string(), source);
+            url
+        }
+        Err(_) => {
+            layers.push_error(ConfigError::InvalidMopidyUrl(raw));
+            default()
+        }
+    }
+}
 
-    if let Some(raw) = direct {
-        return Url::parse(&raw).map_err(|_| ConfigError::InvalidMopidyUrl(raw));
+/// Komma- oder leerraumgetrennte Liste; leere Einträge entfallen.
+fn split_list(raw: &str) -> Vec<String> {
+    raw.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Die Quelle setzt `Layers::parse` ein.
+fn invalid_value(key: &str, raw: &str, reason: impl fmt::Display) -> ConfigError {
+    ConfigError::InvalidValue {
+        key: key.into(),
+        origin: ConfigSource::Default,
+        reason: format!("'{raw}': {reason}"),
     }
+}
 
-    if let Some(base) = get_env("MOPIDY_HTTP_URL") {
-        if let Ok(mut url) = Url::parse(&base) {
-            url.set_path("/mopidy/rpc");
-            return Ok(url);
+/// Tokens aus `auth.tokens`/`HAUSKI_API_TOKENS` (durch Leerraum getrennt) und
+/// `auth.tokens_file`/`HAUSKI_API_TOKENS_FILE` (eine Zeile pro Token, `#` für Kommentare).
+fn load_auth<F>(layers: &mut Layers<'_, F>) -> AuthConfig
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut entries: Vec<String> = Vec::new();
+    if let Some((raw, source)) = layers.raw("auth.tokens") {
+        entries.extend(raw.split_whitespace().map(String::from));
+        let redacted: Vec<Value> = entries
+            .iter()
+            .map(|entry| {
+                let scopes = ApiToken::split(entry).1.unwrap_or("*");
+                Value::String(format!("***={scopes}"))
+            })
+            .collect();
+        layers.record("auth.tokens", redacted, source);
+    }
+
+    if let Some((path, source)) = layers
+        .raw("auth.tokens_file")
+        .filter(|(path, _)| !path.trim().is_empty())
+    {
+        layers.record("auth.tokens_file", path.clone(), source);
+        let path = PathBuf::from(path);
+        match std::fs::read_to_string(&path) {
+            Ok(content) => entries.extend(
+                content
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .map(String::from),
+            ),
+            Err(source) => layers.push_error(ConfigError::TokenFile { path, source }),
         }
-        return Err(ConfigError::InvalidMopidyUrl(base));
     }
 
-    Url::parse(AppConfig::DEFAULT_MOPIDY_RPC)
-        .map_err(|_| ConfigError::InvalidMopidyUrl(AppConfig::DEFAULT_MOPIDY_RPC.into()))
+    let mut tokens = Vec::new();
+    for entry in &entries {
+        match ApiToken::parse(entry) {
+            Ok(token) => tokens.push(token),
+            Err(err) => layers.push_error(err),
+        }
+    }
+    AuthConfig { tokens }
+}
+
+/// `program` aus Umgebung/Datei, alle weiteren Optionen nur aus der Datei.
+fn load_script<F>(
+    layers: &mut Layers<'_, F>,
+    name: &str,
+    default: &str,
+    home: Option<&Path>,
+    script_workdir: &Path,
+) -> ScriptConfig
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let program = layers.parse(
+        &format!("scripts.{name}.program"),
+        default,
+        |raw| Ok(PathBuf::from(raw)),
+        |path| Value::String(path.display().to_string()),
+    );
+    script_options(
+        layers,
+        &format!("scripts.{name}"),
+        program,
+        home,
+        script_workdir,
+    )
 }
+
+/// Optionen neben `program` unter `prefix` (`scripts.<name>` bzw. `actions.<name>`).
+fn script_options<F>(
+    layers: &mut Layers<'_, F>,
+    prefix: &str,
+    program: PathBuf,
+    home: Option<&Path>,
+    script_workdir: &Path,
+) -> ScriptConfig
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let key = |option: &str| format!("{prefix}.{option}");
+
+    let args = layers.file_option(&key("args"), |value| {
+        value
+            .as_array()
+            .and_then(|items| {
+                items
+                    .iter()
+                    .map(|item| item.as_str().map(String::from))
+                    .collect::<Option<Vec<_>>>()
+            })
+            .ok_or_else(|| "expected a list of strings".to_string())
+    });
+    let env = layers.file_option(&key("env"), |value| {
+        value
+            .as_table()
+            .and_then(|table| {
+                table
+                    .iter()
+                    .map(|(name, value)| Some((name.clone(), value.as_str()?.to_string())))
+                    .collect::<Option<BTreeMap<_, _>>>()
+            })
+            .ok_or_else(|| "expected a table of strings".to_string())
+    });
+    let workdir = layers.file_option(&key("workdir"), |value| {
+        value
+            .as_str()
+            .map(|raw| expand_home(raw, home, script_workdir))
+            .ok_or_else(|| "expected a path".to_string())
+    });
+    let timeout = layers.file_option(&key("timeout_ms"), |value| {
+        value
+            .as_integer()
+            .and_then(|ms| u64::try_from(ms).ok())
+            .map(Duration::from_millis)
+            .ok_or_else(|| "expected milliseconds".to_string())
+    });
+    let max_concurrency = layers.file_option(&key("max_concurrency"), |value| {
+        value
+            .as_integer()
+            .and_then(|limit| usize::try_from(limit).ok())
+            .filter(|limit| *limit > 0)
+            .ok_or_else(|| "expected a positive integer".to_string())
+    });
+    let on_busy = layers.file_option(&key("on_busy"), |value| {
+        value
+            .as_str()
+            .and_then(OnBusy::parse)
+            .ok_or_else(|| "expected \"queue\" or \"reject\"".to_string())
+    });
+
+    ScriptConfig {
+        program,
+        args: args.unwrap_or_default(),
+        env: env.unwrap_or_default(),
+        workdir,
+        timeout,
+        max_concurrency,
+        on_busy: on_busy.unwrap_or_default(),
+    }
+}
+
+fn load_mopidy<F>(layers: &mut Layers<'_, F>) -> MopidyConfig
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let defaults = MopidyConfig::default();
+    let number = |layers: &mut Layers<'_, F>, key: &str, default: u64, min: u64| {
+        layers.parse(
+            key,
+            &default.to_string(),
+            |raw| {
+                raw.trim()
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|value| *value >= min)
+                    .ok_or_else(|| invalid_value(key, raw, format!("expected an integer >= {min}")))
+            },
+            |value| Value::Integer(i64::try_from(*value).unwrap_or(i64::MAX)),
+        )
+    };
+    let millis = |duration: Duration| u64::try_from(duration.as_millis()).unwrap_or(u64::MAX);
+    let count = |value: u64| u32::try_from(value).unwrap_or(u32::MAX);
+
+    MopidyConfig {
+        connect_timeout: Duration::from_millis(number(
+            layers,
+            "mopidy.connect_timeout_ms",
+            millis(defaults.connect_timeout),
+            1,
+        )),
+        request_timeout: Duration::from_millis(number(
+            layers,
+            "mopidy.request_timeout_ms",
+            millis(defaults.request_timeout),
+            1,
+        )),
+        retries: count(number(
+            layers,
+            "mopidy.retries",
+            u64::from(defaults.retries),
+            0,
+        )),
+        retry_backoff: Duration::from_millis(number(
+            layers,
+            "mopidy.retry_backoff_ms",
+            millis(defaults.retry_backoff),
+            0,
+        )),
+        breaker_threshold: count(number(
+            layers,
+            "mopidy.breaker_threshold",
+            u64::from(defaults.breaker_threshold),
+            0,
+        )),
+        breaker_cooldown: Duration::from_millis(number(
+            layers,
+            "mopidy.breaker_cooldown_ms",
+            millis(defaults.breaker_cooldown),
+            1,
+        )),
+    }
+}
+
+fn load_mixer<F>(
+    layers: &mut Layers<'_, F>,
+    home: Option<&Path>,
+    script_workdir: &Path,
+) -> MixerConfig
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let defaults = MixerConfig::default();
+    let script = load_script(
+        layers,
+        "hw_mixer",
+        DEFAULT_HW_MIXER_CMD,
+        home,
+        script_workdir,
+    );
+    let mode = layers.parse(
+        "mixer.mode",
+        defaults.mode.as_str(),
+        |raw| {
+            MixerMode::parse(raw).ok_or_else(|| {
+                invalid_value("mixer.mode", raw, "expected mopidy, hardware or auto")
+            })
+        },
+        |mode| Value::String(mode.as_str().into()),
+    );
+    let curve = layers.parse(
+        "mixer.curve",
+        defaults.curve.as_str(),
+        |raw| {
+            VolumeCurve::parse(raw)
+                .ok_or_else(|| invalid_value("mixer.curve", raw, "expected linear or cubic"))
+        },
+        |curve| Value::String(curve.as_str().into()),
+    );
+    let db = |layers: &mut Layers<'_, F>, key: &str, default: f64| {
+        layers.parse(
+            key,
+            &default.to_string(),
+            |raw| {
+                raw.trim()
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|db| db.is_finite())
+                    .ok_or_else(|| invalid_value(key, raw, "expected a dB value"))
+            },
+            |db| Value::Float(*db),
+        )
+    };
+    let mut min_db = db(layers, "mixer.min_db", defaults.min_db);
+    let mut max_db = db(layers, "mixer.max_db", defaults.max_db);
+    if min_db >= max_db {
+        layers.push_error(invalid_value(
+            "mixer.min_db",
+            &min_db.to_string(),
+            format!("must be below mixer.max_db ({max_db})"),
+        ));
+        (min_db, max_db) = (defaults.min_db, defaults.max_db);
+    }
+    let step = layers.parse(
+        "mixer.step",
+        &defaults.step.to_string(),
+        |raw| {
+            raw.trim()
+                .parse::<u8>()
+                .ok()
+                .filter(|step| (1..=100).contains(step))
+                .ok_or_else(|| invalid_value("mixer.step", raw, "expected 1..=100"))
+        },
+        |step| Value::Integer(i64::from(*step)),
+    );
+
+    MixerConfig {
+        mode,
+        script,
+        curve,
+        min_db,
+        max_db,
+        step,
+    }
+}
+
+/// Aktionen aus `[actions.<name>]`; `program` ist Pflicht, `schema` (TOML-Form
+/// eines JSON-Schemas) beschreibt die erlaubte Argumentliste.
+fn load_actions<F>(
+    layers: &mut Layers<'_, F>,
+    home: Option<&Path>,
+    script_workdir: &Path,
+) -> BTreeMap<String, ActionConfig>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut actions = BTreeMap::new();
+    for name in layers.file_keys("actions") {
+        let key = |option: &str| format!("actions.{name}.{option}");
+        let file = ConfigSource::File(
+            layers
+                .file_path()
+                .map(Path::to_path_buf)
+                .unwrap_or_default(),
+        );
+        if !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            layers.invalid(
+                &format!("actions.{name}"),
+                &file,
+                "action names may only contain letters, digits, '_' and '-'",
+            );
+            continue;
+        }
+
+        let Some((program, source)) = layers.file_raw(&key("program")) else {
+            layers.invalid(&key("program"), &file, "missing program");
+            continue;
+        };
+        layers.record(&key("program"), program.clone(), source);
+        let description = layers.file_option(&key("description"), |value| {
+            value
+                .as_str()
+                .map(String::from)
+                .ok_or_else(|| "expected a string".to_string())
+        });
+        let schema = layers.file_option(&key("schema"), |value| {
+            serde_json::to_value(value).map_err(|err| err.to_string())
+        });
+        let script = script_options(
+            layers,
+            &format!("actions.{name}"),
+            PathBuf::from(program),
+            home,
+            script_workdir,
+        );
+
+        let schema = schema.unwrap_or_else(ActionConfig::default_schema);
+        match ActionConfig::new(script, description, schema) {
+            Ok(action) => {
+                actions.insert(name, action);
+            }
+            Err(reason) => layers.invalid(&key("schema"), &file, reason),
+        }
+    }
+    actions
+}
+
+fn load_rpc_policy<F>(layers: &mut Layers<'_, F>) -> RpcPolicy
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut list = |key: &str, default: &[&str]| match layers.raw(key) {
+        Some((raw, source)) => {
+            let patterns = split_list(&raw);
+            layers.record(key, patterns.clone(), source);
+            patterns
+        }
+        None => {
+            let patterns: Vec<String> = default.iter().map(|p| (*p).to_string()).collect();
+            layers.record(key, patterns.clone(), ConfigSource::Default);
+            patterns
+        }
+    };
+    let allow = list("rpc.allow", RpcPolicy::DEFAULT_ALLOW);
+    let deny = list("rpc.deny", RpcPolicy::DEFAULT_DENY);
+
+    RpcPolicy::new(allow, deny).unwrap_or_else(|err| {
+        layers.push_error(err);
+        RpcPolicy::default()
+    })
+}
+
+/// Löst `~/` gegen `$HOME` auf; ohne `$HOME` dient das Script-Workdir als Basis.
+fn expand_home(raw: &str, home: Option<&Path>, fallback: &Path) -> PathBuf {
+    match raw.strip_prefix("~/") {
+        Some(rest) => home.unwrap_or(fallback).join(rest),
+        None => PathBuf::from(raw),
+    }
+}
+
 #[must_use]
 pub fn parse_bool(s: &str) -> Option<bool> {
     match s.trim().to_ascii_lowercase().as_str() {
@@ -250,17 +1244,13 @@ mod tests {
     fn test_script_config_resolve() {
         let base = PathBuf::from("/tmp");
 
-        let relative = ScriptConfig {
-            program: PathBuf::from("script.sh"),
-        };
+        let relative = ScriptConfig::new(PathBuf::from("script.sh"));
         assert_eq!(
             relative.resolve_with(&base),
             PathBuf::from("/tmp/script.sh")
         );
 
-        let absolute = ScriptConfig {
-            program: PathBuf::from("/usr/bin/python"),
-        };
+        let absolute = ScriptConfig::new(PathBuf::from("/usr/bin/python"));
         assert_eq!(
             absolute.resolve_with(&base),
             PathBuf::from("/usr/bin/python")
@@ -283,6 +1273,153 @@ mod tests {
         assert_eq!(config.script_workdir, PathBuf::from("/app"));
         assert_eq!(config.command_timeout, Duration::from_millis(10_000));
         assert!(config.check_mopidy_health);
+        assert_eq!(config.recorder.binary, PathBuf::from("pw-record"));
+        assert_eq!(config.recorder.extension, "wav");
+        assert_eq!(
+            config.recorder.record_dir,
+            PathBuf::from("/app/Music/Recordings")
+        );
+    }
+
+    #[test]
+    fn test_recorder_config_env() {
+        let mut env = HashMap::<String, String>::new();
+        env.insert("HOME".into(), "/home/hauski".into());
+        env.insert("PW_RECORD_BINARY".into(), "/usr/bin/pw-cat".into());
+        env.insert("AUDIO_RECORD_DIR".into(), "~/Takes".into());
+        env.insert("AUDIO_RECORD_EXT".into(), "flac".into());
+        let get_env = |k: &str| env.get(k).cloned();
+        let get_cwd = || Ok(PathBuf::from("/app"));
+
+        let config = AppConfig::from_source(&get_env, get_cwd).unwrap();
+        assert_eq!(config.recorder.binary, PathBuf::from("/usr/bin/pw-cat"));
+        assert_eq!(
+            config.recorder.record_dir,
+            PathBuf::from("/home/hauski/Takes")
+        );
+        assert_eq!(config.recorder.extension, "flac");
+        assert_eq!(
+            config.recorder.state_dir,
+            PathBuf::from("/home/hauski/.cache/hauski-audio")
+        );
+
+        // HAUSKI_REC_BINARY hat Vorrang vor PW_RECORD_BINARY
+        env.insert("HAUSKI_REC_BINARY".into(), "fake-record".into());
+        let get_env = |k: &str| env.get(k).cloned();
+        let config = AppConfig::from_source(&get_env, get_cwd).unwrap();
+        assert_eq!(config.recorder.binary, PathBuf::from("fake-record"));
+    }
+
+    #[test]
+    fn test_api_tokens_from_env_and_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let file = dir.path().join("tokens");
+        std::fs::write(&file, "# Kommentar\nfile-token=read,playback\n\n").unwrap();
+
+        let mut env = HashMap::<String, String>::new();
+        env.insert("HAUSKI_API_TOKENS".into(), "admin  viewer=read".into());
+        env.insert("HAUSKI_API_TOKENS_FILE".into(), file.display().to_string());
+        let get_env = |k: &str| env.get(k).cloned();
+        let get_cwd = || Ok(PathBuf::from("/app"));
+
+        let config = AppConfig::from_source(&get_env, get_cwd).unwrap();
+
+        let tokens: Vec<_> = config
+            .auth
+            .tokens
+            .iter()
+            .map(|t| {
+                (
+                    t.token.as_str(),
+                    t.scopes.iter().copied().collect::<Vec<_>>(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                ("admin", Scope::ALL.to_vec()),
+                ("viewer", vec![Scope::Read]),
+                ("file-token", vec![Scope::Read, Scope::Playback]),
+            ]
+        );
+        assert!(!format!("{:?}", config.auth).contains("admin"));
+    }
+
+    #[test]
+    fn test_api_token_rejects_unknown_scope() {
+        assert!(matches!(
+            ApiToken::parse("secret=read,superuser"),
+            Err(ConfigError::InvalidApiToken(_))
+        ));
+        assert!(ApiToken::parse("=read").is_err());
+        assert!(
+            AppConfig::from_source(&|_: &str| None, || Ok(PathBuf::from("/app")))
+                .unwrap()
+                .auth
+                .tokens
+                .is_empty()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_validate_ignores_job_only_scripts() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let cwd = dir.path().to_path_buf();
+        let config = AppConfig::from_source(&|_: &str| None, || Ok(cwd.clone())).unwrap();
+        assert!(config.validate().is_err());
+
+        let script = dir.path().join("scripts/audio-mode");
+        std::fs::create_dir_all(script.parent().unwrap()).unwrap();
+        std::fs::write(&script, "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        // Ohne playlist-from-list.
+        config.validate().unwrap();
+    }
+
+    #[test]
+    fn test_api_token_keeps_base64_padding() {
+        let padded = ApiToken::parse("c2VjcmV0==").unwrap();
+        assert_eq!(padded.token, "c2VjcmV0==");
+        assert_eq!(padded.scopes, Scope::ALL.into_iter().collect());
+
+        let scoped = ApiToken::parse("c2VjcmV0===read,playback").unwrap();
+        assert_eq!(scoped.token, "c2VjcmV0==");
+        assert_eq!(
+            scoped.scopes.into_iter().collect::<Vec<_>>(),
+            vec![Scope::Read, Scope::Playback]
+        );
+    }
+
+    #[test]
+    fn test_rpc_policy_env() {
+        let config = AppConfig::from_source(&|_: &str| None, || Ok(PathBuf::from("/app"))).unwrap();
+        assert!(config.rpc.permits("core.playback.play"));
+        assert!(!config.rpc.permits("core.library.refresh"));
+        assert!(!config.rpc.permits("core.tracklist.clear"));
+        assert!(!config.rpc.permits("core.playlists.delete"));
+        assert!(!config.rpc.permits("iris.do_something"));
+
+        let mut env = HashMap::<String, String>::new();
+        env.insert(
+            "HAUSKI_RPC_ALLOW".into(),
+            "core.playback.*, core.mixer.get_*".into(),
+        );
+        let get_env = |k: &str| env.get(k).cloned();
+        let config = AppConfig::from_source(&get_env, || Ok(PathBuf::from("/app"))).unwrap();
+        assert!(config.rpc.permits("core.mixer.get_volume"));
+        assert!(!config.rpc.permits("core.mixer.set_volume"));
+        assert_eq!(config.rpc.deny, RpcPolicy::DEFAULT_DENY);
+
+        env.insert("HAUSKI_RPC_DENY".into(), "core.[".into());
+        let get_env = |k: &str| env.get(k).cloned();
+        assert!(matches!(
+            AppConfig::from_source(&get_env, || Ok(PathBuf::from("/app"))),
+            Err(ConfigError::InvalidRpcPattern(_))
+        ));
     }
 
     #[test]
@@ -329,6 +1466,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_mopidy_ws_url_derivation() {
+        let get_cwd = || Ok(PathBuf::from("/app"));
+
+        let env = HashMap::<String, String>::new();
+        let get_env = |k: &str| env.get(k).cloned();
+        let config = AppConfig::from_source(&get_env, get_cwd).unwrap();
+        assert_eq!(
+            config.mopidy_ws_url().as_str(),
+            "ws://127.0.0.1:6680/mopidy/ws"
+        );
+
+        let mut env = HashMap::<String, String>::new();
+        env.insert(
+            "HAUSKI_MOPIDY_RPC_URL".into(),
+            "https://music.local/custom".into(),
+        );
+        let get_env = |k: &str| env.get(k).cloned();
+        let config = AppConfig::from_source(&get_env, get_cwd).unwrap();
+        assert_eq!(
+            config.mopidy_ws_url().as_str(),
+            "wss://music.local/mopidy/ws"
+        );
+    }
+
     #[test]
     fn test_invalid_bind_address() {
         let mut env = HashMap::<String, String>::new();
@@ -419,15 +1581,358 @@ mod tests {
     }
 
     #[test]
-    fn test_app_config_timeout_fallback() {
+    fn test_app_config_invalid_timeout_is_error() {
         let mut env = HashMap::<String, String>::new();
         env.insert("HAUSKI_COMMAND_TIMEOUT_MS".into(), "not-a-number".into());
         let get_env = |k: &str| env.get(k).cloned();
         let get_cwd = || Ok(PathBuf::from("/app"));
 
+        let err = AppConfig::from_source(&get_env, get_cwd).unwrap_err();
+        match err {
+            ConfigError::InvalidValue { key, origin, .. } => {
+                assert_eq!(key, "command_timeout_ms");
+                assert_eq!(
+                    origin,
+                    ConfigSource::Env("HAUSKI_COMMAND_TIMEOUT_MS".into())
+                );
+            }
+            other => panic!("unexpected error: {other}"),
+        }
+    }
+
+    #[test]
+    fn test_config_file_with_env_override() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("backend.toml");
+        std::fs::write(
+            &path,
+            r#"
+bind = "127.0.0.1:9000"
+mopidy_http_url = "http://mopidy.lan:6680"
+command_timeout_ms = 2500
+
+[scripts]
+audio_mode = "/opt/hauski/audio-mode"
+"#,
+        )
+        .unwrap();
+
+        let mut env = HashMap::<String, String>::new();
+        env.insert("HAUSKI_CONFIG".into(), path.display().to_string());
+        env.insert("HAUSKI_BIND".into(), "127.0.0.1:9100".into());
+        let get_env = |k: &str| env.get(k).cloned();
+        let get_cwd = || Ok(PathBuf::from("/app"));
+
         let config = AppConfig::from_source(&get_env, get_cwd).unwrap();
-        // Should fall back to DEFAULT_COMMAND_TIMEOUT_MS (10_000)
-        assert_eq!(config.command_timeout, Duration::from_millis(10_000));
+        assert_eq!(config.bind_addr, "127.0.0.1:9100".parse().unwrap());
+        assert_eq!(
+            config.mopidy_rpc_url.as_str(),
+            "http://mopidy.lan:6680/mopidy/rpc"
+        );
+        assert_eq!(config.command_timeout, Duration::from_millis(2500));
+        assert_eq!(
+            config.audio_mode_script.program,
+            PathBuf::from("/opt/hauski/audio-mode")
+        );
+        assert!(!config.rpc.permits("core.tracklist.clear"));
+
+        let sources = &config.sources;
+        assert_eq!(
+            sources.get("bind").unwrap().source,
+            ConfigSource::Env("HAUSKI_BIND".into())
+        );
+        assert_eq!(
+            sources.get("command_timeout_ms").unwrap().source,
+            ConfigSource::File(path.clone())
+        );
+        assert_eq!(
+            sources.get("check_mopidy_health").unwrap().source,
+            ConfigSource::Default
+        );
+
+        let rendered = sources.render();
+        assert!(rendered.contains("bind = \"127.0.0.1:9100\"  # env HAUSKI_BIND"));
+        assert!(rendered.contains("[scripts.audio_mode]\nprogram = \"/opt/hauski/audio-mode\""));
+    }
+
+    #[test]
+    fn test_config_file_script_options() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("backend.toml");
+        std::fs::write(
+            &path,
+            r#"
+[scripts]
+playlist_from_list = "./bin/playlist-from-list"
+
+[scripts.audio_mode]
+program = "/opt/hauski/audio-mode"
+args = ["--user", "--quiet"]
+env = { PULSE_SINK = "alsa_output.usb", LANG = "C" }
+workdir = "/srv/hauski"
+timeout_ms = 30000
+max_concurrency = 1
+on_busy = "reject"
+"#,
+        )
+        .unwrap();
+
+        let mut env = HashMap::<String, String>::new();
+        env.insert(
+            "HAUSKI_AUDIO_MODE_CMD".into(),
+            "./scripts/audio-mode".into(),
+        );
+        let get_env = |k: &str| env.get(k).cloned();
+        let get_cwd = || Ok(PathBuf::from("/app"));
+        let config = AppConfig::from_layers(&get_env, get_cwd, Some(&path)).unwrap();
+
+        let script = &config.audio_mode_script;
+        // Umgebungsvariable gewinnt nur für `program`.
+        assert_eq!(script.program, PathBuf::from("./scripts/audio-mode"));
+        assert_eq!(script.args, ["--user", "--quiet"]);
+        assert_eq!(script.env["PULSE_SINK"], "alsa_output.usb");
+        assert_eq!(
+            script.workdir_with(&config.script_workdir),
+            PathBuf::from("/srv/hauski")
+        );
+        assert_eq!(script.timeout, Some(Duration::from_secs(30)));
+        assert_eq!(script.max_concurrency, Some(1));
+        assert_eq!(script.on_busy, OnBusy::Reject);
+        assert_eq!(
+            config.playlist_script.program,
+            PathBuf::from("./bin/playlist-from-list")
+        );
+        assert_eq!(config.playlist_script.max_concurrency, None);
+        assert_eq!(config.playlist_script.on_busy, OnBusy::Queue);
+        assert_eq!(
+            config
+                .sources
+                .get("scripts.audio_mode.timeout_ms")
+                .unwrap()
+                .source,
+            ConfigSource::File(path.clone())
+        );
+
+        std::fs::write(
+            &path,
+            r#"
+[scripts.audio_mode]
+args = "--user"
+max_concurrency = 0
+on_busy = "drop"
+retries = 3
+"#,
+        )
+        .unwrap();
+        let err = AppConfig::from_layers(&get_env, get_cwd, Some(&path)).unwrap_err();
+        let ConfigError::Multiple(errors) = err else {
+            panic!("expected multiple errors, got {err}");
+        };
+        let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+        assert_eq!(errors.len(), 4, "{messages:?}");
+        assert!(messages
+            .iter()
+            .any(|m| m.contains("unknown key 'scripts.audio_mode.retries'")));
+        assert!(messages
+            .iter()
+            .any(|m| m.starts_with("invalid value for scripts.audio_mode.on_busy")));
+    }
+
+    #[test]
+    fn test_config_file_actions() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("backend.toml");
+        std::fs::write(
+            &path,
+            r#"
+[actions]
+dac_off = "./bin/dac-off"
+
+[actions.room_preset]
+program = "./bin/room-correction"
+description = "Raumkorrektur-Preset laden"
+timeout_ms = 5000
+schema = { type = "array", items = { enum = ["flat", "night"] }, minItems = 1, maxItems = 1 }
+"#,
+        )
+        .unwrap();
+        let get_env = |_: &str| None;
+        let get_cwd = || Ok(PathBuf::from("/app"));
+        let config = AppConfig::from_layers(&get_env, get_cwd, Some(&path)).unwrap();
+
+        let dac = &config.actions["dac_off"];
+        assert_eq!(dac.script.program, PathBuf::from("./bin/dac-off"));
+        assert_eq!(dac.schema, ActionConfig::default_schema());
+        assert!(!dac.validator.is_valid(&serde_json::json!(["now"])));
+
+        let preset = &config.actions["room_preset"];
+        assert_eq!(
+            preset.description.as_deref(),
+            Some("Raumkorrektur-Preset laden")
+        );
+        assert_eq!(preset.script.timeout, Some(Duration::from_secs(5)));
+        assert!(preset.validator.is_valid(&serde_json::json!(["night"])));
+        assert!(!preset.validator.is_valid(&serde_json::json!(["loud"])));
+
+        std::fs::write(
+            &path,
+            r#"
+[actions.broken]
+schema = { type = "array" }
+
+[actions."rm -rf"]
+program = "/bin/rm"
+
+[actions.odd]
+program = "./bin/odd"
+schema = { type = "no-such-type" }
+"#,
+        )
+        .unwrap();
+        let err = AppConfig::from_layers(&get_env, get_cwd, Some(&path)).unwrap_err();
+        let ConfigError::Multiple(errors) = err else {
+            panic!("expected multiple errors, got {err}");
+        };
+        let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+        assert_eq!(errors.len(), 3, "{messages:?}");
+        assert!(messages
+            .iter()
+            .any(|m| m.starts_with("invalid value for actions.broken.program")));
+        assert!(messages
+            .iter()
+            .any(|m| m.starts_with("invalid value for actions.rm -rf")));
+        assert!(messages
+            .iter()
+            .any(|m| m.starts_with("invalid value for actions.odd.schema")));
+    }
+
+    #[test]
+    fn test_config_mopidy_client() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("backend.toml");
+        std::fs::write(
+            &path,
+            "[mopidy]\nrequest_timeout_ms = 3000\nretries = 0\nbreaker_threshold = 0\n",
+        )
+        .unwrap();
+        let mut env = HashMap::<String, String>::new();
+        env.insert("HAUSKI_MOPIDY_CONNECT_TIMEOUT_MS".into(), "500".into());
+        let get_env = |k: &str| env.get(k).cloned();
+        let get_cwd = || Ok(PathBuf::from("/app"));
+        let config = AppConfig::from_layers(&get_env, get_cwd, Some(&path)).unwrap();
+
+        assert_eq!(
+            config.mopidy,
+            MopidyConfig {
+                connect_timeout: Duration::from_millis(500),
+                request_timeout: Duration::from_secs(3),
+                retries: 0,
+                breaker_threshold: 0,
+                ..MopidyConfig::default()
+            }
+        );
+
+        std::fs::write(&path, "[mopidy]\nrequest_timeout_ms = 0\n").unwrap();
+        let err = AppConfig::from_layers(&get_env, get_cwd, Some(&path)).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("invalid value for mopidy.request_timeout_ms"));
+    }
+
+    #[test]
+    fn test_config_file_mixer() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("backend.toml");
+        std::fs::write(
+            &path,
+            r#"
+[mixer]
+mode = "auto"
+curve = "linear"
+min_db = -80
+max_db = -6.5
+
+[scripts.hw_mixer]
+program = "./bin/hw-mixer"
+args = ["--card", "DAC"]
+"#,
+        )
+        .unwrap();
+        let mut env = HashMap::<String, String>::new();
+        env.insert("HAUSKI_VOLUME_STEP".into(), "2".into());
+        let get_env = |k: &str| env.get(k).cloned();
+        let get_cwd = || Ok(PathBuf::from("/app"));
+        let config = AppConfig::from_layers(&get_env, get_cwd, Some(&path)).unwrap();
+
+        assert_eq!(config.mixer.mode, MixerMode::Auto);
+        assert_eq!(config.mixer.curve, VolumeCurve::Linear);
+        assert!((config.mixer.min_db - -80.0).abs() < f64::EPSILON);
+        assert!((config.mixer.max_db - -6.5).abs() < f64::EPSILON);
+        assert_eq!(config.mixer.step, 2);
+        assert_eq!(config.mixer.script.args, ["--card", "DAC"]);
+        assert_eq!(
+            config.script("hw_mixer").unwrap().program,
+            PathBuf::from("./bin/hw-mixer")
+        );
+
+        std::fs::write(&path, "[mixer]\nmin_db = 0\nmax_db = -10\n").unwrap();
+        let err = AppConfig::from_layers(&get_env, get_cwd, Some(&path)).unwrap_err();
+        assert!(err.to_string().contains("must be below mixer.max_db (-10)"));
+    }
+
+    #[test]
+    fn test_config_file_reports_all_errors() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("backend.toml");
+        std::fs::write(
+            &path,
+            r#"
+bnd = "127.0.0.1:9000"
+command_timeout_ms = "soon"
+check_mopidy_health = 3.5
+
+[recorder]
+colour = "red"
+"#,
+        )
+        .unwrap();
+
+        let get_env = |_: &str| None;
+        let get_cwd = || Ok(PathBuf::from("/app"));
+        let err = AppConfig::from_layers(&get_env, get_cwd, Some(&path)).unwrap_err();
+        let ConfigError::Multiple(errors) = err else {
+            panic!("expected multiple errors, got {err}");
+        };
+        let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+        assert_eq!(errors.len(), 4, "{messages:?}");
+        assert!(messages.iter().any(|m| m.contains("unknown key 'bnd'")));
+        assert!(messages
+            .iter()
+            .any(|m| m.contains("unknown key 'recorder.colour'")));
+        assert!(messages
+            .iter()
+            .any(|m| m.starts_with("invalid value for command_timeout_ms (file ")));
+        assert!(messages
+            .iter()
+            .any(|m| m.starts_with("invalid value for check_mopidy_health")));
+    }
+
+    #[test]
+    fn test_config_file_syntax_and_missing_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("broken.toml");
+        std::fs::write(&path, "bind = ").unwrap();
+        let get_env = |_: &str| None;
+        let get_cwd = || Ok(PathBuf::from("/app"));
+
+        assert!(matches!(
+            AppConfig::from_layers(&get_env, get_cwd, Some(&path)),
+            Err(ConfigError::Syntax { .. })
+        ));
+        assert!(matches!(
+            AppConfig::from_layers(&get_env, get_cwd, Some(&dir.path().join("missing.toml"))),
+            Err(ConfigError::File { .. })
+        ));
     }
 
     #[test]
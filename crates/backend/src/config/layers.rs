@@ -0,0 +1,405 @@
+//! Konfigurationsschichten: Umgebungsvariablen > TOML-Datei > Defaults.
+//!
+//! Jeder Schlüssel der Datei hat feste Umgebungsvariablen (Vorrang von links).
+//! Fehler werden gesammelt statt beim ersten abzubrechen, und zu jedem
+//! effektiven Wert wird die Quelle für `config show` festgehalten.
+//! Skriptoptionen (`[scripts.<name>]`) und Aktionen (`[actions.<name>]`) gibt
+//! es nur in der Datei.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use toml::{Table, Value};
+
+use super::ConfigError;
+
+/// Dateischlüssel und zugehörige Umgebungsvariablen.
+const SETTINGS: &[(&str, &[&str])] = &[
+    ("bind", &["HAUSKI_BACKEND_BIND", "HAUSKI_BIND"]),
+    (
+        "mopidy_rpc_url",
+        &["HAUSKI_MOPIDY_RPC_URL", "MOPIDY_RPC_URL"],
+    ),
+    ("mopidy_http_url", &["MOPIDY_HTTP_URL"]),
+    ("script_workdir", &["HAUSKI_SCRIPT_WORKDIR"]),
+    ("command_timeout_ms", &["HAUSKI_COMMAND_TIMEOUT_MS"]),
+    ("job_timeout_ms", &["HAUSKI_JOB_TIMEOUT_MS"]),
+    ("check_mopidy_health", &["HAUSKI_CHECK_MOPIDY_HEALTH"]),
+    (
+        "mopidy.connect_timeout_ms",
+        &["HAUSKI_MOPIDY_CONNECT_TIMEOUT_MS"],
+    ),
+    (
+        "mopidy.request_timeout_ms",
+        &["HAUSKI_MOPIDY_REQUEST_TIMEOUT_MS"],
+    ),
+    ("mopidy.retries", &["HAUSKI_MOPIDY_RETRIES"]),
+    (
+        "mopidy.retry_backoff_ms",
+        &["HAUSKI_MOPIDY_RETRY_BACKOFF_MS"],
+    ),
+    (
+        "mopidy.breaker_threshold",
+        &["HAUSKI_MOPIDY_BREAKER_THRESHOLD"],
+    ),
+    (
+        "mopidy.breaker_cooldown_ms",
+        &["HAUSKI_MOPIDY_BREAKER_COOLDOWN_MS"],
+    ),
+    ("scripts.audio_mode.program", &["HAUSKI_AUDIO_MODE_CMD"]),
+    (
+        "scripts.playlist_from_list.program",
+        &["HAUSKI_PLAYLIST_FROM_LIST_CMD", "HAUSKI_PLAYLIST_CMD"],
+    ),
+    ("scripts.hw_mixer.program", &["HAUSKI_HW_MIXER_CMD"]),
+    (
+        "recorder.binary",
+        &["HAUSKI_REC_BINARY", "PW_RECORD_BINARY"],
+    ),
+    ("recorder.record_dir", &["AUDIO_RECORD_DIR"]),
+    ("recorder.extension", &["AUDIO_RECORD_EXT"]),
+    ("recorder.state_dir", &["HAUSKI_STATE_DIR"]),
+    ("mixer.mode", &["HAUSKI_MIXER_MODE"]),
+    ("mixer.curve", &["HAUSKI_MIXER_CURVE"]),
+    ("mixer.min_db", &["HAUSKI_MIXER_MIN_DB"]),
+    ("mixer.max_db", &["HAUSKI_MIXER_MAX_DB"]),
+    ("mixer.step", &["HAUSKI_VOLUME_STEP"]),
+    ("auth.tokens", &["HAUSKI_API_TOKENS"]),
+    ("auth.tokens_file", &["HAUSKI_API_TOKENS_FILE"]),
+    ("rpc.allow", &["HAUSKI_RPC_ALLOW"]),
+    ("rpc.deny", &["HAUSKI_RPC_DENY"]),
+];
+
+/// Optionen unter `[scripts.<name>]` neben `program`.
+const SCRIPT_OPTIONS: &[&str] = &[
+    "timeout_ms",
+    "args",
+    "env",
+    "workdir",
+    "max_concurrency",
+    "on_busy",
+];
+
+/// Schlüssel unter `[actions.<name>]` zusätzlich zu `SCRIPT_OPTIONS`.
+const ACTION_KEYS: &[&str] = &["program", "description", "schema"];
+
+/// Umgebungsvariablen eines bekannten Schlüssels; `None` = unbekannt.
+fn env_names(key: &str) -> Option<&'static [&'static str]> {
+    if let Some((_, names)) = SETTINGS.iter().find(|(known, _)| *known == key) {
+        return Some(names);
+    }
+    let (script, option) = key.rsplit_once('.')?;
+    if let Some(action) = script.strip_prefix("actions.") {
+        return (!action.contains('.')
+            && (ACTION_KEYS.contains(&option) || SCRIPT_OPTIONS.contains(&option)))
+        .then_some(&[]);
+    }
+    let program = format!("{script}.program");
+    (SCRIPT_OPTIONS.contains(&option) && SETTINGS.iter().any(|(known, _)| *known == program))
+        .then_some(&[])
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    Default,
+    Env(String),
+    File(PathBuf),
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::Default => f.write_str("default"),
+            ConfigSource::Env(name) => write!(f, "env {name}"),
+            ConfigSource::File(path) => write!(f, "file {}", path.display()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ConfigEntry {
+    pub key: String,
+    pub value: Value,
+    pub source: ConfigSource,
+}
+
+/// Effektive Werte mit Herkunft, in Ladereihenfolge.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigSources {
+    entries: Vec<ConfigEntry>,
+    file: Option<PathBuf>,
+}
+
+impl ConfigSources {
+    #[must_use]
+    pub fn entries(&self) -> &[ConfigEntry] {
+        &self.entries
+    }
+
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&ConfigEntry> {
+        self.entries.iter().find(|entry| entry.key == key)
+    }
+
+    /// Geladene Konfigurationsdatei, falls vorhanden (Basis für Reloads).
+    #[must_use]
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+
+    /// TOML-Darstellung der effektiven Konfiguration, Quelle als Kommentar.
+    #[must_use]
+    pub fn render(&self) -> String {
+        let mut sections: Vec<(&str, Vec<&ConfigEntry>)> = vec![("", Vec::new())];
+        for entry in &self.entries {
+            let section = entry
+                .key
+                .rsplit_once('.')
+                .map_or("", |(section, _)| section);
+            match sections.iter_mut().find(|(name, _)| *name == section) {
+                Some((_, entries)) => entries.push(entry),
+                None => sections.push((section, vec![entry])),
+            }
+        }
+
+        let mut out = String::new();
+        for (section, entries) in sections {
+            if entries.is_empty() {
+                continue;
+            }
+            if !section.is_empty() {
+                out.push_str(&format!("\n[{section}]\n"));
+            }
+            for entry in entries {
+                let key = entry.key.rsplit('.').next().unwrap_or(&entry.key);
+                out.push_str(&format!("{key} = {}  # {}\n", entry.value, entry.source));
+            }
+        }
+        out
+    }
+}
+
+pub(super) struct ConfigFile {
+    path: PathBuf,
+    table: Table,
+}
+
+impl ConfigFile {
+    pub(super) fn load(path: &Path) -> Result<Self, ConfigError> {
+        let content = std::fs::read_to_string(path).map_err(|source| ConfigError::File {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let table = content
+            .parse::<Table>()
+            .map_err(|err| ConfigError::Syntax {
+                path: path.to_path_buf(),
+                message: err.to_string(),
+            })?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            table,
+        })
+    }
+
+    fn unknown_keys(&self) -> Vec<ConfigError> {
+        let mut errors = Vec::new();
+        self.collect_unknown("", &self.table, &mut errors);
+        errors
+    }
+
+    fn collect_unknown(&self, prefix: &str, table: &Table, errors: &mut Vec<ConfigError>) {
+        for (key, value) in table {
+            let full = if prefix.is_empty() {
+                key.clone()
+            } else {
+                format!("{prefix}.{key}")
+            };
+            if env_names(&full).is_some() {
+                continue;
+            }
+            match value {
+                Value::Table(nested) => self.collect_unknown(&full, nested, errors),
+                // Kurzform `[scripts] audio_mode = "…"` für `program`.
+                _ if env_names(&format!("{full}.program")).is_some() => {}
+                _ => errors.push(ConfigError::UnknownKey {
+                    key: full,
+                    path: self.path.clone(),
+                }),
+            }
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<&Value> {
+        let mut segments = key.split('.');
+        let mut value = self.table.get(segments.next()?)?;
+        for segment in segments {
+            value = match value {
+                Value::Table(table) => table.get(segment)?,
+                shorthand if segment == "program" => shorthand,
+                _ => return None,
+            };
+        }
+        Some(value)
+    }
+}
+
+pub(super) struct Layers<'a, F> {
+    get_env: &'a F,
+    file: Option<ConfigFile>,
+    errors: Vec<ConfigError>,
+    sources: ConfigSources,
+}
+
+impl<'a, F> Layers<'a, F>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    pub(super) fn new(get_env: &'a F, file: Option<ConfigFile>) -> Self {
+        let errors = file
+            .as_ref()
+            .map(ConfigFile::unknown_keys)
+            .unwrap_or_default();
+        let sources = ConfigSources {
+            entries: Vec::new(),
+            file: file.as_ref().map(|file| file.path.clone()),
+        };
+        Self {
+            get_env,
+            file,
+            errors,
+            sources,
+        }
+    }
+
+    /// Erster gesetzter Wert aus Umgebung oder Datei.
+    pub(super) fn raw(&mut self, key: &str) -> Option<(String, ConfigSource)> {
+        self.env_raw(key).or_else(|| self.file_raw(key))
+    }
+
+    pub(super) fn env_raw(&self, key: &str) -> Option<(String, ConfigSource)> {
+        let names = env_names(key).unwrap_or_else(|| panic!("unknown config key {key}"));
+        names.iter().find_map(|name| {
+            (self.get_env)(name).map(|value| (value, ConfigSource::Env((*name).into())))
+        })
+    }
+
+    /// Dateiwert als Text; Listen werden mit Leerzeichen verbunden.
+    pub(super) fn file_raw(&mut self, key: &str) -> Option<(String, ConfigSource)> {
+        let file = self.file.as_ref()?;
+        let source = ConfigSource::File(file.path.clone());
+        let text = match file.get(key)? {
+            Value::String(value) => Ok(value.clone()),
+            Value::Integer(value) => Ok(value.to_string()),
+            Value::Float(value) => Ok(value.to_string()),
+            Value::Boolean(value) => Ok(value.to_string()),
+            Value::Array(items) => items
+                .iter()
+                .map(|item| item.as_str().map(String::from).ok_or(()))
+                .collect::<Result<Vec<_>, _>>()
+                .map(|items| items.join(" "))
+                .map_err(|()| "expected a list of strings".to_string()),
+            other => Err(format!("unsupported value type {}", other.type_str())),
+        };
+        match text {
+            Ok(text) => Some((text, source)),
+            Err(reason) => {
+                self.invalid(key, &source, reason);
+                None
+            }
+        }
+    }
+
+    pub(super) fn file_path(&self) -> Option<&Path> {
+        self.file.as_ref().map(|file| file.path.as_path())
+    }
+
+    /// Namen unter einem Abschnitt der Datei, z. B. die Aktionen unter `[actions]`.
+    pub(super) fn file_keys(&self, section: &str) -> Vec<String> {
+        self.file
+            .as_ref()
+            .and_then(|file| file.table.get(section))
+            .and_then(Value::as_table)
+            .map(|table| table.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Optionaler Wert nur aus der Datei, typisiert über `convert`; ungültige
+    /// Werte werden gesammelt.
+    pub(super) fn file_option<T>(
+        &mut self,
+        key: &str,
+        convert: impl FnOnce(&Value) -> Result<T, String>,
+    ) -> Option<T> {
+        let file = self.file.as_ref()?;
+        let value = file.get(key)?.clone();
+        let source = ConfigSource::File(file.path.clone());
+        match convert(&value) {
+            Ok(converted) => {
+                self.record(key, value, source);
+                Some(converted)
+            }
+            Err(reason) => {
+                self.invalid(key, &source, reason);
+                None
+            }
+        }
+    }
+
+    /// Wert aus Umgebung/Datei oder `default`, geparst mit `parse`. Ungültige
+    /// Werte werden gesammelt; weitergerechnet wird dann mit dem Default.
+    pub(super) fn parse<T>(
+        &mut self,
+        key: &str,
+        default: &str,
+        parse: impl Fn(&str) -> Result<T, ConfigError>,
+        display: impl Fn(&T) -> Value,
+    ) -> T {
+        let (raw, source) = self
+            .raw(key)
+            .unwrap_or_else(|| (default.to_string(), ConfigSource::Default));
+        let value = match parse(&raw) {
+            Ok(value) => value,
+            Err(ConfigError::InvalidValue { key, reason, .. }) => {
+                // Der Parser kennt die Quelle nicht; hier wird sie nachgetragen.
+                self.invalid(&key, &source, reason);
+                return parse(default).unwrap_or_else(|_| panic!("default for {key} is valid"));
+            }
+            Err(err) => {
+                self.errors.push(err);
+                return parse(default).unwrap_or_else(|_| panic!("default for {key} is valid"));
+            }
+        };
+        self.record(key, display(&value), source);
+        value
+    }
+
+    pub(super) fn record(&mut self, key: &str, value: impl Into<Value>, source: ConfigSource) {
+        self.sources.entries.push(ConfigEntry {
+            key: key.into(),
+            value: value.into(),
+            source,
+        });
+    }
+
+    pub(super) fn invalid(&mut self, key: &str, source: &ConfigSource, reason: impl Into<String>) {
+        self.errors.push(ConfigError::InvalidValue {
+            key: key.into(),
+            origin: source.clone(),
+            reason: reason.into(),
+        });
+    }
+
+    pub(super) fn push_error(&mut self, error: ConfigError) {
+        self.errors.push(error);
+    }
+
+    /// Ein Fehler wird direkt gemeldet, mehrere gesammelt als `ConfigError::Multiple`.
+    pub(super) fn finish(mut self) -> Result<ConfigSources, ConfigError> {
+        match self.errors.len() {
+            0 => Ok(self.sources),
+            1 => Err(self.errors.remove(0)),
+            _ => Err(ConfigError::Multiple(self.errors)),
+        }
+    }
+}
@@ -1,26 +1,50 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
-use serde_json::Value;
+use serde_json::{json, Value};
 use tracing::instrument;
 
 use crate::error::AppError;
-use crate::models::{SimilarResponse, SimilarTrack};
+use crate::models::{SimilarResponse, SimilarStrategy, SimilarTrack, TrackInfo};
+use crate::mopidy::types::{self, decode_list, SearchResult, Track};
 use crate::mopidy::MopidyClient;
 
+/// Standard ohne `strategy`-Parameter: alle Strategien.
+pub fn parse_strategies(raw: Option<&str>) -> Result<Vec<SimilarStrategy>, AppError> {
+    let Some(raw) = raw.map(str::trim).filter(|raw| !raw.is_empty()) else {
+        return Ok(SimilarStrategy::ALL.to_vec());
+    };
+
+    let mut strategies = Vec::new();
+    for part in raw
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+    {
+        let strategy: SimilarStrategy = part.parse().map_err(AppError::BadRequest)?;
+        if !strategies.contains(&strategy) {
+            strategies.push(strategy);
+        }
+    }
+    Ok(strategies)
+}
+
 #[instrument(skip(mopidy))]
 pub async fn similar_tracks(
     mopidy: &dyn MopidyClient,
     seed: &str,
     limit: Option<usize>,
+    strategies: &[SimilarStrategy],
+    include_variants: bool,
 ) -> Result<SimilarResponse, AppError> {
-    let seed_track_value = mopidy.lookup_track(seed).await?;
-    let seed_track_value =
-        seed_track_value.ok_or_else(|| AppError::bad_request("seed track not found in Mopidy"))?;
+    let seed_model = mopidy
+        .lookup_track(seed)
+        .await?
+        .ok_or_else(|| AppError::bad_request("seed track not found in Mopidy"))?;
 
-    let seed_track = build_track(&seed_track_value)
-        .ok_or_else(|| AppError::internal("seed track missing uri"))?;
+    let seed_track =
+        build_track(&seed_model).ok_or_else(|| AppError::internal("seed track missing name"))?;
 
-    let query = build_query(&seed_track_value)
+    let query = build_query(&seed_model)
         .ok_or_else(|| AppError::internal("unable to derive search query from seed track"))?;
 
     let target_limit = limit.unwrap_or(10);
@@ -28,64 +52,207 @@ pub async fn similar_tracks(
         return Ok(SimilarResponse {
             seed: seed_track,
             query,
+            strategies: strategies.to_vec(),
             tracks: Vec::new(),
         });
     }
 
-    let search_results = mopidy.search_any(&query).await?;
-    let mut seen: HashSet<String> = HashSet::new();
-    seen.insert(seed_track.uri.clone());
+    // Alle Suchen aller Strategien in einem Batch statt einzeln nacheinander.
+    let plans: Vec<(SimilarStrategy, Vec<Value>)> = strategies
+        .iter()
+        .map(|&strategy| {
+            let searches = strategy_searches(strategy, &seed_model, &seed_track, &query);
+            (strategy, searches)
+        })
+        .collect();
+    let calls: Vec<(&str, Option<Value>)> = plans
+        .iter()
+        .flat_map(|(_, searches)| searches)
+        .map(|fields| {
+            (
+                "core.library.search",
+                Some(json!({ "query": fields, "exact": false })),
+            )
+        })
+        .collect();
+    let mut results = mopidy.call_batch(&calls).await?.into_iter();
+
+    let seed_title = base_title(&seed_track.name);
     let mut collected: Vec<SimilarTrack> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
 
-    for backend in search_results {
-        if let Some(tracks) = backend.get("tracks").and_then(Value::as_array) {
-            for track in tracks {
-                if let Some(uri) = track.get("uri").and_then(Value::as_str) {
-                    if seen.contains(uri) {
-                        continue;
-                    }
-                }
+    for (strategy, searches) in plans {
+        let mut candidates: Vec<TrackInfo> = results
+            .by_ref()
+            .take(searches.len())
+            .flat_map(|result| tracks_of(&decode_list::<SearchResult>(result)))
+            .collect();
+        retain_for_strategy(strategy, &seed_track, &mut candidates);
 
-                let Some(candidate) = build_track(track) else {
-                    continue;
-                };
-                if !seen.insert(candidate.uri.clone()) {
-                    continue;
-                }
-                collected.push(candidate);
-                if collected.len() >= target_limit {
-                    break;
-                }
+        for candidate in candidates {
+            if candidate.uri == seed_track.uri {
+                continue;
             }
-        }
-        if collected.len() >= target_limit {
-            break;
+            if !include_variants
+                && (is_karaoke(&candidate.name) || base_title(&candidate.name) == seed_title)
+            {
+                continue;
+            }
+            let position = *index.entry(candidate.uri.clone()).or_insert_with(|| {
+                collected.push(SimilarTrack {
+                    track: candidate,
+                    score: 0.0,
+                    scores: BTreeMap::new(),
+                });
+                collected.len() - 1
+            });
+            let hit = &mut collected[position];
+            hit.scores.insert(strategy, strategy.weight());
+            hit.score = hit.scores.values().sum();
         }
     }
 
+    // Stabil sortieren: bei gleichem Score bleibt die Fundreihenfolge erhalten.
+    collected.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+    if !include_variants {
+        // Mehrere Fassungen desselben Songs auf die bestbewertete reduzieren.
+        let mut songs: HashSet<(String, Option<String>)> = HashSet::new();
+        collected.retain(|hit| {
+            songs.insert((
+                base_title(&hit.track.name),
+                hit.track
+                    .artists
+                    .first()
+                    .map(|artist| artist.to_lowercase()),
+            ))
+        });
+    }
+    collected.truncate(target_limit);
+
     Ok(SimilarResponse {
         seed: seed_track,
         query,
+        strategies: strategies.to_vec(),
         tracks: collected,
     })
 }
 
-fn build_query(track: &Value) -> Option<String> {
-    let name = track.get("name").and_then(Value::as_str)?.trim();
-    if name.is_empty() {
-        return None;
+/// Suchfelder (`core.library.search`-Query) einer Strategie; leer, wenn dem
+/// Seed die nötigen Angaben fehlen.
+fn strategy_searches(
+    strategy: SimilarStrategy,
+    seed_model: &Track,
+    seed: &TrackInfo,
+    query: &str,
+) -> Vec<Value> {
+    match strategy {
+        SimilarStrategy::Query => vec![json!({ "any": [query] })],
+        SimilarStrategy::Artist => seed
+            .artists
+            .first()
+            .map(|artist| json!({ "artist": [artist] }))
+            .into_iter()
+            .collect(),
+        SimilarStrategy::Album => {
+            let Some(album) = &seed.album else {
+                return Vec::new();
+            };
+            let mut fields = json!({ "album": [album] });
+            if let Some(artist) = seed.artists.first() {
+                fields["artist"] = json!([artist]);
+            }
+            vec![fields]
+        }
+        SimilarStrategy::Genre => non_empty(seed_model.genre.as_deref())
+            .map(|genre| json!({ "genre": [genre] }))
+            .into_iter()
+            .collect(),
+        SimilarStrategy::Year => non_empty(seed.date.as_deref())
+            .and_then(|date| date.get(..4))
+            .map(|year| json!({ "date": [year] }))
+            .into_iter()
+            .collect(),
+        SimilarStrategy::CoArtist => co_artists(seed_model, seed)
+            .into_iter()
+            .map(|artist| json!({ "artist": [artist] }))
+            .collect(),
     }
+}
 
-    let artists = track
-        .get("artists")
-        .and_then(Value::as_array)
-        .and_then(|arr| arr.first())
-        .and_then(|artist| artist.get("name"))
-        .and_then(Value::as_str)
-        .map(str::trim)
-        .filter(|value| !value.is_empty());
+/// Künstler-Treffer ohne das Seed-Album, Album-Treffer nur vom Seed-Album.
+fn retain_for_strategy(strategy: SimilarStrategy, seed: &TrackInfo, tracks: &mut Vec<TrackInfo>) {
+    let same_album = |track: &TrackInfo| match (&seed.album, &track.album) {
+        (Some(seed_album), Some(album)) => seed_album.eq_ignore_ascii_case(album),
+        _ => false,
+    };
+    match strategy {
+        SimilarStrategy::Artist => tracks.retain(|track| !same_album(track)),
+        SimilarStrategy::Album => tracks.retain(same_album),
+        SimilarStrategy::Query
+        | SimilarStrategy::Genre
+        | SimilarStrategy::Year
+        | SimilarStrategy::CoArtist => {}
+    }
+}
+
+fn tracks_of(search_results: &[SearchResult]) -> Vec<TrackInfo> {
+    search_results
+        .iter()
+        .flat_map(|backend| &backend.tracks)
+        .filter_map(build_track)
+        .collect()
+}
+
+fn non_empty(value: Option<&str>) -> Option<&str> {
+    value.map(str::trim).filter(|value| !value.is_empty())
+}
+
+/// Weitere Track- und Album-Künstler neben dem Hauptkünstler.
+fn co_artists(seed_model: &Track, seed: &TrackInfo) -> Vec<String> {
+    let album_artists = seed_model
+        .album
+        .iter()
+        .flat_map(|album| types::names(&album.artists));
+
+    let mut artists: Vec<String> = Vec::new();
+    for name in seed.artists.iter().map(String::as_str).chain(album_artists) {
+        if !artists.iter().any(|known| known.eq_ignore_ascii_case(name)) {
+            artists.push(name.into());
+        }
+    }
+    artists.into_iter().skip(1).collect()
+}
+
+/// Titel ohne Klammerzusätze und `- …`-Suffixe (Live, Remaster, Edit, …), kleingeschrieben.
+fn base_title(name: &str) -> String {
+    let mut title = String::new();
+    let mut depth = 0usize;
+    for ch in name.chars() {
+        match ch {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = depth.saturating_sub(1),
+            _ if depth == 0 => title.push(ch),
+            _ => {}
+        }
+    }
+    let title = title.split(" - ").next().unwrap_or_default();
+    title
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+fn is_karaoke(name: &str) -> bool {
+    let name = name.to_lowercase();
+    name.contains("karaoke") || name.contains("originally performed by")
+}
+
+fn build_query(track: &Track) -> Option<String> {
+    let name = non_empty(track.name.as_deref())?;
 
-    let query = if let Some(artist) = artists {
+    let query = if let Some(artist) = track.artist_names().next() {
         format!("{artist} {name}")
     } else {
         name.into()
@@ -94,38 +261,31 @@ fn build_query(track: &Value) -> Option<String> {
     Some(query)
 }
 
-fn build_track(track: &Value) -> Option<SimilarTrack> {
-    let uri = track.get("uri").and_then(Value::as_str)?.into();
+/// Einheitliche Track-Darstellung aller Endpunkte; `None` ohne Namen.
+pub(crate) fn build_track(track: &Track) -> Option<TrackInfo> {
+    let name = non_empty(track.name.as_deref())?.into();
+    Some(track_info(track, name))
+}
 
-    // Name MUSS existieren und nicht leer sein
-    let name = track
-        .get("name")
-        .and_then(Value::as_str)
-        .map(str::trim)
-        .filter(|s| !s.is_empty())?
-        .into();
-    let album = track
-        .get("album")
-        .and_then(|album| album.get("name"))
-        .and_then(Value::as_str)
-        .map(Into::into);
-    let artists = track
-        .get("artists")
-        .and_then(Value::as_array)
-        .map(|arr| {
-            arr.iter()
-                .filter_map(|artist| artist.get("name").and_then(Value::as_str))
-                .map(Into::into)
-                .collect()
-        })
-        .unwrap_or_default();
+/// Wie `build_track`, aber mit vorgegebenem Namen (z. B. URI als Ersatz).
+pub(crate) fn track_info(track: &Track, name: String) -> TrackInfo {
+    let album = track.album.as_ref().and_then(|album| album.name.clone());
+    let date = track
+        .date
+        .clone()
+        .or_else(|| track.album.as_ref().and_then(|album| album.date.clone()));
 
-    Some(SimilarTrack {
-        uri,
+    TrackInfo {
+        uri: track.uri.clone(),
         name,
         album,
-        artists,
-    })
+        artists: track.artist_names().map(Into::into).collect(),
+        duration_ms: track.length,
+        track_no: track.track_no,
+        disc_no: track.disc_no,
+        date,
+        bitrate: track.bitrate,
+    }
 }
 
 #[cfg(test)]
@@ -139,6 +299,7 @@ mod tests {
     struct StubMopidy {
         lookup: Option<Value>,
         search: Vec<Value>,
+        fields: Vec<(Value, Vec<Value>)>,
         queries: Arc<Mutex<Vec<String>>>,
     }
 
@@ -147,9 +308,16 @@ mod tests {
             Self {
                 lookup,
                 search,
+                fields: Vec::new(),
                 queries: Arc::new(Mutex::new(Vec::new())),
             }
         }
+
+        /// Antwort für eine bestimmte Feldsuche; sonst greift `search`.
+        fn with_field_search(mut self, query: Value, tracks: Value) -> Self {
+            self.fields.push((query, vec![json!({ "tracks": tracks })]));
+            self
+        }
     }
 
     #[async_trait]
@@ -158,13 +326,42 @@ mod tests {
             unreachable!("proxy should not be invoked directly in tests");
         }
 
-        async fn lookup_track(&self, _uri: &str) -> Result<Option<Value>, AppError> {
-            Ok(self.lookup.clone())
+        async fn lookup_track(&self, _uri: &str) -> Result<Option<Track>, AppError> {
+            Ok(self.lookup.clone().and_then(types::decode))
+        }
+
+        async fn search_any(&self, query: &str) -> Result<Vec<SearchResult>, AppError> {
+            self.queries.lock().unwrap().push(query.to_string());
+            Ok(decode_list(Value::Array(self.search.clone())))
         }
 
-        async fn search_any(&self, query: &str) -> Result<Vec<Value>, AppError> {
+        async fn search(
+            &self,
+            query: Value,
+            _uris: &[String],
+            _exact: bool,
+        ) -> Result<Vec<SearchResult>, AppError> {
             self.queries.lock().unwrap().push(query.to_string());
-            Ok(self.search.clone())
+            let results = self
+                .fields
+                .iter()
+                .find(|(fields, _)| *fields == query)
+                .map_or_else(|| self.search.clone(), |(_, results)| results.clone());
+            Ok(decode_list(Value::Array(results)))
+        }
+
+        async fn call_batch(
+            &self,
+            calls: &[(&str, Option<Value>)],
+        ) -> Result<Vec<Value>, AppError> {
+            let mut results = Vec::new();
+            for (method, params) in calls {
+                assert_eq!(*method, "core.library.search");
+                let params = params.clone().unwrap_or_default();
+                let found = self.search(params["query"].clone(), &[], false).await?;
+                results.push(serde_json::to_value(found).unwrap());
+            }
+            Ok(results)
         }
     }
 
@@ -177,9 +374,15 @@ mod tests {
         });
         let mopidy = StubMopidy::new(Some(seed), vec![json!({"tracks": []})]);
 
-        let response = similar_tracks(&mopidy, "qobuz:track:seed", Some(0))
-            .await
-            .expect("response");
+        let response = similar_tracks(
+            &mopidy,
+            "qobuz:track:seed",
+            Some(0),
+            &SimilarStrategy::ALL,
+            false,
+        )
+        .await
+        .expect("response");
 
         assert!(response.tracks.is_empty());
         assert_eq!(response.query, "Artist Seed");
@@ -225,17 +428,23 @@ mod tests {
         });
         let mopidy = StubMopidy::new(Some(seed_track), vec![results]);
 
-        let response = similar_tracks(&mopidy, "qobuz:track:seed", Some(10))
-            .await
-            .expect("response");
+        let response = similar_tracks(
+            &mopidy,
+            "qobuz:track:seed",
+            Some(10),
+            &SimilarStrategy::ALL,
+            false,
+        )
+        .await
+        .expect("response");
 
         let uris: Vec<_> = response
             .tracks
             .iter()
-            .map(|track| track.uri.as_str())
+            .map(|hit| hit.track.uri.as_str())
             .collect();
         assert_eq!(uris, vec!["qobuz:track:1", "qobuz:track:2"]);
-        assert_eq!(response.tracks[1].album.as_deref(), Some("Album"));
+        assert_eq!(response.tracks[1].track.album.as_deref(), Some("Album"));
     }
 
     #[tokio::test]
@@ -270,15 +479,193 @@ mod tests {
         });
         let mopidy = StubMopidy::new(Some(seed_track), vec![backend1, backend2]);
 
-        let response = similar_tracks(&mopidy, "qobuz:track:seed", Some(10))
-            .await
-            .expect("response");
+        let response = similar_tracks(
+            &mopidy,
+            "qobuz:track:seed",
+            Some(10),
+            &SimilarStrategy::ALL,
+            false,
+        )
+        .await
+        .expect("response");
 
         let uris: Vec<_> = response
             .tracks
             .iter()
-            .map(|track| track.uri.as_str())
+            .map(|hit| hit.track.uri.as_str())
             .collect();
         assert_eq!(uris, vec!["qobuz:track:1", "qobuz:track:2"]);
     }
+
+    #[tokio::test]
+    async fn similar_tracks_merges_strategy_scores() {
+        let seed = json!({
+            "uri": "qobuz:track:seed",
+            "name": "Seed",
+            "artists": [{"name": "Artist"}, {"name": "Guest"}],
+            "album": {"name": "Seed Album"}
+        });
+        let mopidy = StubMopidy::new(Some(seed), vec![json!({"tracks": []})])
+            .with_field_search(
+                json!({"artist": ["Artist"]}),
+                json!([
+                    {"uri": "qobuz:track:same-album", "name": "Sibling", "album": {"name": "Seed Album"}},
+                    {"uri": "qobuz:track:other", "name": "Other", "album": {"name": "Other Album"}}
+                ]),
+            )
+            .with_field_search(
+                json!({"artist": ["Guest"]}),
+                json!([
+                    {"uri": "qobuz:track:guest", "name": "Guest Song"},
+                    {"uri": "qobuz:track:other", "name": "Other", "album": {"name": "Other Album"}}
+                ]),
+            )
+            .with_field_search(
+                json!({"album": ["Seed Album"], "artist": ["Artist"]}),
+                json!([
+                    {"uri": "qobuz:track:same-album", "name": "Sibling", "album": {"name": "Seed Album"}}
+                ]),
+            );
+
+        let strategies = parse_strategies(Some("album,artist,co_artist")).expect("strategies");
+        let response = similar_tracks(&mopidy, "qobuz:track:seed", Some(10), &strategies, false)
+            .await
+            .expect("response");
+
+        let ranked: Vec<_> = response
+            .tracks
+            .iter()
+            .map(|hit| (hit.track.uri.as_str(), hit.score))
+            .collect();
+        assert_eq!(
+            ranked,
+            vec![
+                ("qobuz:track:other", 1.8),
+                ("qobuz:track:guest", 0.8),
+                ("qobuz:track:same-album", 0.6),
+            ]
+        );
+        assert_eq!(
+            response.tracks[0]
+                .scores
+                .keys()
+                .copied()
+                .collect::<Vec<_>>(),
+            vec![SimilarStrategy::Artist, SimilarStrategy::CoArtist]
+        );
+    }
+
+    #[tokio::test]
+    async fn similar_tracks_searches_genre_and_year_separately() {
+        let seed = json!({
+            "uri": "qobuz:track:seed",
+            "name": "Seed",
+            "genre": "Jazz",
+            "date": "1959-08-17",
+            "artists": [{"name": "Artist"}]
+        });
+        let mopidy = StubMopidy::new(Some(seed), vec![json!({"tracks": []})])
+            .with_field_search(
+                json!({"genre": ["Jazz"]}),
+                json!([
+                    {"uri": "qobuz:track:both", "name": "Both"},
+                    {"uri": "qobuz:track:genre", "name": "Genre Only"}
+                ]),
+            )
+            .with_field_search(
+                json!({"date": ["1959"]}),
+                json!([
+                    {"uri": "qobuz:track:both", "name": "Both"},
+                    {"uri": "qobuz:track:year", "name": "Year Only"}
+                ]),
+            );
+        let strategies = [SimilarStrategy::Genre, SimilarStrategy::Year];
+
+        let response = similar_tracks(&mopidy, "qobuz:track:seed", Some(10), &strategies, false)
+            .await
+            .expect("response");
+
+        let ranked: Vec<_> = response
+            .tracks
+            .iter()
+            .map(|hit| (hit.track.uri.as_str(), hit.score))
+            .collect();
+        assert_eq!(
+            ranked,
+            vec![
+                ("qobuz:track:both", 0.7),
+                ("qobuz:track:genre", 0.5),
+                ("qobuz:track:year", 0.2),
+            ]
+        );
+
+        // Ohne Genre entfällt die Genre-Suche, statt nur nach dem Jahr zu suchen.
+        let seed = json!({
+            "uri": "qobuz:track:seed",
+            "name": "Seed",
+            "date": "1959",
+            "artists": [{"name": "Artist"}]
+        });
+        let mopidy = StubMopidy::new(Some(seed), vec![json!({"tracks": []})]);
+        similar_tracks(
+            &mopidy,
+            "qobuz:track:seed",
+            Some(10),
+            &[SimilarStrategy::Genre],
+            false,
+        )
+        .await
+        .expect("response");
+        assert!(mopidy.queries.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn similar_tracks_filters_title_variants() {
+        let seed = json!({
+            "uri": "qobuz:track:seed",
+            "name": "Seed Song",
+            "artists": [{"name": "Artist"}]
+        });
+        let results = json!({
+            "tracks": [
+                {"uri": "qobuz:track:live", "name": "Seed Song (Live)", "artists": [{"name": "Artist"}]},
+                {"uri": "qobuz:track:karaoke", "name": "Other Song (Karaoke Version)", "artists": [{"name": "Artist"}]},
+                {"uri": "qobuz:track:1", "name": "Other Song", "artists": [{"name": "Artist"}]},
+                {"uri": "qobuz:track:2", "name": "Other Song - Remastered 2011", "artists": [{"name": "Artist"}]}
+            ]
+        });
+        let mopidy = StubMopidy::new(Some(seed), vec![results]);
+        let strategies = [SimilarStrategy::Query];
+
+        let filtered = similar_tracks(&mopidy, "qobuz:track:seed", Some(10), &strategies, false)
+            .await
+            .expect("response");
+        let all = similar_tracks(&mopidy, "qobuz:track:seed", Some(10), &strategies, true)
+            .await
+            .expect("response");
+
+        let uris: Vec<_> = filtered
+            .tracks
+            .iter()
+            .map(|t| t.track.uri.as_str())
+            .collect();
+        assert_eq!(uris, vec!["qobuz:track:1"]);
+        assert_eq!(all.tracks.len(), 4);
+    }
+
+    #[test]
+    fn parse_strategies_defaults_and_rejects_unknown() {
+        assert_eq!(
+            parse_strategies(None).unwrap(),
+            SimilarStrategy::ALL.to_vec()
+        );
+        assert_eq!(
+            parse_strategies(Some("genre, artist,genre")).unwrap(),
+            vec![SimilarStrategy::Genre, SimilarStrategy::Artist]
+        );
+        assert!(matches!(
+            parse_strategies(Some("mood")),
+            Err(AppError::BadRequest(_))
+        ));
+    }
 }
@@ -1,8 +1,10 @@
 use crate::config::ConfigError;
-use axum::http::StatusCode;
+use crate::scripts::runner::ScriptOutput;
+use axum::http::{header, StatusCode};
 use axum::response::{IntoResponse, Response};
 use axum::Json;
 use serde_json::json;
+use std::time::Duration;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -15,8 +17,17 @@ pub enum AppError {
     Command(#[from] std::io::Error),
     #[error("an unexpected error occurred: {0}")]
     Anyhow(#[from] anyhow::Error),
-    #[error("command returned non-zero exit code: {0}")]
-    CommandStatus(String),
+    #[error("script {script} timed out after {timeout:?}")]
+    ScriptTimeout { script: String, timeout: Duration },
+    #[error(
+        "script {} exited with {}: {}",
+        .0.script,
+        .0.exit_code.map_or_else(|| "signal".to_string(), |code| code.to_string()),
+        .0.stderr.trim()
+    )]
+    ScriptFailed(Box<ScriptOutput>),
+    #[error("script {script} was cancelled")]
+    ScriptCancelled { script: String },
     #[error("configuration validation failed: {0}")]
     Validation(String),
     #[error("startup failed: {0}")]
@@ -24,6 +35,14 @@ pub enum AppError {
     #[error("{0}")]
     BadRequest(String),
     #[error("{0}")]
+    Unauthorized(String),
+    #[error("{0}")]
+    Forbidden(String),
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    Conflict(String),
+    #[error("{0}")]
     Upstream(String),
     #[error("{0}")]
     Internal(String),
@@ -34,6 +53,22 @@ impl AppError {
         Self::BadRequest(message.into())
     }
 
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        Self::Unauthorized(message.into())
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        Self::Forbidden(message.into())
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::NotFound(message.into())
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::Conflict(message.into())
+    }
+
     pub fn upstream(message: impl Into<String>) -> Self {
         Self::Upstream(message.into())
     }
@@ -47,14 +82,33 @@ impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         let status = match &self {
             AppError::Validation(_) | AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
-            AppError::Mopidy(_) | AppError::Upstream(_) => StatusCode::BAD_GATEWAY,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::Mopidy(_) | AppError::Upstream(_) | AppError::ScriptFailed(_) => {
+                StatusCode::BAD_GATEWAY
+            }
+            AppError::ScriptTimeout { .. } => StatusCode::GATEWAY_TIMEOUT,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         };
 
-        let payload = json!({
+        let mut payload = json!({
             "error": self.to_string(),
         });
+        // Skriptdetails (stdout/stderr, Exit-Code, Laufzeit) für die Fehlersuche mitliefern.
+        if let AppError::ScriptFailed(output) = &self {
+            payload["script"] = json!(output);
+        }
 
+        if status == StatusCode::UNAUTHORIZED {
+            return (
+                status,
+                [(header::WWW_AUTHENTICATE, "Bearer")],
+                Json(payload),
+            )
+                .into_response();
+        }
         (status, Json(payload)).into_response()
     }
 }
@@ -0,0 +1,445 @@
+//! Bridge für Mopidys WebSocket-Eventstream (`/mopidy/ws`).
+//!
+//! Die Verbindung wird erst beim ersten Abonnenten aufgebaut, danach bei
+//! Abbrüchen mit exponentiellem Backoff erneuert; ein Reload mit neuer
+//! Mopidy-Adresse baut sie sofort neu auf. Events landen typisiert in
+//! einem Broadcast-Kanal, aus dem `GET /events` (SSE) verteilt.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tokio::time;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, info, warn};
+use url::Url;
+
+use crate::models::{PlaybackState, QueueEntry};
+use crate::mopidy::types;
+use crate::queue::build_entry;
+
+const CHANNEL_CAPACITY: usize = 256;
+const DEFAULT_MIN_BACKOFF: Duration = Duration::from_millis(500);
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum PlayerEvent {
+    TrackPlaybackStarted {
+        track: Option<QueueEntry>,
+    },
+    TrackPlaybackPaused {
+        track: Option<QueueEntry>,
+        time_position: u64,
+    },
+    TrackPlaybackResumed {
+        track: Option<QueueEntry>,
+        time_position: u64,
+    },
+    TrackPlaybackEnded {
+        track: Option<QueueEntry>,
+        time_position: u64,
+    },
+    PlaybackStateChanged {
+        old_state: PlaybackState,
+        new_state: PlaybackState,
+    },
+    Seeked {
+        time_position: u64,
+    },
+    TracklistChanged,
+    OptionsChanged,
+    VolumeChanged {
+        volume: u64,
+    },
+    MuteChanged {
+        mute: bool,
+    },
+    StreamTitleChanged {
+        title: String,
+    },
+    PlaylistsLoaded,
+    PlaylistChanged {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        uri: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+    },
+    PlaylistDeleted {
+        uri: String,
+    },
+    /// Verbindungsstatus der Bridge selbst (kein Mopidy-Event).
+    Connection {
+        connected: bool,
+    },
+    /// Unbekannte oder unvollständige Mopidy-Events, unverändert durchgereicht.
+    Other {
+        name: String,
+        payload: Value,
+    },
+}
+
+impl PlayerEvent {
+    /// Name für das SSE-`event:`-Feld (bei `Other` der Original-Eventname).
+    #[must_use]
+    pub fn name(&self) -> &str {
+        match self {
+            PlayerEvent::TrackPlaybackStarted { .. } => "track_playback_started",
+            PlayerEvent::TrackPlaybackPaused { .. } => "track_playback_paused",
+            PlayerEvent::TrackPlaybackResumed { .. } => "track_playback_resumed",
+            PlayerEvent::TrackPlaybackEnded { .. } => "track_playback_ended",
+            PlayerEvent::PlaybackStateChanged { .. } => "playback_state_changed",
+            PlayerEvent::Seeked { .. } => "seeked",
+            PlayerEvent::TracklistChanged => "tracklist_changed",
+            PlayerEvent::OptionsChanged => "options_changed",
+            PlayerEvent::VolumeChanged { .. } => "volume_changed",
+            PlayerEvent::MuteChanged { .. } => "mute_changed",
+            PlayerEvent::StreamTitleChanged { .. } => "stream_title_changed",
+            PlayerEvent::PlaylistsLoaded => "playlists_loaded",
+            PlayerEvent::PlaylistChanged { .. } => "playlist_changed",
+            PlayerEvent::PlaylistDeleted { .. } => "playlist_deleted",
+            PlayerEvent::Connection { .. } => "connection",
+            PlayerEvent::Other { name, .. } => name,
+        }
+    }
+}
+
+/// Übersetzt eine Mopidy-Eventnachricht; JSON-RPC-Antworten (ohne `event`) ergeben `None`.
+#[must_use]
+pub fn parse_event(raw: &Value) -> Option<PlayerEvent> {
+    let name = raw.get("event").and_then(Value::as_str)?;
+    let track = || {
+        raw.get("tl_track")
+            .cloned()
+            .and_then(types::decode)
+            .as_ref()
+            .map(build_entry)
+    };
+    let time_position = || raw.get("time_position").and_then(Value::as_u64);
+    let state = |key: &str| {
+        raw.get(key)
+            .cloned()
+            .and_then(|value| serde_json::from_value::<PlaybackState>(value).ok())
+    };
+    let string = |key: &str| raw.get(key).and_then(Value::as_str).map(String::from);
+
+    let typed = match name {
+        "track_playback_started" => Some(PlayerEvent::TrackPlaybackStarted { track: track() }),
+        "track_playback_paused" => {
+            time_position().map(|time_position| PlayerEvent::TrackPlaybackPaused {
+                track: track(),
+                time_position,
+            })
+        }
+        "track_playback_resumed" => {
+            time_position().map(|time_position| PlayerEvent::TrackPlaybackResumed {
+                track: track(),
+                time_position,
+            })
+        }
+        "track_playback_ended" => {
+            time_position().map(|time_position| PlayerEvent::TrackPlaybackEnded {
+                track: track(),
+                time_position,
+            })
+        }
+        "playback_state_changed" => {
+            state("old_state")
+                .zip(state("new_state"))
+                .map(|(old_state, new_state)| PlayerEvent::PlaybackStateChanged {
+                    old_state,
+                    new_state,
+                })
+        }
+        "seeked" => time_position().map(|time_position| PlayerEvent::Seeked { time_position }),
+        "tracklist_changed" => Some(PlayerEvent::TracklistChanged),
+        "options_changed" => Some(PlayerEvent::OptionsChanged),
+        "volume_changed" => raw
+            .get("volume")
+            .and_then(Value::as_u64)
+            .map(|volume| PlayerEvent::VolumeChanged { volume }),
+        "mute_changed" => raw
+            .get("mute")
+            .and_then(Value::as_bool)
+            .map(|mute| PlayerEvent::MuteChanged { mute }),
+        "stream_title_changed" => {
+            string("title").map(|title| PlayerEvent::StreamTitleChanged { title })
+        }
+        "playlists_loaded" => Some(PlayerEvent::PlaylistsLoaded),
+        "playlist_changed" => {
+            let playlist = raw.get("playlist");
+            let field = |key: &str| {
+                playlist
+                    .and_then(|playlist| playlist.get(key))
+                    .and_then(Value::as_str)
+                    .map(String::from)
+            };
+            Some(PlayerEvent::PlaylistChanged {
+                uri: field("uri"),
+                name: field("name"),
+            })
+        }
+        "playlist_deleted" => string("uri").map(|uri| PlayerEvent::PlaylistDeleted { uri }),
+        _ => None,
+    };
+
+    Some(typed.unwrap_or_else(|| {
+        let mut payload = raw.clone();
+        if let Some(object) = payload.as_object_mut() {
+            object.remove("event");
+        }
+        PlayerEvent::Other {
+            name: name.into(),
+            payload,
+        }
+    }))
+}
+
+pub struct EventBridge {
+    url: Mutex<Url>,
+    sender: broadcast::Sender<PlayerEvent>,
+    /// Laufende Verbindungsschleife; `None`, bis der erste Abonnent kommt.
+    task: Mutex<Option<JoinHandle<()>>>,
+    min_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl EventBridge {
+    #[must_use]
+    pub fn new(url: Url) -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self {
+            url: Mutex::new(url),
+            sender,
+            task: Mutex::new(None),
+            min_backoff: DEFAULT_MIN_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+        }
+    }
+
+    #[must_use]
+    pub fn with_backoff(mut self, min: Duration, max: Duration) -> Self {
+        self.min_backoff = min;
+        self.max_backoff = max.max(min);
+        self
+    }
+
+    #[must_use]
+    pub fn url(&self) -> Url {
+        lock(&self.url).clone()
+    }
+
+    /// Abonniert den Eventstrom und startet beim ersten Aufruf die WebSocket-Verbindung.
+    /// Muss innerhalb einer Tokio-Runtime aufgerufen werden.
+    pub fn subscribe(&self) -> broadcast::Receiver<PlayerEvent> {
+        let receiver = self.sender.subscribe();
+        let mut task = lock(&self.task);
+        if task.is_none() {
+            *task = Some(self.spawn(self.url()));
+        }
+        receiver
+    }
+
+    /// Wechselt auf eine neue Mopidy-Adresse (Reload). Eine bestehende Verbindung
+    /// wird abgebrochen und neu aufgebaut; Abonnenten bleiben am selben Kanal.
+    pub fn set_url(&self, url: Url) {
+        {
+            let mut current = lock(&self.url);
+            if *current == url {
+                return;
+            }
+            info!("Mopidy events now at {url}");
+            current.clone_from(&url);
+        }
+        let mut task = lock(&self.task);
+        if let Some(running) = task.take() {
+            running.abort();
+            let _ = self
+                .sender
+                .send(PlayerEvent::Connection { connected: false });
+            *task = Some(self.spawn(url));
+        }
+    }
+
+    fn spawn(&self, url: Url) -> JoinHandle<()> {
+        tokio::spawn(run(
+            url,
+            self.sender.clone(),
+            self.min_backoff,
+            self.max_backoff,
+        ))
+    }
+}
+
+/// Beendet die Verbindungsschleife mit der Bridge; sonst hielte sie ihren
+/// Sender-Klon und verbände sich ohne mögliche Abonnenten endlos neu.
+impl Drop for EventBridge {
+    fn drop(&mut self) {
+        if let Some(task) = lock(&self.task).take() {
+            task.abort();
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+async fn run(
+    url: Url,
+    sender: broadcast::Sender<PlayerEvent>,
+    min_backoff: Duration,
+    max_backoff: Duration,
+) {
+    let mut delay = min_backoff;
+    loop {
+        match tokio_tungstenite::connect_async(url.as_str()).await {
+            Ok((mut stream, _)) => {
+                info!("connected to Mopidy events at {url}");
+                delay = min_backoff;
+                let _ = sender.send(PlayerEvent::Connection { connected: true });
+
+                while let Some(message) = stream.next().await {
+                    match message {
+                        Ok(Message::Text(text)) => {
+                            let Ok(raw) = serde_json::from_str::<Value>(&text) else {
+                                debug!("ignoring non-JSON message from Mopidy");
+                                continue;
+                            };
+                            if let Some(event) = parse_event(&raw) {
+                                // Ohne Abonnenten schlägt send fehl – das ist kein Fehler.
+                                let _ = sender.send(event);
+                            }
+                        }
+                        Ok(Message::Close(_)) => break,
+                        Ok(_) => {}
+                        Err(err) => {
+                            warn!("Mopidy event stream failed: {err}");
+                            break;
+                        }
+                    }
+                }
+
+                warn!("Mopidy event stream closed; reconnecting in {delay:?}");
+                let _ = sender.send(PlayerEvent::Connection { connected: false });
+            }
+            Err(err) => {
+                warn!("failed to connect to Mopidy events at {url}: {err}; retrying in {delay:?}");
+            }
+        }
+
+        time::sleep(delay).await;
+        delay = (delay * 2).min(max_backoff);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::SinkExt;
+    use serde_json::json;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn parse_event_maps_known_events() {
+        let event = parse_event(&json!({
+            "event": "playback_state_changed",
+            "old_state": "stopped",
+            "new_state": "playing"
+        }));
+        assert_eq!(
+            event,
+            Some(PlayerEvent::PlaybackStateChanged {
+                old_state: PlaybackState::Stopped,
+                new_state: PlaybackState::Playing,
+            })
+        );
+
+        let event = parse_event(&json!({
+            "event": "track_playback_started",
+            "tl_track": {
+                "__model__": "TlTrack",
+                "tlid": 4,
+                "track": {"uri": "qobuz:track:1", "name": "One"}
+            }
+        }))
+        .expect("event");
+        let PlayerEvent::TrackPlaybackStarted { track: Some(entry) } = event else {
+            panic!("unexpected event: {event:?}");
+        };
+        assert_eq!(entry.tlid, 4);
+        assert_eq!(entry.track.uri, "qobuz:track:1");
+    }
+
+    #[test]
+    fn parse_event_passes_unknown_events_through() {
+        let event = parse_event(&json!({"event": "custom_thing", "value": 1})).expect("event");
+
+        assert_eq!(event.name(), "custom_thing");
+        assert_eq!(
+            event,
+            PlayerEvent::Other {
+                name: "custom_thing".into(),
+                payload: json!({"value": 1}),
+            }
+        );
+        assert!(parse_event(&json!({"jsonrpc": "2.0", "id": 1, "result": null})).is_none());
+    }
+
+    #[tokio::test]
+    async fn bridge_reconnects_after_close() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        // Stub-Server: pro Verbindung ein Event senden und schließen
+        tokio::spawn(async move {
+            for volume in [10u64, 20] {
+                let (tcp, _) = listener.accept().await.unwrap();
+                let mut ws = tokio_tungstenite::accept_async(tcp).await.unwrap();
+                let message = json!({"event": "volume_changed", "volume": volume}).to_string();
+                ws.send(Message::Text(message.into())).await.unwrap();
+                ws.close(None).await.unwrap();
+            }
+        });
+
+        let url = Url::parse(&format!("ws://{addr}/mopidy/ws")).unwrap();
+        let bridge = EventBridge::new(url)
+            .with_backoff(Duration::from_millis(10), Duration::from_millis(20));
+        let mut receiver = bridge.subscribe();
+
+        let mut volumes = Vec::new();
+        while volumes.len() < 2 {
+            let event = time::timeout(Duration::from_secs(5), receiver.recv())
+                .await
+                .expect("event in time")
+                .expect("channel open");
+            if let PlayerEvent::VolumeChanged { volume } = event {
+                volumes.push(volume);
+            }
+        }
+        assert_eq!(volumes, vec![10, 20]);
+    }
+
+    #[tokio::test]
+    async fn dropping_bridge_stops_reconnecting() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let url = Url::parse(&format!("ws://{addr}/mopidy/ws")).unwrap();
+        let bridge = EventBridge::new(url)
+            .with_backoff(Duration::from_millis(10), Duration::from_millis(20));
+        let _receiver = bridge.subscribe();
+
+        // Erste Verbindung annehmen und sofort schließen, dann die Bridge verwerfen
+        let (tcp, _) = listener.accept().await.unwrap();
+        drop(tcp);
+        drop(bridge);
+
+        let reconnect = time::timeout(Duration::from_millis(200), listener.accept()).await;
+        assert!(reconnect.is_err(), "bridge reconnected after drop");
+    }
+}
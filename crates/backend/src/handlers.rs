@@ -1,47 +1,107 @@
-use axum::extract::{Query, State};
-use axum::routing::{get, post};
+use std::convert::Infallible;
+
+use axum::body::Bytes;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::middleware;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post, put};
 use axum::{Json, Router};
-use serde_json::Value;
+use futures_util::{Stream, StreamExt};
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+use tokio_stream::wrappers::BroadcastStream;
 use tower_http::trace::TraceLayer;
-use tracing::instrument;
+use tracing::{instrument, warn};
 
 use crate::error::AppError;
+use crate::jobs::JobEvent;
+use crate::metrics::METRICS;
 use crate::models::{
-    CommandResponse, HealthResponse, ModeGetResponse, ModeSetRequest, MopidyHealth,
-    PlaylistRequest, PlaylistResponse, SimilarQuery, SimilarResponse,
+    ActionInfo, ActionRequest, AudioMode, HealthResponse, JobRequest, JobStatus,
+    LibraryBrowseQuery, LibraryBrowseResponse, LibraryImagesResponse, LibraryLookupResponse,
+    LibraryUrisQuery, ModeGetResponse, ModeSetRequest, ModeSwitchReport, MopidyHealth, MuteRequest,
+    MuteStatus, PlayRequest, PlaybackStatus, PlaylistAppendRequest, PlaylistDeleteQuery,
+    PlaylistDeleteResponse, PlaylistRequest, PlaylistResponse, QueueAddRequest, QueueAddResponse,
+    QueueMoveRequest, QueueOptions, QueueOptionsUpdate, QueueRemoveRequest, QueueRemoveResponse,
+    QueueResponse, QueueShuffleRequest, RecordingStartRequest, RecordingStartResponse,
+    RecordingStatus, RecordingStopRequest, RecordingStopResponse, ReloadReport, SearchQuery,
+    SearchResponse, SeekRequest, SimilarQuery, SimilarResponse, VolumeRequest, VolumeStatus,
+};
+use crate::scripts::runner::ScriptOutput;
+use crate::{
+    actions, auth, discover, library, metrics, playlists, queue, rpc, search, validation, AppState,
 };
-use crate::{discover, scripts, validation, AppState};
 
 pub fn app_routes(state: AppState) -> Router {
     Router::new()
         .route("/health", get(health))
         .route("/rpc", post(proxy_rpc))
         .route("/mode", get(get_mode).post(set_mode))
+        .route("/playlists", delete(playlist_delete))
         .route("/playlists/from-list", post(playlist_from_list))
+        .route("/playlists/append", post(playlist_append))
         .route("/discover/similar", get(discover_similar))
+        .route("/library/browse", get(library_browse))
+        .route("/library/lookup", get(library_lookup))
+        .route("/library/images", get(library_images))
+        .route("/search", get(library_search))
+        .route("/playback", get(playback_status))
+        .route("/playback/play", post(playback_play))
+        .route("/playback/pause", post(playback_pause))
+        .route("/playback/resume", post(playback_resume))
+        .route("/playback/stop", post(playback_stop))
+        .route("/playback/next", post(playback_next))
+        .route("/playback/previous", post(playback_previous))
+        .route("/playback/seek", post(playback_seek))
+        .route(
+            "/queue",
+            get(queue_list).post(queue_add).delete(queue_clear),
+        )
+        .route("/queue/remove", post(queue_remove))
+        .route("/queue/move", post(queue_move))
+        .route("/queue/shuffle", post(queue_shuffle))
+        .route("/queue/options", put(queue_set_options))
+        .route("/volume", get(volume_status).put(volume_set))
+        .route("/mute", get(mute_status).put(mute_set))
+        .route("/recording", get(recording_status))
+        .route("/recording/start", post(recording_start))
+        .route("/recording/stop", post(recording_stop))
+        .route("/events", get(events))
+        .route("/metrics", get(metrics))
+        .route("/admin/reload", post(admin_reload))
+        .route("/actions", get(action_list))
+        .route("/actions/{name}", post(action_run))
+        .route("/jobs", post(job_start))
+        .route("/jobs/{id}", get(job_status).delete(job_cancel))
+        .route("/jobs/{id}/stream", get(job_stream))
+        .route_layer(middleware::from_fn_with_state(state.clone(), auth::require))
+        .route_layer(middleware::from_fn(metrics::track))
         .with_state(state)
         .layer(TraceLayer::new_for_http())
 }
 
 #[instrument(skip(state))]
 pub async fn health(State(state): State<AppState>) -> Result<Json<HealthResponse>, AppError> {
-    let (overall_status, mopidy_status) = if state.config.check_mopidy_health {
-        match state.mopidy.health_check().await {
-            Ok(()) => (
-                "ok",
-                Some(MopidyHealth {
-                    status: "ok".into(),
-                    detail: None,
-                }),
-            ),
-            Err(err) => (
-                "degraded",
-                Some(MopidyHealth {
-                    status: "error".into(),
-                    detail: Some(err),
-                }),
-            ),
-        }
+    let (overall_status, mopidy_status) = if state.config().check_mopidy_health {
+        let mopidy = state.mopidy();
+        let (overall, status, detail) = match mopidy.health_check().await {
+            Ok(()) => ("ok", "ok", None),
+            Err(err) => ("degraded", "error", Some(err)),
+        };
+        // Nach dem Check, damit eine gelungene Probe schon „closed“ meldet.
+        let circuit = mopidy.circuit();
+        (
+            overall,
+            Some(MopidyHealth {
+                status: status.into(),
+                detail,
+                circuit: circuit.map(|(state, _)| state),
+                retry_after_ms: circuit
+                    .and_then(|(_, wait)| wait)
+                    .map(|wait| u64::try_from(wait.as_millis()).unwrap_or(u64::MAX)),
+            }),
+        )
     } else {
         ("ok", None)
     };
@@ -53,54 +113,42 @@ pub async fn health(State(state): State<AppState>) -> Result<Json<HealthResponse
     }))
 }
 
-#[instrument(skip(state, payload))]
-pub async fn proxy_rpc(
-    State(state): State<AppState>,
-    Json(payload): Json<Value>,
-) -> Result<Json<Value>, AppError> {
-    let response = state.mopidy.proxy(payload).await?;
-    Ok(Json(response))
+#[instrument(skip(state, body))]
+pub async fn proxy_rpc(State(state): State<AppState>, body: Bytes) -> Result<Response, AppError> {
+    let response = match rpc::handle(&state.config().rpc, &*state.mopidy(), &body).await? {
+        Some(response) => Json(response).into_response(),
+        None => StatusCode::NO_CONTENT.into_response(),
+    };
+    Ok(response)
 }
 
 #[instrument(skip(state))]
 pub async fn get_mode(State(state): State<AppState>) -> Result<Json<ModeGetResponse>, AppError> {
-    let script_path = state
-        .config
-        .audio_mode_script
-        .resolve_with(&state.config.script_workdir);
-    let script_path_str = script_path
-        .to_str()
-        .ok_or_else(|| AppError::Internal("invalid UTF-8 path for audio_mode_script".into()))?;
-    let output =
-        scripts::runner::run_script(&state.config, script_path_str, &["show"], None).await?;
-    let trimmed = output.trim();
-    let inferred = crate::models::AudioMode::infer(trimmed);
+    let output = state.mode.current(&state.config()).await?;
+    let inferred = AudioMode::infer(&output);
 
     Ok(Json(ModeGetResponse {
-        value: trimmed.into(),
+        value: output,
         mode: inferred,
     }))
 }
 
+/// Fehlgeschlagene Wechsel liefern den Bericht mit `502` (nach Rollback).
 #[instrument(skip(state, body))]
 pub async fn set_mode(
     State(state): State<AppState>,
     Json(body): Json<ModeSetRequest>,
-) -> Result<Json<CommandResponse>, AppError> {
-    let script_path = state
-        .config
-        .audio_mode_script
-        .resolve_with(&state.config.script_workdir);
-    let script_path_str = script_path
-        .to_str()
-        .ok_or_else(|| AppError::Internal("invalid UTF-8 path for audio_mode_script".into()))?;
-    let output =
-        scripts::runner::run_script(&state.config, script_path_str, &[body.mode.as_str()], None)
-            .await?;
-    Ok(Json(CommandResponse {
-        stdout: output.trim().into(),
-        stderr: String::new(),
-    }))
+) -> Result<(StatusCode, Json<ModeSwitchReport>), AppError> {
+    let report = state
+        .mode
+        .switch(&state.config(), &*state.mopidy(), body.mode)
+        .await?;
+    let status = if report.verification.ok {
+        StatusCode::OK
+    } else {
+        StatusCode::BAD_GATEWAY
+    };
+    Ok((status, Json(report)))
 }
 
 #[instrument(skip(state, body))]
@@ -108,25 +156,26 @@ pub async fn playlist_from_list(
     State(state): State<AppState>,
     Json(body): Json<PlaylistRequest>,
 ) -> Result<Json<PlaylistResponse>, AppError> {
-    let script_path = state
-        .config
-        .playlist_script
-        .resolve_with(&state.config.script_workdir);
-    let script_path_str = script_path
-        .to_str()
-        .ok_or_else(|| AppError::Internal("invalid UTF-8 path for playlist_script".into()))?;
-    let uris = body.uris.join("\n");
-    let output = scripts::runner::run_script(
-        &state.config,
-        script_path_str,
-        &["--input", "-", "--", &body.name],
-        Some(&uris),
-    )
-    .await?;
-    Ok(Json(PlaylistResponse {
-        stdout: output.trim().into(),
-        stderr: String::new(),
-    }))
+    let response = playlists::from_list(&*state.mopidy(), &body).await?;
+    Ok(Json(response))
+}
+
+#[instrument(skip(state, body))]
+pub async fn playlist_append(
+    State(state): State<AppState>,
+    Json(body): Json<PlaylistAppendRequest>,
+) -> Result<Json<PlaylistResponse>, AppError> {
+    let response = playlists::append(&*state.mopidy(), &body).await?;
+    Ok(Json(response))
+}
+
+#[instrument(skip(state))]
+pub async fn playlist_delete(
+    State(state): State<AppState>,
+    Query(params): Query<PlaylistDeleteQuery>,
+) -> Result<Json<PlaylistDeleteResponse>, AppError> {
+    let response = playlists::delete(&*state.mopidy(), &params.uri).await?;
+    Ok(Json(response))
 }
 
 #[instrument(skip(state, params))]
@@ -137,7 +186,385 @@ pub async fn discover_similar(
     if !validation::is_allowed_uri(&params.seed) {
         return Err(AppError::bad_request("disallowed URI scheme"));
     }
-    let response = discover::similar_tracks(&*state.mopidy, &params.seed, params.limit).await?;
+    let strategies = discover::parse_strategies(params.strategy.as_deref())?;
+    let response = discover::similar_tracks(
+        &*state.mopidy(),
+        &params.seed,
+        params.limit,
+        &strategies,
+        params.variants,
+    )
+    .await?;
+
+    Ok(Json(response))
+}
+
+#[instrument(skip(state))]
+pub async fn library_browse(
+    State(state): State<AppState>,
+    Query(params): Query<LibraryBrowseQuery>,
+) -> Result<Json<LibraryBrowseResponse>, AppError> {
+    let response = library::browse(
+        &*state.mopidy(),
+        params.uri.as_deref(),
+        params.offset,
+        params.limit,
+    )
+    .await?;
+    Ok(Json(response))
+}
+
+#[instrument(skip(state))]
+pub async fn library_lookup(
+    State(state): State<AppState>,
+    Query(params): Query<LibraryUrisQuery>,
+) -> Result<Json<LibraryLookupResponse>, AppError> {
+    let response = library::lookup(&*state.mopidy(), &params.uris).await?;
+    Ok(Json(response))
+}
+
+#[instrument(skip(state))]
+pub async fn library_images(
+    State(state): State<AppState>,
+    Query(params): Query<LibraryUrisQuery>,
+) -> Result<Json<LibraryImagesResponse>, AppError> {
+    let response = library::images(&*state.mopidy(), &params.uris).await?;
+    Ok(Json(response))
+}
+
+#[instrument(skip(state))]
+pub async fn library_search(
+    State(state): State<AppState>,
+    Query(params): Query<SearchQuery>,
+) -> Result<Json<SearchResponse>, AppError> {
+    let response = search::search(&*state.mopidy(), &params).await?;
+    Ok(Json(response))
+}
+
+#[instrument(skip(state, body))]
+pub async fn recording_start(
+    State(state): State<AppState>,
+    Json(body): Json<RecordingStartRequest>,
+) -> Result<Json<RecordingStartResponse>, AppError> {
+    let response = state.recorder.start(&body).await?;
+    Ok(Json(response))
+}
+
+#[instrument(skip(state, body))]
+pub async fn recording_stop(
+    State(state): State<AppState>,
+    Json(body): Json<RecordingStopRequest>,
+) -> Result<Json<RecordingStopResponse>, AppError> {
+    let response = state.recorder.stop(&body).await?;
+    Ok(Json(response))
+}
+
+#[instrument(skip(state))]
+pub async fn recording_status(
+    State(state): State<AppState>,
+) -> Result<Json<RecordingStatus>, AppError> {
+    Ok(Json(state.recorder.status().await))
+}
+
+#[instrument(skip(state))]
+pub async fn playback_status(
+    State(state): State<AppState>,
+) -> Result<Json<PlaybackStatus>, AppError> {
+    let playback_state = state.mopidy().playback_state().await?;
+    let track = state
+        .mopidy()
+        .current_track()
+        .await?
+        .as_ref()
+        .and_then(discover::build_track);
+    let time_position = state.mopidy().time_position().await?;
+
+    Ok(Json(PlaybackStatus {
+        state: playback_state,
+        track,
+        time_position,
+    }))
+}
+
+#[instrument(skip(state, body))]
+pub async fn playback_play(
+    State(state): State<AppState>,
+    body: Option<Json<PlayRequest>>,
+) -> Result<StatusCode, AppError> {
+    let Json(body) = body.unwrap_or_default();
+    state.mopidy().play(body.tlid).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[instrument(skip(state))]
+pub async fn playback_pause(State(state): State<AppState>) -> Result<StatusCode, AppError> {
+    state.mopidy().pause().await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[instrument(skip(state))]
+pub async fn playback_resume(State(state): State<AppState>) -> Result<StatusCode, AppError> {
+    state.mopidy().resume().await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[instrument(skip(state))]
+pub async fn playback_stop(State(state): State<AppState>) -> Result<StatusCode, AppError> {
+    state.mopidy().stop().await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[instrument(skip(state))]
+pub async fn playback_next(State(state): State<AppState>) -> Result<StatusCode, AppError> {
+    state.mopidy().next().await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[instrument(skip(state))]
+pub async fn playback_previous(State(state): State<AppState>) -> Result<StatusCode, AppError> {
+    state.mopidy().previous().await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[instrument(skip(state, body))]
+pub async fn playback_seek(
+    State(state): State<AppState>,
+    Json(body): Json<SeekRequest>,
+) -> Result<StatusCode, AppError> {
+    if !state.mopidy().seek(body.position).await? {
+        return Err(AppError::conflict(
+            "Mopidy rejected seek (nothing playing?)",
+        ));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
 
+#[instrument(skip(state))]
+pub async fn queue_list(State(state): State<AppState>) -> Result<Json<QueueResponse>, AppError> {
+    let response = queue::snapshot(&*state.mopidy()).await?;
     Ok(Json(response))
 }
+
+#[instrument(skip(state, body))]
+pub async fn queue_add(
+    State(state): State<AppState>,
+    Json(body): Json<QueueAddRequest>,
+) -> Result<Json<QueueAddResponse>, AppError> {
+    let uris = queue::collect_uris(&body)?;
+    let added = state
+        .mopidy()
+        .tracklist_add(&uris, body.at_position)
+        .await?;
+
+    Ok(Json(QueueAddResponse {
+        added: added.iter().map(queue::build_entry).collect(),
+    }))
+}
+
+#[instrument(skip(state))]
+pub async fn queue_clear(State(state): State<AppState>) -> Result<StatusCode, AppError> {
+    state.mopidy().tracklist_clear().await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[instrument(skip(state, body))]
+pub async fn queue_remove(
+    State(state): State<AppState>,
+    Json(body): Json<QueueRemoveRequest>,
+) -> Result<Json<QueueRemoveResponse>, AppError> {
+    if body.tlids.is_empty() {
+        return Err(AppError::bad_request("no tlids to remove"));
+    }
+    let removed = state.mopidy().tracklist_remove(&body.tlids).await?;
+
+    Ok(Json(QueueRemoveResponse {
+        removed: removed.iter().map(|tl_track| tl_track.tlid).collect(),
+    }))
+}
+
+#[instrument(skip(state, body))]
+pub async fn queue_move(
+    State(state): State<AppState>,
+    Json(body): Json<QueueMoveRequest>,
+) -> Result<StatusCode, AppError> {
+    if body.start >= body.end {
+        return Err(AppError::bad_request("start must be lower than end"));
+    }
+    state
+        .mopidy()
+        .tracklist_move(body.start, body.end, body.to_position)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[instrument(skip(state, body))]
+pub async fn queue_shuffle(
+    State(state): State<AppState>,
+    body: Option<Json<QueueShuffleRequest>>,
+) -> Result<StatusCode, AppError> {
+    let Json(body) = body.unwrap_or_default();
+    state
+        .mopidy()
+        .tracklist_shuffle(body.start, body.end)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[instrument(skip(state, body))]
+pub async fn queue_set_options(
+    State(state): State<AppState>,
+    Json(body): Json<QueueOptionsUpdate>,
+) -> Result<Json<QueueOptions>, AppError> {
+    let options = queue::update_options(&*state.mopidy(), &body).await?;
+    Ok(Json(options))
+}
+
+#[instrument(skip(state))]
+pub async fn volume_status(State(state): State<AppState>) -> Result<Json<VolumeStatus>, AppError> {
+    let status = state
+        .mixer
+        .status(&state.config(), &*state.mopidy(), &state.mode)
+        .await?;
+    Ok(Json(status))
+}
+
+/// Absolut (`volume`) oder relativ (`step`), optional als Rampe (`ramp_ms`).
+#[instrument(skip(state))]
+pub async fn volume_set(
+    State(state): State<AppState>,
+    Json(body): Json<VolumeRequest>,
+) -> Result<Json<VolumeStatus>, AppError> {
+    let status = state
+        .mixer
+        .set_volume(&state.config(), &*state.mopidy(), &state.mode, &body)
+        .await?;
+    Ok(Json(status))
+}
+
+#[instrument(skip(state))]
+pub async fn mute_status(State(state): State<AppState>) -> Result<Json<MuteStatus>, AppError> {
+    let status = state
+        .mixer
+        .status(&state.config(), &*state.mopidy(), &state.mode)
+        .await?;
+    Ok(Json(MuteStatus {
+        mixer: status.mixer,
+        mute: status.mute,
+    }))
+}
+
+#[instrument(skip(state))]
+pub async fn mute_set(
+    State(state): State<AppState>,
+    Json(body): Json<MuteRequest>,
+) -> Result<Json<MuteStatus>, AppError> {
+    let status = state
+        .mixer
+        .set_mute(&state.config(), &*state.mopidy(), &state.mode, body.mute)
+        .await?;
+    Ok(Json(status))
+}
+
+/// Server-Sent Events aus der Mopidy-WebSocket-Bridge; `event:` trägt den Eventnamen.
+pub async fn events(
+    State(state): State<AppState>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let stream = BroadcastStream::new(state.events.subscribe()).filter_map(|item| async move {
+        match item {
+            Ok(event) => Event::default()
+                .event(event.name())
+                .json_data(&event)
+                .ok()
+                .map(Ok),
+            Err(BroadcastStreamRecvError::Lagged(skipped)) => {
+                warn!("SSE client lagged behind, skipped {skipped} events");
+                None
+            }
+        }
+    });
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+/// Prometheus-Textformat; der Recording-Status wird beim Scrape aufgefrischt.
+pub async fn metrics(State(state): State<AppState>) -> impl IntoResponse {
+    METRICS.set_recording(state.recorder.status().await.running);
+    (
+        [(
+            axum::http::header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        METRICS.render(),
+    )
+}
+
+/// Konfiguration neu laden; ungültige Konfiguration → `400`, die alte bleibt aktiv.
+#[instrument(skip(state))]
+pub async fn admin_reload(State(state): State<AppState>) -> (StatusCode, Json<ReloadReport>) {
+    let report = state.live.reload().await;
+    let status = if report.reloaded {
+        StatusCode::OK
+    } else {
+        StatusCode::BAD_REQUEST
+    };
+    (status, Json(report))
+}
+
+pub async fn action_list(State(state): State<AppState>) -> Json<Vec<ActionInfo>> {
+    Json(actions::list(&state.config()))
+}
+
+/// Argumente werden gegen das Schema der Aktion geprüft (`400`), unbekannt → `404`.
+#[instrument(skip(state, body))]
+pub async fn action_run(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(body): Json<ActionRequest>,
+) -> Result<Json<ScriptOutput>, AppError> {
+    Ok(Json(
+        actions::run(&state.config(), &name, &body.args).await?,
+    ))
+}
+
+/// Startet ein konfiguriertes Skript als Job; Antwort `202` mit Job-ID.
+#[instrument(skip(state, body))]
+pub async fn job_start(
+    State(state): State<AppState>,
+    Json(body): Json<JobRequest>,
+) -> Result<(StatusCode, Json<JobStatus>), AppError> {
+    let status = state.jobs.spawn(&state, body)?;
+    Ok((StatusCode::ACCEPTED, Json(status)))
+}
+
+pub async fn job_status(
+    State(state): State<AppState>,
+    Path(id): Path<u64>,
+) -> Result<Json<JobStatus>, AppError> {
+    Ok(Json(state.jobs.status(id)?))
+}
+
+/// Laufender Job → Skript wird beendet; beendeter Job → aus der Liste entfernt.
+#[instrument(skip(state))]
+pub async fn job_cancel(
+    State(state): State<AppState>,
+    Path(id): Path<u64>,
+) -> Result<Json<JobStatus>, AppError> {
+    Ok(Json(state.jobs.cancel(id).await?))
+}
+
+/// SSE: `stdout`/`stderr` je Zeile (Daten = Zeile), zum Schluss `finished` mit dem Status.
+pub async fn job_stream(
+    State(state): State<AppState>,
+    Path(id): Path<u64>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    let events = state.jobs.stream(id)?.map(|event| {
+        Ok(match &event {
+            JobEvent::Line { stream, line } => Event::default().event(stream.as_str()).data(line),
+            JobEvent::Finished(status) => Event::default()
+                .event("finished")
+                .json_data(status)
+                .unwrap_or_default(),
+        })
+    });
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
@@ -0,0 +1,381 @@
+//! Asynchrone Skript-Jobs für lange Operationen (`/jobs`).
+//!
+//! Ein Job läuft als eigene Task über `scripts::runner::run_script_streaming`;
+//! Ausgabezeilen werden gepuffert und live verteilt, damit ein später
+//! verbundener Stream zuerst die bisherigen Zeilen und dann den Rest sieht.
+//! Jobs leben nur im Speicher; beendete werden ab `MAX_FINISHED_JOBS` verworfen.
+//!
+//! Recorder und Mixer laufen nicht als Job: ihre Endpunkte haben Pfadprüfung
+//! bzw. Zustand, die ein Job umgehen würde. `audio_mode` läuft als Job über
+//! `ModeSwitcher::switch` (mit Verifikation und Rollback); seine Zeilen kommen
+//! erst nach dem Wechsel, abbrechen lässt er sich nicht.
+//! Aufrufer-Argumente prüft `check_args` je Skript.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use futures_util::stream::{self, Stream, StreamExt};
+use serde::Serialize;
+use tokio::sync::{broadcast, watch, Notify};
+use tokio_stream::wrappers::BroadcastStream;
+use tracing::{info, warn};
+
+use crate::error::AppError;
+use crate::models::{AudioMode, JobRequest, JobState, JobStatus, ModeSwitchReport};
+use crate::scripts::runner::{self, OutputStream};
+use crate::AppState;
+
+const MAX_FINISHED_JOBS: usize = 50;
+/// Gepufferte Zeilen pro Job; ältere fallen für spätere Streams weg.
+const MAX_BUFFERED_LINES: usize = 10_000;
+/// Wie lange `DELETE` auf das Ende des abgebrochenen Skripts wartet.
+const CANCEL_WAIT: Duration = Duration::from_secs(5);
+
+/// Ereignis im Stream eines Jobs.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum JobEvent {
+    Line { stream: OutputStream, line: String },
+    Finished(JobStatus),
+}
+
+struct Job {
+    status: Mutex<(JobStatus, VecDeque<(OutputStream, String)>)>,
+    /// `false` für Moduswechsel: ein Abbruch mitten im Wechsel verhindert den Rollback.
+    cancellable: bool,
+    events: broadcast::Sender<JobEvent>,
+    cancel: Notify,
+    done: watch::Sender<bool>,
+}
+
+impl Job {
+    fn lock(&self) -> MutexGuard<'_, (JobStatus, VecDeque<(OutputStream, String)>)> {
+        self.status
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    fn snapshot(&self) -> JobStatus {
+        self.lock().0.clone()
+    }
+
+    fn push_line(&self, stream: OutputStream, line: &str) {
+        let mut guard = self.lock();
+        let (status, lines) = &mut *guard;
+        status.lines += 1;
+        if lines.len() == MAX_BUFFERED_LINES {
+            lines.pop_front();
+        }
+        lines.push_back((stream, line.to_string()));
+        // Unter dem Lock senden, damit `stream` Puffer und Abo konsistent sieht.
+        let _ = self.events.send(JobEvent::Line {
+            stream,
+            line: line.to_string(),
+        });
+    }
+
+    fn finish(&self, outcome: Result<runner::ScriptOutput, AppError>) {
+        self.complete(|status| match outcome {
+            Ok(output) => {
+                status.state = JobState::Succeeded;
+                status.exit_code = output.exit_code;
+                status.duration_ms = Some(output.duration_ms);
+                status.result = output.result;
+            }
+            Err(err) => {
+                status.state = match &err {
+                    AppError::ScriptTimeout { .. } => JobState::TimedOut,
+                    AppError::ScriptCancelled { .. } => JobState::Cancelled,
+                    _ => JobState::Failed,
+                };
+                if let AppError::ScriptFailed(output) = &err {
+                    status.exit_code = output.exit_code;
+                    status.duration_ms = Some(output.duration_ms);
+                    status.result.clone_from(&output.result);
+                }
+                status.error = Some(err.to_string());
+            }
+        });
+    }
+
+    /// Ein nicht verifizierter (und ggf. zurückgerollter) Wechsel gilt als fehlgeschlagen;
+    /// der Bericht steht in `result`.
+    fn finish_mode(&self, outcome: Result<ModeSwitchReport, AppError>) {
+        if let Ok(report) = &outcome {
+            if let Some(output) = &report.script {
+                for line in output.stdout.lines() {
+                    self.push_line(OutputStream::Stdout, line);
+                }
+                for line in output.stderr.lines() {
+                    self.push_line(OutputStream::Stderr, line);
+                }
+            }
+        }
+        self.complete(|status| match outcome {
+            Ok(report) => {
+                status.state = if report.verification.ok {
+                    JobState::Succeeded
+                } else {
+                    JobState::Failed
+                };
+                if let Some(output) = &report.script {
+                    status.exit_code = output.exit_code;
+                    status.duration_ms = Some(output.duration_ms);
+                }
+                status.error.clone_from(&report.error);
+                status.result = match serde_json::to_value(&report) {
+                    Ok(serde_json::Value::Object(map)) => Some(map),
+                    _ => None,
+                };
+            }
+            Err(err) => {
+                status.state = JobState::Failed;
+                status.error = Some(err.to_string());
+            }
+        });
+    }
+
+    fn complete(&self, update: impl FnOnce(&mut JobStatus)) {
+        let mut guard = self.lock();
+        let status = &mut guard.0;
+        status.finished_at = Some(chrono::Utc::now().to_rfc3339());
+        update(status);
+        info!("job {} finished: {:?}", status.id, status.state);
+        let _ = self.events.send(JobEvent::Finished(status.clone()));
+        drop(guard);
+        self.done.send_replace(true);
+    }
+}
+
+/// Erlaubt nur Job-Skripte und deren unkritische Optionen; Quelle und Ziel
+/// (`--input`, `--rpc-url`) legt das Backend fest, nicht der Aufrufer.
+fn check_args(script: &str, args: &[String]) -> Result<(), AppError> {
+    match script {
+        "playlist_from_list" => {
+            let mut positional = 0;
+            let mut args = args.iter();
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--replace" => {}
+                    "--scheme" => {
+                        if args.next().is_none_or(|value| value.starts_with('-')) {
+                            return Err(AppError::bad_request("--scheme needs a value"));
+                        }
+                    }
+                    _ if arg.starts_with("--scheme=") => {}
+                    _ if arg.starts_with('-') => {
+                        return Err(AppError::bad_request(format!(
+                            "option '{arg}' is not allowed for jobs"
+                        )));
+                    }
+                    _ => positional += 1,
+                }
+            }
+            if positional > 1 {
+                return Err(AppError::bad_request(
+                    "playlist_from_list takes a single playlist name",
+                ));
+            }
+            Ok(())
+        }
+        "audio_mode" => mode_target(args).map(|_| ()),
+        "hw_mixer" => Err(AppError::bad_request(format!(
+            "script '{script}' cannot run as a job; use its endpoint"
+        ))),
+        _ => Err(AppError::bad_request(format!("unknown script '{script}'"))),
+    }
+}
+
+/// Einziges Argument eines `audio_mode`-Jobs: der Zielmodus (`pulse`/`alsa`).
+fn mode_target(args: &[String]) -> Result<AudioMode, AppError> {
+    match args {
+        [mode] => <AudioMode as clap::ValueEnum>::from_str(mode, true)
+            .map_err(|_| AppError::bad_request(format!("unknown audio mode '{mode}'"))),
+        _ => Err(AppError::bad_request(
+            "audio_mode takes a single mode (pulse or alsa)",
+        )),
+    }
+}
+
+#[derive(Default)]
+pub struct JobRegistry {
+    next_id: AtomicU64,
+    jobs: Mutex<BTreeMap<u64, Arc<Job>>>,
+}
+
+impl JobRegistry {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn jobs(&self) -> MutexGuard<'_, BTreeMap<u64, Arc<Job>>> {
+        self.jobs
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    fn get(&self, id: u64) -> Result<Arc<Job>, AppError> {
+        self.jobs()
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| AppError::not_found(format!("job {id} not found")))
+    }
+
+    /// Startet das Skript im Hintergrund; Timeout des Skripts, sonst `job_timeout`.
+    pub fn spawn(&self, state: &AppState, request: JobRequest) -> Result<JobStatus, AppError> {
+        check_args(&request.script, &request.args)?;
+        let config = state.config();
+        let script = config
+            .script(&request.script)
+            .ok_or_else(|| AppError::bad_request(format!("unknown script '{}'", request.script)))?
+            .clone();
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let status = JobStatus {
+            id,
+            script: request.script,
+            args: request.args,
+            state: JobState::Running,
+            started_at: chrono::Utc::now().to_rfc3339(),
+            finished_at: None,
+            exit_code: None,
+            duration_ms: None,
+            result: None,
+            error: None,
+            lines: 0,
+        };
+        let mode = (status.script == "audio_mode")
+            .then(|| mode_target(&status.args))
+            .transpose()?;
+        let job = Arc::new(Job {
+            status: Mutex::new((status.clone(), VecDeque::new())),
+            cancellable: mode.is_none(),
+            events: broadcast::channel(256).0,
+            cancel: Notify::new(),
+            done: watch::channel(false).0,
+        });
+        self.insert(id, job.clone());
+        info!("job {id} started: {} {:?}", status.script, status.args);
+
+        if let Some(target) = mode {
+            let switcher = state.mode.clone();
+            let mopidy = state.mopidy();
+            tokio::spawn(async move {
+                let outcome = switcher.switch(&config, &*mopidy, target).await;
+                job.finish_mode(outcome);
+            });
+            return Ok(status);
+        }
+
+        let args = status.args.clone();
+        let input = request.input;
+        tokio::spawn(async move {
+            let args: Vec<&str> = args.iter().map(String::as_str).collect();
+            let outcome = runner::run_script_streaming(
+                &config,
+                &script,
+                &args,
+                input.as_deref(),
+                script.timeout.unwrap_or(config.job_timeout),
+                job.cancel.notified(),
+                |stream, line| job.push_line(stream, line),
+            )
+            .await;
+            job.finish(outcome);
+        });
+
+        Ok(status)
+    }
+
+    fn insert(&self, id: u64, job: Arc<Job>) {
+        let mut jobs = self.jobs();
+        jobs.insert(id, job);
+        let finished: Vec<u64> = jobs
+            .iter()
+            .filter(|(_, job)| job.snapshot().state.is_finished())
+            .map(|(id, _)| *id)
+            .collect();
+        for id in finished
+            .iter()
+            .take(finished.len().saturating_sub(MAX_FINISHED_JOBS))
+        {
+            jobs.remove(id);
+        }
+    }
+
+    pub fn status(&self, id: u64) -> Result<JobStatus, AppError> {
+        Ok(self.get(id)?.snapshot())
+    }
+
+    /// Laufende Jobs werden abgebrochen (Kill des Skripts), beendete entfernt.
+    pub async fn cancel(&self, id: u64) -> Result<JobStatus, AppError> {
+        let job = self.get(id)?;
+        if job.snapshot().state.is_finished() {
+            self.jobs().remove(&id);
+            return Ok(job.snapshot());
+        }
+        if !job.cancellable {
+            return Err(AppError::conflict(format!(
+                "job {id} switches the audio mode and cannot be cancelled"
+            )));
+        }
+
+        job.cancel.notify_one();
+        let mut done = job.done.subscribe();
+        if tokio::time::timeout(CANCEL_WAIT, done.wait_for(|done| *done))
+            .await
+            .is_err()
+        {
+            warn!("job {id} did not stop within {CANCEL_WAIT:?}");
+        }
+        Ok(job.snapshot())
+    }
+
+    /// Bisherige Zeilen, dann Live-Zeilen bis einschließlich `Finished`.
+    pub fn stream(&self, id: u64) -> Result<impl Stream<Item = JobEvent> + use<>, AppError> {
+        let job = self.get(id)?;
+        let guard = job.lock();
+        let (status, lines) = &*guard;
+        let mut replay: Vec<JobEvent> = lines
+            .iter()
+            .map(|(stream, line)| JobEvent::Line {
+                stream: *stream,
+                line: line.clone(),
+            })
+            .collect();
+
+        let live = if status.state.is_finished() {
+            replay.push(JobEvent::Finished(status.clone()));
+            None
+        } else {
+            Some(BroadcastStream::new(job.events.subscribe()))
+        };
+        drop(guard);
+
+        let live = stream::iter(live).flatten().filter_map(|event| async move {
+            match event {
+                Ok(event) => Some(event),
+                Err(err) => {
+                    warn!("job stream lagged: {err}");
+                    None
+                }
+            }
+        });
+        // Nach `Finished` endet der Stream, ohne auf weitere Ereignisse zu warten.
+        let events = Box::pin(stream::iter(replay).chain(live));
+        Ok(stream::unfold(
+            (events, false),
+            |(mut events, finished)| async move {
+                if finished {
+                    return None;
+                }
+                let event = events.next().await?;
+                let finished = matches!(event, JobEvent::Finished(_));
+                Some((event, (events, finished)))
+            },
+        ))
+    }
+}
@@ -1,15 +1,36 @@
+mod actions;
+mod auth;
+pub mod client;
 pub mod config;
 pub mod discover;
 pub mod error;
+pub mod events;
 mod handlers;
+mod jobs;
+mod library;
+mod metrics;
+mod mixer;
+mod mode;
 mod models;
 mod mopidy;
+mod playlists;
+mod queue;
+mod recording;
+mod reload;
+mod rpc;
 pub mod scripts;
+mod search;
 pub mod validation;
 
 pub use error::AppError;
-pub use models::{AudioMode, SimilarResponse, SimilarTrack};
-pub use mopidy::{HttpMopidyClient, MopidyClient};
+pub use events::{EventBridge, PlayerEvent};
+pub use jobs::{JobEvent, JobRegistry};
+pub use mixer::Mixer;
+pub use mode::ModeSwitcher;
+pub use models::{AudioMode, SimilarResponse, SimilarTrack, StopSignal, TrackInfo};
+pub use mopidy::{types as mopidy_types, HttpMopidyClient, MopidyClient};
+pub use recording::Recorder;
+pub use reload::LiveConfig;
 
 use axum::Router;
 use std::sync::Arc;
@@ -19,26 +40,58 @@ use crate::handlers::app_routes;
 
 #[derive(Clone)]
 pub struct AppState {
-    pub config: Arc<AppConfig>,
-    pub mopidy: Arc<dyn MopidyClient>,
+    /// Konfiguration und Mopidy-Client, per Reload austauschbar.
+    pub live: Arc<LiveConfig>,
+    pub recorder: Arc<Recorder>,
+    pub events: Arc<EventBridge>,
+    pub mode: Arc<ModeSwitcher>,
+    pub jobs: Arc<JobRegistry>,
+    pub mixer: Arc<Mixer>,
+}
+
+impl AppState {
+    fn new(live: Arc<LiveConfig>) -> Self {
+        let config = live.config();
+        Self {
+            recorder: Arc::new(Recorder::new(config.recorder.clone())),
+            events: live.events(),
+            mode: Arc::new(ModeSwitcher::new()),
+            jobs: Arc::new(JobRegistry::new()),
+            mixer: Arc::new(Mixer::new()),
+            live,
+        }
+    }
+
+    /// Aktuelle Konfiguration; pro Request einmal holen, damit ein Reload
+    /// mitten im Request keine gemischten Werte liefert.
+    #[must_use]
+    pub fn config(&self) -> Arc<AppConfig> {
+        self.live.config()
+    }
+
+    #[must_use]
+    pub fn mopidy(&self) -> Arc<dyn MopidyClient> {
+        self.live.mopidy()
+    }
 }
 
 pub fn build_router(config: AppConfig) -> Router {
-    let config = Arc::new(config);
-    let mopidy_client = Arc::new(HttpMopidyClient::new(
-        reqwest::Client::new(),
-        config.mopidy_rpc_url.clone(),
-    )) as Arc<dyn MopidyClient>;
-
-    app_routes(AppState {
-        config,
-        mopidy: mopidy_client,
-    })
+    build_router_with_reload(config).0
+}
+
+/// Wie `build_router`, liefert zusätzlich den Handle für Reloads (z. B. per SIGHUP).
+pub fn build_router_with_reload(config: AppConfig) -> (Router, Arc<LiveConfig>) {
+    let live = Arc::new(LiveConfig::new(config, |config| {
+        Arc::new(HttpMopidyClient::from_config(
+            &config.mopidy,
+            config.mopidy_rpc_url.clone(),
+        )) as Arc<dyn MopidyClient>
+    }));
+    (app_routes(AppState::new(live.clone())), live)
 }
 
+/// Mit festem Mopidy-Client (Tests); ein Reload tauscht nur die Konfiguration.
 pub fn build_router_with_mopidy(config: AppConfig, mopidy_client: Arc<dyn MopidyClient>) -> Router {
-    app_routes(AppState {
-        config: Arc::new(config),
-        mopidy: mopidy_client,
-    })
+    let live = Arc::new(LiveConfig::new(config, move |_| mopidy_client.clone()));
+    app_routes(AppState::new(live))
 }
@@ -0,0 +1,164 @@
+//! Bibliothek durchblättern (`core.library.browse`), Tracks und Cover nachschlagen.
+//!
+//! Mopidy kennt keine Seiten; große Verzeichnisse (z. B. Qobuz-Favoriten)
+//! werden hier nach `offset`/`limit` zugeschnitten.
+
+use tracing::instrument;
+
+use crate::discover::build_track;
+use crate::error::AppError;
+use crate::models::{
+    LibraryBrowseResponse, LibraryImage, LibraryImagesResponse, LibraryLookupResponse, LibraryRef,
+};
+use crate::mopidy::MopidyClient;
+use crate::validation;
+
+/// Seitengröße ohne `limit`.
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+/// Größere `limit`-Werte werden gekappt.
+pub const MAX_PAGE_SIZE: usize = 500;
+/// Höchstzahl URIs je `lookup`/`images`-Anfrage.
+pub const MAX_URIS: usize = 50;
+
+#[instrument(skip(mopidy))]
+pub async fn browse(
+    mopidy: &dyn MopidyClient,
+    uri: Option<&str>,
+    offset: usize,
+    limit: Option<usize>,
+) -> Result<LibraryBrowseResponse, AppError> {
+    let uri = uri.map(str::trim).filter(|uri| !uri.is_empty());
+    if let Some(uri) = uri {
+        if !validation::is_allowed_uri(uri) {
+            return Err(AppError::bad_request(format!(
+                "disallowed URI scheme: {uri}"
+            )));
+        }
+    }
+    let limit = page_size(limit)?;
+
+    // Auch Einträge nicht freigeschalteter Backends (z. B. `file:`) ausblenden.
+    let entries: Vec<LibraryRef> = mopidy
+        .browse(uri)
+        .await?
+        .into_iter()
+        .filter(|entry| validation::is_allowed_uri(&entry.uri))
+        .map(|entry| LibraryRef {
+            uri: entry.uri,
+            name: entry.name,
+            kind: entry.kind,
+        })
+        .collect();
+
+    Ok(LibraryBrowseResponse {
+        uri: uri.map(Into::into),
+        total: entries.len(),
+        offset,
+        limit,
+        items: entries.into_iter().skip(offset).take(limit).collect(),
+    })
+}
+
+#[instrument(skip(mopidy))]
+pub async fn lookup(
+    mopidy: &dyn MopidyClient,
+    raw_uris: &str,
+) -> Result<LibraryLookupResponse, AppError> {
+    let uris = parse_uris(raw_uris)?;
+    let tracks = mopidy
+        .lookup_tracks(&uris)
+        .await?
+        .into_iter()
+        .map(|(uri, tracks)| (uri, tracks.iter().filter_map(build_track).collect()))
+        .collect();
+
+    Ok(LibraryLookupResponse { tracks })
+}
+
+#[instrument(skip(mopidy))]
+pub async fn images(
+    mopidy: &dyn MopidyClient,
+    raw_uris: &str,
+) -> Result<LibraryImagesResponse, AppError> {
+    let uris = parse_uris(raw_uris)?;
+    let images = mopidy
+        .images(&uris)
+        .await?
+        .into_iter()
+        .map(|(uri, images)| {
+            let images = images
+                .into_iter()
+                .map(|image| LibraryImage {
+                    uri: image.uri,
+                    width: image.width,
+                    height: image.height,
+                })
+                .collect();
+            (uri, images)
+        })
+        .collect();
+
+    Ok(LibraryImagesResponse { images })
+}
+
+pub(crate) fn page_size(limit: Option<usize>) -> Result<usize, AppError> {
+    match limit {
+        Some(0) => Err(AppError::bad_request("limit must be at least 1")),
+        Some(limit) => Ok(limit.min(MAX_PAGE_SIZE)),
+        None => Ok(DEFAULT_PAGE_SIZE),
+    }
+}
+
+/// Kommagetrennte URIs ohne Duplikate; eine unerlaubte URI lehnt die ganze Anfrage ab.
+pub(crate) fn parse_uris(raw: &str) -> Result<Vec<String>, AppError> {
+    let mut uris: Vec<String> = Vec::new();
+    for uri in raw.split(',').map(str::trim).filter(|uri| !uri.is_empty()) {
+        if !validation::is_allowed_uri(uri) {
+            return Err(AppError::bad_request(format!(
+                "disallowed URI scheme: {uri}"
+            )));
+        }
+        if !uris.iter().any(|known| known == uri) {
+            uris.push(uri.into());
+        }
+    }
+
+    if uris.is_empty() {
+        return Err(AppError::bad_request("no URIs given"));
+    }
+    if uris.len() > MAX_URIS {
+        return Err(AppError::bad_request(format!(
+            "too many URIs ({}, max {MAX_URIS})",
+            uris.len()
+        )));
+    }
+    Ok(uris)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_uris_deduplicates_and_validates() {
+        assert_eq!(
+            parse_uris(" qobuz:album:1, local:track:a.flac,qobuz:album:1,").unwrap(),
+            vec!["qobuz:album:1", "local:track:a.flac"]
+        );
+        assert!(matches!(
+            parse_uris("qobuz:album:1,file:///etc/passwd"),
+            Err(AppError::BadRequest(_))
+        ));
+        assert!(parse_uris(" , ").is_err());
+
+        let many: Vec<String> = (0..=MAX_URIS).map(|i| format!("local:track:{i}")).collect();
+        assert!(parse_uris(&many.join(",")).is_err());
+    }
+
+    #[test]
+    fn page_size_defaults_and_caps() {
+        assert_eq!(page_size(None).unwrap(), DEFAULT_PAGE_SIZE);
+        assert_eq!(page_size(Some(10_000)).unwrap(), MAX_PAGE_SIZE);
+        assert!(page_size(Some(0)).is_err());
+    }
+}
@@ -1,9 +1,60 @@
-use hauski_backend::build_router;
+mod cli;
+
+use std::path::{Path, PathBuf};
+
+use clap::{Parser, Subcommand};
+use hauski_backend::build_router_with_reload;
+use hauski_backend::client::BackendClient;
 use hauski_backend::config::AppConfig;
 use hauski_backend::error::AppError;
 use tokio::net::TcpListener;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use tracing_subscriber::EnvFilter;
+use url::Url;
+
+#[derive(Parser)]
+#[command(
+    name = "hauski-backend",
+    version,
+    about = "HTTP-Backend für hausKI-audio"
+)]
+struct Cli {
+    /// TOML-Konfigurationsdatei (sonst `HAUSKI_CONFIG`); Umgebungsvariablen haben Vorrang.
+    #[arg(long, global = true, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Laufender Dienst für Client-Befehle; ohne Angabe laufen sie in-process.
+    #[arg(long, global = true, env = "HAUSKI_URL", value_name = "URL")]
+    url: Option<Url>,
+
+    /// Bearer-Token für `--url`.
+    #[arg(long, global = true, env = "HAUSKI_TOKEN", hide_env_values = true)]
+    token: Option<String>,
+
+    /// Antwort der API unverändert als JSON ausgeben.
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Startet den HTTP-Server (Standard).
+    Serve,
+    /// Konfiguration prüfen und anzeigen.
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    #[command(flatten)]
+    Client(cli::ClientCommand),
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Effektive Konfiguration mit Herkunft jedes Werts ausgeben.
+    Show,
+}
 
 #[tokio::main]
 async fn main() {
@@ -15,20 +66,47 @@ async fn main() {
 
 async fn run() -> Result<(), AppError> {
     dotenvy::dotenv().ok();
+    let cli = Cli::parse();
 
-    let env_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    // Client-Befehle loggen nur Warnungen, damit stdout (z. B. `--json`) sauber bleibt.
+    let default_level = match cli.command {
+        None | Some(Command::Serve) => "info",
+        Some(_) => "warn",
+    };
+    let env_filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_level));
 
     tracing_subscriber::fmt()
         .with_env_filter(env_filter)
         .with_target(false)
+        .with_writer(std::io::stderr)
         .compact()
         .init();
 
-    let config = AppConfig::from_env()
-        .map_err(|err| AppError::Startup(format!("failed to load configuration: {err}")))?;
+    if let Some(Command::Client(command)) = cli.command {
+        let client = match cli.url {
+            Some(url) => BackendClient::http(url, cli.token),
+            None => BackendClient::in_process(load_config(cli.config.as_deref())?),
+        };
+        return cli::run(&client, command, cli.json).await;
+    }
+
+    let config = load_config(cli.config.as_deref())?;
+
+    if let Some(Command::Config(ConfigCommand::Show)) = cli.command {
+        print!("{}", config.sources.render());
+        return Ok(());
+    }
 
     config.validate()?;
 
+    if !config.auth.enabled() && !config.bind_addr.ip().is_loopback() {
+        warn!(
+            "binding to {} without API tokens; set HAUSKI_API_TOKENS or HAUSKI_API_TOKENS_FILE",
+            config.bind_addr
+        );
+    }
+
     let bind_addr = config.bind_addr;
     let listener = TcpListener::bind(bind_addr)
         .await
@@ -36,9 +114,21 @@ async fn run() -> Result<(), AppError> {
 
     info!("listening on {bind_addr}");
 
-    axum::serve(listener, build_router(config))
+    let (app, live) = build_router_with_reload(config);
+    #[cfg(unix)]
+    live.reload_on_sighup()
+        .map_err(|err| AppError::Startup(format!("failed to install SIGHUP handler: {err}")))?;
+    #[cfg(not(unix))]
+    drop(live);
+
+    axum::serve(listener, app)
         .await
         .map_err(|err| AppError::Startup(format!("server error: {err}")))?;
 
     Ok(())
 }
+
+fn load_config(path: Option<&Path>) -> Result<AppConfig, AppError> {
+    AppConfig::load(path)
+        .map_err(|err| AppError::Startup(format!("failed to load configuration: {err}")))
+}
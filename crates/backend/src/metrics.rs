@@ -0,0 +1,304 @@
+//! Prometheus-Metriken für `GET /metrics`.
+//!
+//! Eine prozessweite Registry, damit Mopidy-Client und Script-Runner ohne
+//! zusätzliche Parameter zählen können. Routen werden über das Muster
+//! (`MatchedPath`) gelabelt, nicht über konkrete Pfade.
+
+use std::sync::LazyLock;
+use std::time::Instant;
+
+use axum::extract::{MatchedPath, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+
+use crate::models::{AudioMode, CircuitState};
+
+pub(crate) static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+/// Mopidys Core-API (3.x); andere Methoden aus `/rpc` zählen als `other`,
+/// damit Clients keine beliebigen Label-Werte erzeugen.
+const MOPIDY_METHODS: &[&str] = &[
+    "core.describe",
+    "core.get_uri_schemes",
+    "core.get_version",
+    "core.history.get_history",
+    "core.history.get_length",
+    "core.library.browse",
+    "core.library.get_distinct",
+    "core.library.get_images",
+    "core.library.lookup",
+    "core.library.refresh",
+    "core.library.search",
+    "core.mixer.get_mute",
+    "core.mixer.get_volume",
+    "core.mixer.set_mute",
+    "core.mixer.set_volume",
+    "core.playback.get_current_tl_track",
+    "core.playback.get_current_tlid",
+    "core.playback.get_current_track",
+    "core.playback.get_state",
+    "core.playback.get_stream_title",
+    "core.playback.get_time_position",
+    "core.playback.next",
+    "core.playback.pause",
+    "core.playback.play",
+    "core.playback.previous",
+    "core.playback.resume",
+    "core.playback.seek",
+    "core.playback.set_state",
+    "core.playback.stop",
+    "core.playlists.as_list",
+    "core.playlists.create",
+    "core.playlists.delete",
+    "core.playlists.get_items",
+    "core.playlists.get_uri_schemes",
+    "core.playlists.lookup",
+    "core.playlists.refresh",
+    "core.playlists.save",
+    "core.tracklist.add",
+    "core.tracklist.clear",
+    "core.tracklist.eot_track",
+    "core.tracklist.filter",
+    "core.tracklist.get_consume",
+    "core.tracklist.get_eot_tlid",
+    "core.tracklist.get_length",
+    "core.tracklist.get_next_tlid",
+    "core.tracklist.get_previous_tlid",
+    "core.tracklist.get_random",
+    "core.tracklist.get_repeat",
+    "core.tracklist.get_single",
+    "core.tracklist.get_tl_tracks",
+    "core.tracklist.get_tracks",
+    "core.tracklist.get_version",
+    "core.tracklist.index",
+    "core.tracklist.move",
+    "core.tracklist.next_track",
+    "core.tracklist.previous_track",
+    "core.tracklist.remove",
+    "core.tracklist.set_consume",
+    "core.tracklist.set_random",
+    "core.tracklist.set_repeat",
+    "core.tracklist.set_single",
+    "core.tracklist.shuffle",
+    "core.tracklist.slice",
+];
+
+pub(crate) struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_duration: HistogramVec,
+    mopidy_calls: IntCounterVec,
+    mopidy_errors: IntCounterVec,
+    mopidy_duration: HistogramVec,
+    mopidy_retries: IntCounterVec,
+    mopidy_circuit: IntGauge,
+    script_runs: IntCounterVec,
+    script_duration: HistogramVec,
+    script_reaped: IntCounterVec,
+    audio_mode: IntGaugeVec,
+    recording_active: IntGauge,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry =
+            Registry::new_custom(Some("hauski".into()), None).expect("metric prefix is valid");
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests by route and status"),
+            &["method", "route", "status"],
+        )
+        .expect("valid metric");
+        let http_duration = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "HTTP request latency"),
+            &["method", "route"],
+        )
+        .expect("valid metric");
+        let mopidy_calls = IntCounterVec::new(
+            Opts::new("mopidy_calls_total", "Mopidy JSON-RPC calls by method"),
+            &["method"],
+        )
+        .expect("valid metric");
+        let mopidy_errors = IntCounterVec::new(
+            Opts::new(
+                "mopidy_errors_total",
+                "Failed Mopidy calls (transport or JSON-RPC error)",
+            ),
+            &["method"],
+        )
+        .expect("valid metric");
+        let mopidy_duration = HistogramVec::new(
+            HistogramOpts::new("mopidy_call_duration_seconds", "Mopidy JSON-RPC latency"),
+            &["method"],
+        )
+        .expect("valid metric");
+        let mopidy_retries = IntCounterVec::new(
+            Opts::new("mopidy_retries_total", "Retried idempotent Mopidy calls"),
+            &["method"],
+        )
+        .expect("valid metric");
+        let mopidy_circuit = IntGauge::new(
+            "mopidy_circuit_state",
+            "Mopidy circuit breaker (0 = closed, 1 = half-open, 2 = open)",
+        )
+        .expect("valid metric");
+        let script_runs = IntCounterVec::new(
+            Opts::new(
+                "script_runs_total",
+                "Script invocations by exit status (`timeout`, `spawn_error`, `signal`)",
+            ),
+            &["script", "status"],
+        )
+        .expect("valid metric");
+        let script_duration = HistogramVec::new(
+            HistogramOpts::new("script_duration_seconds", "Script runtime")
+                .buckets(vec![0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0]),
+            &["script"],
+        )
+        .expect("valid metric");
+        let script_reaped = IntCounterVec::new(
+            Opts::new(
+                "script_reaped_total",
+                "Scripts terminated by the reaper (`timeout`, `cancelled`, `dropped`) and final signal",
+            ),
+            &["script", "reason", "signal"],
+        )
+        .expect("valid metric");
+        let audio_mode = IntGaugeVec::new(
+            Opts::new("audio_mode", "Last known audio mode (1 = active)"),
+            &["mode"],
+        )
+        .expect("valid metric");
+        let recording_active =
+            IntGauge::new("recording_active", "1 while a recording is running").expect("valid");
+
+        for collector in [
+            Box::new(http_requests.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(http_duration.clone()),
+            Box::new(mopidy_calls.clone()),
+            Box::new(mopidy_errors.clone()),
+            Box::new(mopidy_duration.clone()),
+            Box::new(mopidy_retries.clone()),
+            Box::new(mopidy_circuit.clone()),
+            Box::new(script_runs.clone()),
+            Box::new(script_duration.clone()),
+            Box::new(script_reaped.clone()),
+            Box::new(audio_mode.clone()),
+            Box::new(recording_active.clone()),
+        ] {
+            registry
+                .register(collector)
+                .expect("metric registered once");
+        }
+
+        Self {
+            registry,
+            http_requests,
+            http_duration,
+            mopidy_calls,
+            mopidy_errors,
+            mopidy_duration,
+            mopidy_retries,
+            mopidy_circuit,
+            script_runs,
+            script_duration,
+            script_reaped,
+            audio_mode,
+            recording_active,
+        }
+    }
+
+    pub(crate) fn observe_mopidy(&self, method: &str, started: Instant, failed: bool) {
+        let method = mopidy_label(method);
+        self.mopidy_calls.with_label_values(&[method]).inc();
+        self.mopidy_duration
+            .with_label_values(&[method])
+            .observe(started.elapsed().as_secs_f64());
+        if failed {
+            self.mopidy_errors.with_label_values(&[method]).inc();
+        }
+    }
+
+    pub(crate) fn observe_mopidy_retry(&self, method: &str) {
+        self.mopidy_retries
+            .with_label_values(&[mopidy_label(method)])
+            .inc();
+    }
+
+    pub(crate) fn set_mopidy_circuit(&self, state: CircuitState) {
+        self.mopidy_circuit.set(match state {
+            CircuitState::Closed => 0,
+            CircuitState::HalfOpen => 1,
+            CircuitState::Open => 2,
+        });
+    }
+
+    pub(crate) fn observe_script(&self, script: &str, status: &str, started: Instant) {
+        self.script_runs.with_label_values(&[script, status]).inc();
+        self.script_duration
+            .with_label_values(&[script])
+            .observe(started.elapsed().as_secs_f64());
+    }
+
+    pub(crate) fn observe_reaped(&self, script: &str, reason: &str, signal: &str) {
+        self.script_reaped
+            .with_label_values(&[script, reason, signal])
+            .inc();
+    }
+
+    /// `None` = Modus unbekannt; alle Modi stehen dann auf 0.
+    pub(crate) fn set_audio_mode(&self, mode: Option<AudioMode>) {
+        for candidate in [AudioMode::Pulse, AudioMode::Alsa] {
+            self.audio_mode
+                .with_label_values(&[candidate.as_str()])
+                .set(i64::from(mode == Some(candidate)));
+        }
+    }
+
+    pub(crate) fn set_recording(&self, running: bool) {
+        self.recording_active.set(i64::from(running));
+    }
+
+    pub(crate) fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("text encoding cannot fail");
+        String::from_utf8(buffer).expect("prometheus text is UTF-8")
+    }
+}
+
+/// Label für eine Mopidy-Methode: bekannte Methoden, sonst `other`.
+fn mopidy_label(method: &str) -> &str {
+    if MOPIDY_METHODS.contains(&method) {
+        method
+    } else {
+        "other"
+    }
+}
+
+/// Middleware: zählt Requests und misst die Latenz je Route.
+pub async fn track(request: Request, next: Next) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or_else(|| "unmatched".to_string(), |path| path.as_str().to_string());
+    let method = request.method().as_str().to_string();
+    let started = Instant::now();
+
+    let response = next.run(request).await;
+
+    METRICS
+        .http_requests
+        .with_label_values(&[&method, &route, response.status().as_str()])
+        .inc();
+    METRICS
+        .http_duration
+        .with_label_values(&[&method, &route])
+        .observe(started.elapsed().as_secs_f64());
+    response
+}
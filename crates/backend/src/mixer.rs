@@ -0,0 +1,318 @@
+//! Lautstärke und Stummschaltung (`/volume`, `/mute`).
+//!
+//! Im Normalfall über Mopidys `core.mixer.*`. Im ALSA-Bitperfect-Modus muss
+//! Mopidys Software-Mixer aus bleiben; dann stellt das `hw-mixer`-Skript den
+//! Hardware-Regler in dB, umgerechnet über die konfigurierte Kurve.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use serde::Deserialize;
+use tokio::time;
+use tracing::{debug, instrument};
+
+use crate::config::{AppConfig, MixerConfig, MixerMode, VolumeCurve};
+use crate::error::AppError;
+use crate::mode::ModeSwitcher;
+use crate::models::{
+    AudioMode, MixerKind, MuteStatus, StepDirection, VolumeRequest, VolumeStatus, VolumeStep,
+};
+use crate::mopidy::MopidyClient;
+use crate::scripts::runner;
+
+/// Mindestabstand zwischen zwei Schritten einer Rampe.
+const RAMP_TICK: Duration = Duration::from_millis(25);
+const MAX_RAMP: Duration = Duration::from_secs(10);
+
+/// Ein neuer Stellbefehl bricht eine laufende Rampe ab.
+#[derive(Default)]
+pub struct Mixer {
+    generation: AtomicU64,
+}
+
+/// Abschließendes JSON-Objekt von `hw-mixer`.
+#[derive(Debug, Deserialize)]
+struct HardwareState {
+    db: Option<f64>,
+    mute: Option<bool>,
+}
+
+enum Backend<'a> {
+    Mopidy(&'a dyn MopidyClient),
+    Hardware(&'a AppConfig),
+}
+
+impl Backend<'_> {
+    fn kind(&self) -> MixerKind {
+        match self {
+            Backend::Mopidy(_) => MixerKind::Mopidy,
+            Backend::Hardware(_) => MixerKind::Hardware,
+        }
+    }
+
+    async fn read(&self) -> Result<VolumeStatus, AppError> {
+        match self {
+            Backend::Mopidy(mopidy) => Ok(VolumeStatus {
+                mixer: MixerKind::Mopidy,
+                volume: mopidy.volume().await?,
+                mute: mopidy.mute().await?,
+                db: None,
+            }),
+            Backend::Hardware(config) => {
+                let state = hardware(config, &["get".into()]).await?;
+                Ok(VolumeStatus {
+                    mixer: MixerKind::Hardware,
+                    volume: state.db.map(|db| db_to_percent(&config.mixer, db)),
+                    mute: state.mute,
+                    db: state.db,
+                })
+            }
+        }
+    }
+
+    async fn write_volume(&self, volume: u8) -> Result<(), AppError> {
+        match self {
+            Backend::Mopidy(mopidy) => {
+                if !mopidy.set_volume(volume).await? {
+                    return Err(AppError::conflict("Mopidy mixer rejected the volume"));
+                }
+            }
+            Backend::Hardware(config) => {
+                let db = percent_to_db(&config.mixer, volume);
+                hardware(config, &["set-db".into(), format!("{db:.2}")]).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn write_mute(&self, mute: bool) -> Result<(), AppError> {
+        match self {
+            Backend::Mopidy(mopidy) => {
+                if !mopidy.set_mute(mute).await? {
+                    return Err(AppError::conflict("Mopidy mixer rejected mute"));
+                }
+            }
+            Backend::Hardware(config) => {
+                let state = if mute { "on" } else { "off" };
+                hardware(config, &["mute".into(), state.into()]).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Mixer {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn status(
+        &self,
+        config: &AppConfig,
+        mopidy: &dyn MopidyClient,
+        modes: &ModeSwitcher,
+    ) -> Result<VolumeStatus, AppError> {
+        backend(config, mopidy, modes).await?.read().await
+    }
+
+    #[instrument(skip(self, config, mopidy, modes))]
+    pub async fn set_volume(
+        &self,
+        config: &AppConfig,
+        mopidy: &dyn MopidyClient,
+        modes: &ModeSwitcher,
+        request: &VolumeRequest,
+    ) -> Result<VolumeStatus, AppError> {
+        let ramp = Duration::from_millis(request.ramp_ms.unwrap_or_default());
+        if ramp > MAX_RAMP {
+            return Err(AppError::bad_request(format!(
+                "ramp_ms must not exceed {}",
+                MAX_RAMP.as_millis()
+            )));
+        }
+        let backend = backend(config, mopidy, modes).await?;
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+
+        let needs_current = request.step.is_some() || !ramp.is_zero();
+        let current = if needs_current {
+            backend.read().await?.volume
+        } else {
+            None
+        };
+        let target = match (request.volume, request.step) {
+            (Some(volume), None) if volume <= 100 => volume,
+            (Some(_), None) => return Err(AppError::bad_request("volume must be 0–100")),
+            (None, Some(step)) => {
+                let current =
+                    current.ok_or_else(|| AppError::conflict("mixer reports no current volume"))?;
+                apply_step(current, step, config.mixer.step)
+            }
+            _ => return Err(AppError::bad_request("expected either volume or step")),
+        };
+
+        match current.filter(|_| !ramp.is_zero()) {
+            Some(start) if start != target => {
+                let distance = u64::from(start.abs_diff(target));
+                let ticks = u64::try_from(ramp.as_millis() / RAMP_TICK.as_millis())
+                    .unwrap_or(u64::MAX)
+                    .clamp(1, distance);
+                let interval = ramp / u32::try_from(ticks).unwrap_or(u32::MAX);
+                for tick in 1..=ticks {
+                    if self.generation.load(Ordering::SeqCst) != generation {
+                        debug!("volume ramp superseded");
+                        break;
+                    }
+                    let offset = i64::from(target) - i64::from(start);
+                    let step = offset * i64::try_from(tick).unwrap_or(i64::MAX)
+                        / i64::try_from(ticks).unwrap_or(1);
+                    let volume = u8::try_from(i64::from(start) + step).unwrap_or(target);
+                    backend.write_volume(volume).await?;
+                    if tick < ticks {
+                        time::sleep(interval).await;
+                    }
+                }
+            }
+            _ => backend.write_volume(target).await?,
+        }
+
+        backend.read().await
+    }
+
+    #[instrument(skip(self, config, mopidy, modes))]
+    pub async fn set_mute(
+        &self,
+        config: &AppConfig,
+        mopidy: &dyn MopidyClient,
+        modes: &ModeSwitcher,
+        mute: bool,
+    ) -> Result<MuteStatus, AppError> {
+        let backend = backend(config, mopidy, modes).await?;
+        backend.write_mute(mute).await?;
+        let status = backend.read().await?;
+        Ok(MuteStatus {
+            mixer: backend.kind(),
+            mute: status.mute,
+        })
+    }
+}
+
+/// `auto` fragt den aktuellen Audio-Modus ab; im ALSA-Modus gilt der Hardware-Mixer.
+async fn backend<'a>(
+    config: &'a AppConfig,
+    mopidy: &'a dyn MopidyClient,
+    modes: &ModeSwitcher,
+) -> Result<Backend<'a>, AppError> {
+    let hardware = match config.mixer.mode {
+        MixerMode::Mopidy => false,
+        MixerMode::Hardware => true,
+        MixerMode::Auto => AudioMode::infer(&modes.current(config).await?) == Some(AudioMode::Alsa),
+    };
+    Ok(if hardware {
+        Backend::Hardware(config)
+    } else {
+        Backend::Mopidy(mopidy)
+    })
+}
+
+async fn hardware(config: &AppConfig, args: &[String]) -> Result<HardwareState, AppError> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let output = runner::run_configured(config, &config.mixer.script, &args, None).await?;
+    output
+        .parse_result::<HardwareState>()?
+        .ok_or_else(|| AppError::upstream(format!("{} printed no mixer state", output.script)))
+}
+
+fn apply_step(current: u8, step: VolumeStep, default_step: u8) -> u8 {
+    let delta = match step {
+        VolumeStep::Percent(delta) => delta,
+        VolumeStep::Direction(StepDirection::Up) => i16::from(default_step),
+        VolumeStep::Direction(StepDirection::Down) => -i16::from(default_step),
+    };
+    u8::try_from((i16::from(current) + delta).clamp(0, 100)).unwrap_or(current)
+}
+
+fn percent_to_db(mixer: &MixerConfig, percent: u8) -> f64 {
+    let x = f64::from(percent.min(100)) / 100.0;
+    match mixer.curve {
+        VolumeCurve::Linear => mixer.min_db + (mixer.max_db - mixer.min_db) * x,
+        VolumeCurve::Cubic => {
+            let floor = cubic_floor(mixer);
+            mixer.max_db + 60.0 * (floor + (1.0 - floor) * x).log10()
+        }
+    }
+}
+
+fn db_to_percent(mixer: &MixerConfig, db: f64) -> u8 {
+    let db = db.clamp(mixer.min_db, mixer.max_db);
+    let x = match mixer.curve {
+        VolumeCurve::Linear => (db - mixer.min_db) / (mixer.max_db - mixer.min_db),
+        VolumeCurve::Cubic => {
+            let floor = cubic_floor(mixer);
+            (10f64.powf((db - mixer.max_db) / 60.0) - floor) / (1.0 - floor)
+        }
+    };
+    // x liegt in [0, 1]; die Umwandlung kann nicht überlaufen.
+    (x.clamp(0.0, 1.0) * 100.0).round() as u8
+}
+
+/// Normierter Wert von `min_db` auf der Kubik-Kurve; wie bei alsamixer wird die
+/// Kurve so verschoben, dass 0 % genau `min_db` und 100 % genau `max_db` ergibt.
+fn cubic_floor(mixer: &MixerConfig) -> f64 {
+    10f64.powf((mixer.min_db - mixer.max_db) / 60.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mixer(curve: VolumeCurve) -> MixerConfig {
+        MixerConfig {
+            curve,
+            min_db: -60.0,
+            max_db: 0.0,
+            ..MixerConfig::default()
+        }
+    }
+
+    #[test]
+    fn curves_map_percent_and_db_both_ways() {
+        let linear = mixer(VolumeCurve::Linear);
+        assert!((percent_to_db(&linear, 50) - -30.0).abs() < 1e-9);
+        assert_eq!(db_to_percent(&linear, -30.0), 50);
+        assert_eq!(db_to_percent(&linear, -90.0), 0);
+
+        let cubic = mixer(VolumeCurve::Cubic);
+        assert!((percent_to_db(&cubic, 100) - 0.0).abs() < 1e-9);
+        assert!((percent_to_db(&cubic, 50) - -15.58).abs() < 0.01);
+        assert!((percent_to_db(&cubic, 0) - -60.0).abs() < 1e-9);
+
+        // Engerer Bereich: die Kurve muss ihn voll nutzen, sonst hängen Schritte bei 0 %.
+        let narrow = MixerConfig {
+            min_db: -40.0,
+            max_db: -6.0,
+            ..cubic.clone()
+        };
+        assert!((percent_to_db(&narrow, 0) - -40.0).abs() < 1e-9);
+        assert!((percent_to_db(&narrow, 100) - -6.0).abs() < 1e-9);
+        assert!(percent_to_db(&narrow, 5) > -40.0);
+        for mixer in [&cubic, &narrow] {
+            for percent in [0, 1, 5, 20, 25, 50, 75, 100] {
+                assert_eq!(db_to_percent(mixer, percent_to_db(mixer, percent)), percent);
+            }
+        }
+    }
+
+    #[test]
+    fn steps_clamp_to_range() {
+        assert_eq!(apply_step(50, VolumeStep::Percent(-10), 5), 40);
+        assert_eq!(
+            apply_step(98, VolumeStep::Direction(StepDirection::Up), 5),
+            100
+        );
+        assert_eq!(
+            apply_step(3, VolumeStep::Direction(StepDirection::Down), 5),
+            0
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//! Audio-Moduswechsel als kleine Zustandsmaschine:
+//! lesen → anwenden (Skript schreibt `mopidy.conf` und startet Mopidy neu) →
+//! auf Mopidy warten → verifizieren → bei Fehlern auf den alten Modus zurück.
+
+use std::time::Duration;
+
+use tokio::sync::Mutex;
+use tokio::time::{self, Instant};
+use tracing::{info, instrument, warn};
+
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::metrics::METRICS;
+use crate::models::{AudioMode, ModeSwitchReport, ModeVerification};
+use crate::mopidy::MopidyClient;
+use crate::scripts;
+use crate::scripts::runner::ScriptOutput;
+
+const MOPIDY_RESTART_TIMEOUT: Duration = Duration::from_secs(20);
+const MOPIDY_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Serialisiert Moduswechsel; ein zweiter gleichzeitiger Wechsel wird mit 409 abgelehnt.
+#[derive(Default)]
+pub struct ModeSwitcher {
+    lock: Mutex<()>,
+}
+
+impl ModeSwitcher {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Aktueller Output-String aus `audio-mode show`.
+    pub async fn current(&self, config: &AppConfig) -> Result<String, AppError> {
+        let output = run_mode_script(config, "show").await?;
+        METRICS.set_audio_mode(AudioMode::infer(&output));
+        Ok(output)
+    }
+
+    #[instrument(skip(self, config, mopidy))]
+    pub async fn switch(
+        &self,
+        config: &AppConfig,
+        mopidy: &dyn MopidyClient,
+        target: AudioMode,
+    ) -> Result<ModeSwitchReport, AppError> {
+        let _guard = self
+            .lock
+            .try_lock()
+            .map_err(|_| AppError::conflict("audio mode switch already in progress"))?;
+
+        let previous_output = self.current(config).await?;
+        let previous_mode = AudioMode::infer(&previous_output);
+        let mut report = ModeSwitchReport {
+            previous_mode,
+            previous_output: previous_output.clone(),
+            requested_mode: target,
+            mode: previous_mode,
+            output: previous_output,
+            changed: false,
+            verification: ModeVerification::default(),
+            rolled_back: false,
+            script: None,
+            error: None,
+        };
+
+        if previous_mode != Some(target) {
+            match run_mode_script_output(config, target.as_str()).await {
+                Ok(output) => {
+                    report.changed = true;
+                    report.script = Some(output);
+                }
+                Err(err) => {
+                    report.error = Some(format!("applying {} failed: {err}", target.as_str()));
+                    if let AppError::ScriptFailed(output) = err {
+                        report.script = Some(*output);
+                    }
+                }
+            }
+        }
+
+        if report.error.is_none() {
+            // Auch ein fehlschlagendes `show` nach dem Anwenden gilt als
+            // gescheiterte Prüfung; der neue Modus ist dann unbestätigt.
+            match verify(config, mopidy, target).await {
+                Ok((verification, output)) => {
+                    report.verification = verification;
+                    report.mode = AudioMode::infer(&output);
+                    report.output = output;
+                    if report.verification.ok {
+                        info!("audio mode {} verified: {}", target.as_str(), report.output);
+                        return Ok(report);
+                    }
+                    report.error = Some(format!(
+                        "verification failed for {}: output '{}'",
+                        target.as_str(),
+                        report.output
+                    ));
+                }
+                Err(err) => {
+                    report.error = Some(format!("verifying {} failed: {err}", target.as_str()));
+                }
+            }
+        }
+
+        warn!("{}", report.error.as_deref().unwrap_or_default());
+        self.rollback(config, mopidy, &mut report).await;
+        Ok(report)
+    }
+
+    async fn rollback(
+        &self,
+        config: &AppConfig,
+        mopidy: &dyn MopidyClient,
+        report: &mut ModeSwitchReport,
+    ) {
+        let Some(previous) = report.previous_mode else {
+            warn!("previous audio mode unknown; skipping rollback");
+            return;
+        };
+        if let Err(err) = run_mode_script(config, previous.as_str()).await {
+            warn!("rollback to {} failed: {err}", previous.as_str());
+            return;
+        }
+        report.rolled_back = true;
+        wait_for_mopidy(config, mopidy).await;
+        if let Ok(output) = self.current(config).await {
+            report.mode = AudioMode::infer(&output);
+            report.output = output;
+        }
+        info!("audio mode rolled back to {}", previous.as_str());
+    }
+}
+
+async fn verify(
+    config: &AppConfig,
+    mopidy: &dyn MopidyClient,
+    target: AudioMode,
+) -> Result<(ModeVerification, String), AppError> {
+    let mopidy_reachable = wait_for_mopidy(config, mopidy).await;
+    let output = run_mode_script(config, "show").await?;
+    METRICS.set_audio_mode(AudioMode::infer(&output));
+    let output_matches = AudioMode::infer(&output) == Some(target);
+
+    let verification = ModeVerification {
+        ok: output_matches && mopidy_reachable != Some(false),
+        output_matches,
+        mopidy_reachable,
+    };
+    Ok((verification, output))
+}
+
+/// Wartet nach dem Neustart auf Mopidy; ohne `check_mopidy_health` wird nicht geprüft.
+async fn wait_for_mopidy(config: &AppConfig, mopidy: &dyn MopidyClient) -> Option<bool> {
+    if !config.check_mopidy_health {
+        return None;
+    }
+    let deadline = Instant::now() + MOPIDY_RESTART_TIMEOUT;
+    loop {
+        match mopidy.health_check().await {
+            Ok(()) => return Some(true),
+            Err(err) if Instant::now() >= deadline => {
+                warn!("Mopidy not reachable after mode switch: {err}");
+                return Some(false);
+            }
+            Err(_) => time::sleep(MOPIDY_POLL_INTERVAL).await,
+        }
+    }
+}
+
+async fn run_mode_script(config: &AppConfig, arg: &str) -> Result<String, AppError> {
+    let output = run_mode_script_output(config, arg).await?;
+    Ok(output.stdout.trim().into())
+}
+
+async fn run_mode_script_output(config: &AppConfig, arg: &str) -> Result<ScriptOutput, AppError> {
+    scripts::runner::run_configured(config, &config.audio_mode_script, &[arg], None).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn infer_uses_sink_element() {
+        assert_eq!(
+            AudioMode::infer("alsasink device=hw:MOTU_M2,0"),
+            Some(AudioMode::Alsa)
+        );
+        assert_eq!(
+            AudioMode::infer("audioresample ! pulsesink"),
+            Some(AudioMode::Pulse)
+        );
+        // Gerätenamen allein reichen nicht mehr (früher Substring-Treffer)
+        assert_eq!(AudioMode::infer("pipewiresink target=alsa_output"), None);
+        assert_eq!(AudioMode::infer("(output unset)"), None);
+    }
+}
@@ -1,6 +1,11 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+use crate::mopidy::types::RefType;
+use crate::scripts::runner::ScriptOutput;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
 #[serde(rename_all = "lowercase")]
 pub enum AudioMode {
     Pulse,
@@ -16,16 +21,17 @@ impl AudioMode {
         }
     }
 
+    /// Leitet den Modus aus dem Sink-Element des GStreamer-Output-Strings ab
+    /// (`alsasink device=…` bzw. `pulsesink`); andere Sinks ergeben `None`.
     #[must_use]
     pub fn infer(raw: &str) -> Option<Self> {
-        let normalized = raw.to_ascii_lowercase();
-        if normalized.contains("alsa") {
-            Some(AudioMode::Alsa)
-        } else if normalized.contains("pulse") {
-            Some(AudioMode::Pulse)
-        } else {
-            None
-        }
+        raw.split(|c: char| c.is_whitespace() || c == '!')
+            .map(str::to_ascii_lowercase)
+            .find_map(|element| match element.as_str() {
+                "alsasink" => Some(AudioMode::Alsa),
+                "pulsesink" => Some(AudioMode::Pulse),
+                _ => None,
+            })
     }
 }
 
@@ -41,24 +47,90 @@ pub struct ModeGetResponse {
     pub mode: Option<AudioMode>,
 }
 
+/// Ergebnis eines Moduswechsels inkl. Verifikation und ggf. Rollback.
+#[derive(Debug, Serialize)]
+pub struct ModeSwitchReport {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_mode: Option<AudioMode>,
+    pub previous_output: String,
+    pub requested_mode: AudioMode,
+    /// Modus laut Konfiguration nach Abschluss (nach Rollback ggf. wieder der alte).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<AudioMode>,
+    pub output: String,
+    pub changed: bool,
+    pub verification: ModeVerification,
+    pub rolled_back: bool,
+    /// Lauf des Skripts, das den Modus angewendet hat.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub script: Option<ScriptOutput>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Ergebnis von `POST /admin/reload` bzw. SIGHUP.
 #[derive(Debug, Serialize)]
-pub struct CommandResponse {
-    pub stdout: String,
-    #[serde(skip_serializing_if = "String::is_empty")]
-    pub stderr: String,
+pub struct ReloadReport {
+    pub reloaded: bool,
+    /// Geänderte Schlüssel (Notation wie in der TOML-Datei).
+    pub changed: Vec<String>,
+    /// Änderungen, die erst nach einem Neustart greifen (Bind, Recorder, Event-Stream).
+    pub restart_required: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Default)]
+pub struct ModeVerification {
+    pub ok: bool,
+    /// Output-String passt zum angeforderten Modus.
+    pub output_matches: bool,
+    /// Mopidy nach dem Neustart erreichbar; `None`, wenn nicht geprüft.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mopidy_reachable: Option<bool>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct PlaylistRequest {
     pub name: String,
     pub uris: Vec<String>,
+    /// Bestehende Playlist gleichen Namens überschreiben statt mit 409 abzulehnen.
+    #[serde(default)]
+    pub replace: bool,
+    /// `uri_scheme`-Hinweis für Mopidy (z. B. `m3u`, `qobuz`).
+    #[serde(default)]
+    pub scheme: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PlaylistAppendRequest {
+    pub uri: String,
+    pub uris: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PlaylistDeleteQuery {
+    pub uri: String,
 }
 
 #[derive(Debug, Serialize)]
 pub struct PlaylistResponse {
-    pub stdout: String,
-    #[serde(skip_serializing_if = "String::is_empty")]
-    pub stderr: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uri: Option<String>,
+    pub name: String,
+    /// Anzahl Tracks, die Mopidy nach dem Speichern in der Playlist führt.
+    pub tracks: usize,
+    pub added: usize,
+    pub rejected: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub rejected_uris: Vec<String>,
+    pub replaced: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlaylistDeleteResponse {
+    pub uri: String,
+    pub deleted: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -75,6 +147,22 @@ pub struct MopidyHealth {
     pub status: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub detail: Option<String>,
+    /// Zustand des Circuit Breakers (nur beim HTTP-Client).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub circuit: Option<CircuitState>,
+    /// Bei offenem Breaker: Wartezeit bis zum nächsten Versuch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after_ms: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    Closed,
+    /// Ein Probe-Aufruf darf durch.
+    HalfOpen,
+    /// Aufrufe scheitern sofort.
+    Open,
 }
 
 #[derive(Debug, Deserialize)]
@@ -82,21 +170,637 @@ pub struct SimilarQuery {
     pub seed: String,
     #[serde(default)]
     pub limit: Option<usize>,
+    /// Kommagetrennte Strategien (`artist,album,genre,year,co_artist,query`).
+    #[serde(default)]
+    pub strategy: Option<String>,
+    /// Live-/Remaster-/Karaoke-Varianten nicht herausfiltern.
+    #[serde(default)]
+    pub variants: bool,
 }
 
-#[derive(Debug, Serialize, Clone)]
-pub struct SimilarTrack {
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum SimilarStrategy {
+    /// Gleicher Künstler, andere Alben.
+    Artist,
+    /// Weitere Tracks des Seed-Albums.
+    Album,
+    /// Gleiches Genre.
+    Genre,
+    /// Gleiches Erscheinungsjahr; eigene Suche mit geringem Gewicht.
+    Year,
+    /// Tracks der weiteren beteiligten Künstler.
+    CoArtist,
+    /// Freitextsuche `"<Künstler> <Titel>"` (bisheriges Verhalten).
+    Query,
+}
+
+impl SimilarStrategy {
+    pub const ALL: [SimilarStrategy; 6] = [
+        SimilarStrategy::Artist,
+        SimilarStrategy::Album,
+        SimilarStrategy::Genre,
+        SimilarStrategy::Year,
+        SimilarStrategy::CoArtist,
+        SimilarStrategy::Query,
+    ];
+
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SimilarStrategy::Artist => "artist",
+            SimilarStrategy::Album => "album",
+            SimilarStrategy::Genre => "genre",
+            SimilarStrategy::Year => "year",
+            SimilarStrategy::CoArtist => "co_artist",
+            SimilarStrategy::Query => "query",
+        }
+    }
+
+    /// Gewicht eines Treffers dieser Strategie im Gesamtscore.
+    #[must_use]
+    pub fn weight(&self) -> f64 {
+        match self {
+            SimilarStrategy::Artist => 1.0,
+            SimilarStrategy::CoArtist => 0.8,
+            SimilarStrategy::Album => 0.6,
+            SimilarStrategy::Genre => 0.5,
+            SimilarStrategy::Query => 0.3,
+            SimilarStrategy::Year => 0.2,
+        }
+    }
+}
+
+impl std::str::FromStr for SimilarStrategy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        SimilarStrategy::ALL
+            .into_iter()
+            .find(|strategy| strategy.as_str() == value)
+            .ok_or_else(|| format!("unknown strategy: {value}"))
+    }
+}
+
+/// Einheitliche Track-Darstellung aller Endpunkte (Wiedergabe, Queue, Bibliothek, Suche).
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct TrackInfo {
     pub uri: String,
     pub name: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub album: Option<String>,
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     pub artists: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub duration_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub track_no: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub disc_no: Option<u32>,
+    /// Wie von Mopidy geliefert (`YYYY` oder `YYYY-MM-DD`).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub date: Option<String>,
+    /// kbit/s.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub bitrate: Option<u32>,
+}
+
+/// Treffer von `/discover/similar`: Track plus Bewertung der Strategien.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct SimilarTrack {
+    #[serde(flatten)]
+    pub track: TrackInfo,
+    /// Summe der Strategie-Gewichte.
+    pub score: f64,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
+    pub scores: BTreeMap<SimilarStrategy, f64>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct SimilarResponse {
-    pub seed: SimilarTrack,
+    pub seed: TrackInfo,
     pub query: String,
+    pub strategies: Vec<SimilarStrategy>,
     pub tracks: Vec<SimilarTrack>,
 }
+
+#[derive(Debug, Deserialize, Default)]
+pub struct RecordingStartRequest {
+    #[serde(default)]
+    pub rate: Option<u32>,
+    #[serde(default)]
+    pub channels: Option<u16>,
+    #[serde(default)]
+    pub format: Option<String>,
+    #[serde(default)]
+    pub device: Option<String>,
+    #[serde(default)]
+    pub output: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+#[serde(rename_all = "UPPERCASE")]
+#[value(rename_all = "UPPER")]
+pub enum StopSignal {
+    Int,
+    Term,
+    Kill,
+}
+
+impl StopSignal {
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StopSignal::Int => "INT",
+            StopSignal::Term => "TERM",
+            StopSignal::Kill => "KILL",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct RecordingStopRequest {
+    /// Erstes Signal; der Supervisor eskaliert danach über TERM bis KILL.
+    #[serde(default)]
+    pub signal: Option<StopSignal>,
+    /// Wartezeit in Sekunden pro Eskalationsstufe.
+    #[serde(default)]
+    pub timeout: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordingStartResponse {
+    pub pid: u32,
+    pub output: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub command: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordingStopResponse {
+    pub pid: u32,
+    /// Signal, auf das der Recorder reagiert hat (`None` bei verwaister PID-Datei).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signal: Option<StopSignal>,
+    pub killed: bool,
+    /// `true`, wenn der Recorder schon vorher beendet war (verwaiste PID-Datei).
+    #[serde(default)]
+    pub stale: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordingStatus {
+    pub running: bool,
+    /// `true`, solange ein `stop` den Recorder signalisiert und auf sein Ende wartet.
+    #[serde(default)]
+    pub stopping: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PlaybackState {
+    Playing,
+    Paused,
+    Stopped,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct PlayRequest {
+    /// Tracklist-ID aus der Queue; ohne Angabe spielt Mopidy den aktuellen Track.
+    #[serde(default)]
+    pub tlid: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SeekRequest {
+    /// Zielposition in Millisekunden.
+    pub position: u64,
+}
+
+/// Welcher Mixer die Lautstärke stellt.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MixerKind {
+    Mopidy,
+    Hardware,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VolumeStatus {
+    pub mixer: MixerKind,
+    /// 0–100; `None`, wenn der Mixer keinen Wert meldet.
+    pub volume: Option<u8>,
+    pub mute: Option<bool>,
+    /// Nur beim Hardware-Mixer.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub db: Option<f64>,
+}
+
+/// Entweder `volume` (absolut) oder `step` (relativ).
+#[derive(Debug, Deserialize)]
+pub struct VolumeRequest {
+    pub volume: Option<u8>,
+    pub step: Option<VolumeStep>,
+    /// Über diese Dauer überblenden statt springen.
+    pub ramp_ms: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[serde(untagged)]
+pub enum VolumeStep {
+    /// Prozentpunkte, z. B. `5` oder `-10`.
+    Percent(i16),
+    /// `"up"`/`"down"` um `mixer.step`.
+    Direction(StepDirection),
+}
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum StepDirection {
+    Up,
+    Down,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MuteRequest {
+    pub mute: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MuteStatus {
+    pub mixer: MixerKind,
+    pub mute: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlaybackStatus {
+    pub state: PlaybackState,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub track: Option<TrackInfo>,
+    /// Position im aktuellen Track in Millisekunden.
+    pub time_position: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TracklistOption {
+    Consume,
+    Random,
+    Repeat,
+    Single,
+}
+
+impl TracklistOption {
+    pub const ALL: [TracklistOption; 4] = [
+        TracklistOption::Consume,
+        TracklistOption::Random,
+        TracklistOption::Repeat,
+        TracklistOption::Single,
+    ];
+
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TracklistOption::Consume => "consume",
+            TracklistOption::Random => "random",
+            TracklistOption::Repeat => "repeat",
+            TracklistOption::Single => "single",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct QueueEntry {
+    pub tlid: u64,
+    pub track: TrackInfo,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+pub struct QueueOptions {
+    pub consume: bool,
+    pub random: bool,
+    pub repeat: bool,
+    pub single: bool,
+}
+
+impl QueueOptions {
+    pub fn set(&mut self, option: TracklistOption, value: bool) {
+        match option {
+            TracklistOption::Consume => self.consume = value,
+            TracklistOption::Random => self.random = value,
+            TracklistOption::Repeat => self.repeat = value,
+            TracklistOption::Single => self.single = value,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct QueueResponse {
+    pub tracks: Vec<QueueEntry>,
+    pub options: QueueOptions,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TrackRef {
+    pub uri: String,
+}
+
+/// `tracks` nimmt z. B. direkt das `tracks`-Array aus `/discover/similar` entgegen.
+#[derive(Debug, Deserialize)]
+pub struct QueueAddRequest {
+    #[serde(default)]
+    pub uris: Vec<String>,
+    #[serde(default)]
+    pub tracks: Vec<TrackRef>,
+    #[serde(default)]
+    pub at_position: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct QueueAddResponse {
+    pub added: Vec<QueueEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QueueRemoveRequest {
+    pub tlids: Vec<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct QueueRemoveResponse {
+    pub removed: Vec<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QueueMoveRequest {
+    pub start: u64,
+    pub end: u64,
+    pub to_position: u64,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct QueueShuffleRequest {
+    #[serde(default)]
+    pub start: Option<u64>,
+    #[serde(default)]
+    pub end: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct QueueOptionsUpdate {
+    #[serde(default)]
+    pub consume: Option<bool>,
+    #[serde(default)]
+    pub random: Option<bool>,
+    #[serde(default)]
+    pub repeat: Option<bool>,
+    #[serde(default)]
+    pub single: Option<bool>,
+}
+
+impl QueueOptionsUpdate {
+    #[must_use]
+    pub fn get(&self, option: TracklistOption) -> Option<bool> {
+        match option {
+            TracklistOption::Consume => self.consume,
+            TracklistOption::Random => self.random,
+            TracklistOption::Repeat => self.repeat,
+            TracklistOption::Single => self.single,
+        }
+    }
+}
+
+/// Eintrag von `GET /actions`.
+#[derive(Debug, Serialize)]
+pub struct ActionInfo {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// JSON-Schema der Argumentliste.
+    pub schema: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ActionRequest {
+    /// Wird gegen das Schema der Aktion geprüft; Default `[]`.
+    #[serde(default = "empty_args")]
+    pub args: serde_json::Value,
+}
+
+fn empty_args() -> serde_json::Value {
+    serde_json::Value::Array(Vec::new())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JobRequest {
+    /// `playlist_from_list` oder `audio_mode` (einziges Argument: `pulse`/`alsa`).
+    pub script: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Wird auf stdin geschrieben (z. B. URI-Liste für `playlist_from_list`).
+    #[serde(default)]
+    pub input: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Running,
+    Succeeded,
+    Failed,
+    TimedOut,
+    Cancelled,
+}
+
+impl JobState {
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        *self != JobState::Running
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct JobStatus {
+    pub id: u64,
+    pub script: String,
+    pub args: Vec<String>,
+    pub state: JobState,
+    pub started_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    /// Abschließendes JSON-Objekt des Skripts (siehe `ScriptOutput::result`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<serde_json::Map<String, serde_json::Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Anzahl bisher ausgegebener Zeilen (stdout + stderr).
+    pub lines: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LibraryBrowseQuery {
+    /// Ohne `uri`: Wurzelverzeichnisse aller Backends.
+    #[serde(default)]
+    pub uri: Option<String>,
+    #[serde(default)]
+    pub offset: usize,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct LibraryRef {
+    pub uri: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(rename = "type")]
+    pub kind: RefType,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LibraryBrowseResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uri: Option<String>,
+    /// Einträge im Verzeichnis insgesamt (vor `offset`/`limit`).
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+    pub items: Vec<LibraryRef>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LibraryUrisQuery {
+    /// Kommagetrennte URIs.
+    pub uris: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LibraryLookupResponse {
+    /// Tracks je angefragter URI (Alben und Künstler liefern mehrere).
+    pub tracks: BTreeMap<String, Vec<TrackInfo>>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct LibraryImage {
+    pub uri: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LibraryImagesResponse {
+    pub images: BTreeMap<String, Vec<LibraryImage>>,
+}
+
+/// Feldsuche; mindestens ein Feld muss gesetzt sein, Felder werden UND-verknüpft.
+#[derive(Debug, Deserialize, Default)]
+pub struct SearchQuery {
+    #[serde(default)]
+    pub any: Option<String>,
+    #[serde(default)]
+    pub artist: Option<String>,
+    #[serde(default)]
+    pub album: Option<String>,
+    #[serde(default)]
+    pub track_name: Option<String>,
+    #[serde(default)]
+    pub genre: Option<String>,
+    #[serde(default)]
+    pub date: Option<String>,
+    #[serde(default)]
+    pub albumartist: Option<String>,
+    #[serde(default)]
+    pub composer: Option<String>,
+    #[serde(default)]
+    pub exact: bool,
+    /// Kommagetrennte Backends bzw. URI-Präfixe (`qobuz:`, `local:`); leer = alle.
+    #[serde(default)]
+    pub uris: Option<String>,
+    #[serde(default)]
+    pub offset: usize,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+impl SearchQuery {
+    /// Gesetzte Felder mit Mopidy-Feldnamen.
+    #[must_use]
+    pub fn fields(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("any", &self.any),
+            ("artist", &self.artist),
+            ("album", &self.album),
+            ("track_name", &self.track_name),
+            ("genre", &self.genre),
+            ("date", &self.date),
+            ("albumartist", &self.albumartist),
+            ("composer", &self.composer),
+        ]
+        .into_iter()
+        .filter_map(|(field, value)| {
+            let value = value.as_deref()?.trim();
+            (!value.is_empty()).then_some((field, value))
+        })
+        .collect()
+    }
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct SearchAlbum {
+    pub uri: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub artists: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_tracks: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct SearchArtist {
+    pub uri: String,
+    pub name: String,
+}
+
+/// Trefferzahlen vor `offset`/`limit`.
+#[derive(Debug, Serialize, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SearchTotals {
+    pub tracks: usize,
+    pub albums: usize,
+    pub artists: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchBackend {
+    /// URI-Schema des Backends (`qobuz`, `local`, …).
+    pub backend: String,
+    pub total: SearchTotals,
+    pub tracks: Vec<TrackInfo>,
+    pub albums: Vec<SearchAlbum>,
+    pub artists: Vec<SearchArtist>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchResponse {
+    pub exact: bool,
+    pub offset: usize,
+    pub limit: usize,
+    pub backends: Vec<SearchBackend>,
+}
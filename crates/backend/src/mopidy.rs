@@ -1,128 +1,654 @@
+mod breaker;
+pub mod types;
+
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
 use async_trait::async_trait;
 use serde_json::{json, Map, Value};
+use tokio::time;
+use tracing::debug;
 use url::Url;
 
+use crate::config::MopidyConfig;
 use crate::error::AppError;
+use crate::metrics::METRICS;
+use crate::models::{CircuitState, PlaybackState, TracklistOption};
+
+use types::{decode, decode_list, decode_map, Image, Playlist, Ref, SearchResult, TlTrack, Track};
+
+use breaker::CircuitBreaker;
+
+/// Prozessweite Request-IDs für Clients ohne eigenen Zähler.
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
 
 #[async_trait]
 pub trait MopidyClient: Send + Sync + 'static {
     async fn proxy(&self, payload: Value) -> Result<Value, AppError>;
 
+    /// Eindeutige JSON-RPC-ID für den nächsten Request.
+    fn next_id(&self) -> u64 {
+        NEXT_ID.fetch_add(1, Ordering::Relaxed)
+    }
+
     async fn call_method(&self, method: &str, params: Option<Value>) -> Result<Value, AppError> {
-        let mut payload = Map::new();
-        payload.insert("jsonrpc".into(), Value::String("2.0".into()));
-        payload.insert("id".into(), Value::from(1));
-        payload.insert("method".into(), Value::String(method.into()));
-        if let Some(params) = params {
-            payload.insert("params".into(), params);
+        let id = self.next_id();
+        let response = self.proxy(request(id, method, params)).await?;
+        rpc_result(response, id)
+    }
+
+    /// Mehrere Aufrufe in einem JSON-RPC-Batch; Ergebnisse in Aufrufreihenfolge.
+    /// Ein Fehler in einem Eintrag lässt den ganzen Batch scheitern.
+    async fn call_batch(&self, calls: &[(&str, Option<Value>)]) -> Result<Vec<Value>, AppError> {
+        if calls.is_empty() {
+            return Ok(Vec::new());
         }
+        let ids: Vec<u64> = calls.iter().map(|_| self.next_id()).collect();
+        let payload = ids
+            .iter()
+            .zip(calls)
+            .map(|(id, (method, params))| request(*id, method, params.clone()))
+            .collect();
+
+        let Value::Array(responses) = self.proxy(Value::Array(payload)).await? else {
+            return Err(AppError::upstream("Mopidy batch response is not an array"));
+        };
+        // Mopidy darf Batch-Antworten in beliebiger Reihenfolge liefern.
+        let mut by_id = HashMap::new();
+        for response in responses {
+            let id = response.get("id").and_then(Value::as_u64).ok_or_else(|| {
+                AppError::upstream(format!("Mopidy batch response without id: {response}"))
+            })?;
+            if by_id.insert(id, response).is_some() {
+                return Err(AppError::upstream(format!(
+                    "duplicate Mopidy response id {id}"
+                )));
+            }
+        }
+        let results = ids
+            .iter()
+            .map(|id| {
+                let response = by_id
+                    .remove(id)
+                    .ok_or_else(|| AppError::upstream(format!("Mopidy response {id} missing")))?;
+                rpc_result(response, *id)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(id) = by_id.keys().next() {
+            return Err(AppError::upstream(format!(
+                "unexpected Mopidy response id {id}"
+            )));
+        }
+        Ok(results)
+    }
 
-        let response = self.proxy(Value::Object(payload)).await?;
-        if let Some(error) = response.get("error") {
-            let message = error
-                .get("message")
-                .and_then(Value::as_str)
-                .unwrap_or("unknown Mopidy error");
-            return Err(AppError::upstream(message));
+    async fn lookup_track(&self, uri: &str) -> Result<Option<Track>, AppError> {
+        let result = self
+            .call_method("core.library.lookup", Some(json!({ "uri": uri })))
+            .await?;
+
+        Ok(decode_list(result).into_iter().next())
+    }
+
+    /// Tracks je URI in einem Aufruf (Alben und Künstler liefern mehrere).
+    async fn lookup_tracks(
+        &self,
+        uris: &[String],
+    ) -> Result<BTreeMap<String, Vec<Track>>, AppError> {
+        let result = self
+            .call_method("core.library.lookup", Some(json!({ "uris": uris })))
+            .await?;
+
+        Ok(decode_map(result))
+    }
+
+    /// Verzeichniseinträge; ohne `uri` die Wurzelverzeichnisse aller Backends.
+    async fn browse(&self, uri: Option<&str>) -> Result<Vec<Ref>, AppError> {
+        let result = self
+            .call_method("core.library.browse", Some(json!({ "uri": uri })))
+            .await?;
+
+        Ok(decode_list(result))
+    }
+
+    async fn images(&self, uris: &[String]) -> Result<BTreeMap<String, Vec<Image>>, AppError> {
+        let result = self
+            .call_method("core.library.get_images", Some(json!({ "uris": uris })))
+            .await?;
+
+        Ok(decode_map(result))
+    }
+
+    async fn search_any(&self, query: &str) -> Result<Vec<SearchResult>, AppError> {
+        self.search(json!({ "any": [query] }), &[], false).await
+    }
+
+    /// Feldsuche (`{"artist": [...], "album": [...]}`); Felder werden von Mopidy UND-verknüpft.
+    /// `uris` beschränkt die Suche auf Backends (z. B. `qobuz:`); leer = alle.
+    async fn search(
+        &self,
+        query: Value,
+        uris: &[String],
+        exact: bool,
+    ) -> Result<Vec<SearchResult>, AppError> {
+        let mut params = json!({ "query": query, "exact": exact });
+        if !uris.is_empty() {
+            params["uris"] = json!(uris);
         }
+        let result = self
+            .call_method("core.library.search", Some(params))
+            .await?;
+
+        Ok(decode_list(result))
+    }
+
+    async fn play(&self, tlid: Option<u64>) -> Result<(), AppError> {
+        let params = tlid.map(|tlid| json!({ "tlid": tlid }));
+        self.call_method("core.playback.play", params).await?;
+        Ok(())
+    }
+
+    async fn pause(&self) -> Result<(), AppError> {
+        self.call_method("core.playback.pause", None).await?;
+        Ok(())
+    }
+
+    async fn resume(&self) -> Result<(), AppError> {
+        self.call_method("core.playback.resume", None).await?;
+        Ok(())
+    }
+
+    async fn stop(&self) -> Result<(), AppError> {
+        self.call_method("core.playback.stop", None).await?;
+        Ok(())
+    }
+
+    async fn next(&self) -> Result<(), AppError> {
+        self.call_method("core.playback.next", None).await?;
+        Ok(())
+    }
 
-        response
-            .get("result")
-            .cloned()
-            .ok_or_else(|| AppError::upstream("Mopidy response missing result"))
+    async fn previous(&self) -> Result<(), AppError> {
+        self.call_method("core.playback.previous", None).await?;
+        Ok(())
     }
 
-    async fn lookup_track(&self, uri: &str) -> Result<Option<Value>, AppError> {
+    /// Liefert `false`, wenn Mopidy den Sprung ablehnt (z. B. nichts spielt).
+    async fn seek(&self, time_position_ms: u64) -> Result<bool, AppError> {
         let result = self
-            .call_method("core.library.lookup", Some(json!({ "uri": uri })))
+            .call_method(
+                "core.playback.seek",
+                Some(json!({ "time_position": time_position_ms })),
+            )
+            .await?;
+
+        Ok(result.as_bool().unwrap_or(false))
+    }
+
+    async fn playback_state(&self) -> Result<PlaybackState, AppError> {
+        let result = self.call_method("core.playback.get_state", None).await?;
+
+        serde_json::from_value(result.clone())
+            .map_err(|_| AppError::upstream(format!("unexpected playback state {result}")))
+    }
+
+    async fn current_track(&self) -> Result<Option<Track>, AppError> {
+        let result = self
+            .call_method("core.playback.get_current_track", None)
             .await?;
 
-        Ok(result.as_array().and_then(|arr| arr.first().cloned()))
+        Ok(decode(result))
     }
 
-    async fn search_any(&self, query: &str) -> Result<Vec<Value>, AppError> {
+    async fn time_position(&self) -> Result<u64, AppError> {
+        let result = self
+            .call_method("core.playback.get_time_position", None)
+            .await?;
+
+        Ok(result.as_u64().unwrap_or_default())
+    }
+
+    /// `None`, wenn Mopidy keinen Mixer hat (z. B. `mixer = none` im ALSA-Modus).
+    async fn volume(&self) -> Result<Option<u8>, AppError> {
+        let result = self.call_method("core.mixer.get_volume", None).await?;
+
+        Ok(result.as_u64().and_then(|volume| u8::try_from(volume).ok()))
+    }
+
+    /// Liefert `false`, wenn der Mixer den Wert nicht übernimmt.
+    async fn set_volume(&self, volume: u8) -> Result<bool, AppError> {
+        let result = self
+            .call_method("core.mixer.set_volume", Some(json!({ "volume": volume })))
+            .await?;
+
+        Ok(result.as_bool().unwrap_or(false))
+    }
+
+    async fn mute(&self) -> Result<Option<bool>, AppError> {
+        let result = self.call_method("core.mixer.get_mute", None).await?;
+
+        Ok(result.as_bool())
+    }
+
+    async fn set_mute(&self, mute: bool) -> Result<bool, AppError> {
+        let result = self
+            .call_method("core.mixer.set_mute", Some(json!({ "mute": mute })))
+            .await?;
+
+        Ok(result.as_bool().unwrap_or(false))
+    }
+
+    /// Hängt URIs an (oder fügt sie ab `at_position` ein); liefert die neuen TlTracks.
+    async fn tracklist_add(
+        &self,
+        uris: &[String],
+        at_position: Option<u64>,
+    ) -> Result<Vec<TlTrack>, AppError> {
+        let mut params = json!({ "uris": uris });
+        if let Some(position) = at_position {
+            params["at_position"] = Value::from(position);
+        }
+        let result = self.call_method("core.tracklist.add", Some(params)).await?;
+
+        Ok(decode_list(result))
+    }
+
+    async fn tracklist_remove(&self, tlids: &[u64]) -> Result<Vec<TlTrack>, AppError> {
         let result = self
             .call_method(
-                "core.library.search",
-                Some(json!({
-                    "query": {
-                        "any": [query],
-                    },
-                    "exact": false,
-                })),
+                "core.tracklist.remove",
+                Some(json!({ "criteria": { "tlid": tlids } })),
             )
             .await?;
 
-        Ok(result.as_array().cloned().unwrap_or_default())
+        Ok(decode_list(result))
     }
 
-    async fn health_check(&self) -> Result<(), String> {
-        let payload = json!({
-            "jsonrpc": "2.0",
-            "id": 1,
-            "method": "core.playback.get_state",
-        });
+    /// Verschiebt die Slice `[start, end)` an `to_position`.
+    async fn tracklist_move(&self, start: u64, end: u64, to_position: u64) -> Result<(), AppError> {
+        self.call_method(
+            "core.tracklist.move",
+            Some(json!({ "start": start, "end": end, "to_position": to_position })),
+        )
+        .await?;
+        Ok(())
+    }
 
-        match self.proxy(payload).await {
-            Ok(value) => {
-                if let Some(error) = value.get("error") {
-                    let message = error
-                        .get("message")
-                        .and_then(Value::as_str)
-                        .unwrap_or("RPC error returned");
-                    Err(message.into())
-                } else {
-                    Ok(())
-                }
-            }
-            Err(err) => Err(err.to_string()),
+    async fn tracklist_clear(&self) -> Result<(), AppError> {
+        self.call_method("core.tracklist.clear", None).await?;
+        Ok(())
+    }
+
+    async fn tracklist_shuffle(
+        &self,
+        start: Option<u64>,
+        end: Option<u64>,
+    ) -> Result<(), AppError> {
+        self.call_method(
+            "core.tracklist.shuffle",
+            Some(json!({ "start": start, "end": end })),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn tl_tracks(&self) -> Result<Vec<TlTrack>, AppError> {
+        let result = self
+            .call_method("core.tracklist.get_tl_tracks", None)
+            .await?;
+
+        Ok(decode_list(result))
+    }
+
+    async fn tracklist_option(&self, option: TracklistOption) -> Result<bool, AppError> {
+        let method = format!("core.tracklist.get_{}", option.as_str());
+        let result = self.call_method(&method, None).await?;
+
+        Ok(result.as_bool().unwrap_or(false))
+    }
+
+    async fn set_tracklist_option(
+        &self,
+        option: TracklistOption,
+        value: bool,
+    ) -> Result<(), AppError> {
+        let method = format!("core.tracklist.set_{}", option.as_str());
+        self.call_method(&method, Some(json!({ "value": value })))
+            .await?;
+        Ok(())
+    }
+
+    /// Liefert die Playlist-`Ref`s (`uri`, `name`) aller Backends.
+    async fn playlists(&self) -> Result<Vec<Ref>, AppError> {
+        let result = self.call_method("core.playlists.as_list", None).await?;
+
+        Ok(decode_list(result))
+    }
+
+    async fn playlist_lookup(&self, uri: &str) -> Result<Option<Playlist>, AppError> {
+        let result = self
+            .call_method("core.playlists.lookup", Some(json!({ "uri": uri })))
+            .await?;
+
+        Ok(decode(result))
+    }
+
+    async fn playlist_create(
+        &self,
+        name: &str,
+        uri_scheme: Option<&str>,
+    ) -> Result<Playlist, AppError> {
+        let mut params = json!({ "name": name });
+        if let Some(scheme) = uri_scheme {
+            params["uri_scheme"] = Value::String(scheme.into());
         }
+        let result = self
+            .call_method("core.playlists.create", Some(params))
+            .await?;
+
+        decode(result).ok_or_else(|| AppError::upstream("Mopidy did not create the playlist"))
+    }
+
+    /// Speichert die Playlist; `None`, wenn das Backend das Speichern verweigert.
+    async fn playlist_save(&self, playlist: &Playlist) -> Result<Option<Playlist>, AppError> {
+        let result = self
+            .call_method("core.playlists.save", Some(json!({ "playlist": playlist })))
+            .await?;
+
+        Ok(decode(result))
+    }
+
+    async fn playlist_delete(&self, uri: &str) -> Result<bool, AppError> {
+        let result = self
+            .call_method("core.playlists.delete", Some(json!({ "uri": uri })))
+            .await?;
+
+        // Mopidy < 2.2 liefert `null` statt eines Bools.
+        Ok(result.as_bool().unwrap_or(true))
+    }
+
+    /// Zustand des Circuit Breakers und ggf. Restzeit; `None` ohne Breaker.
+    fn circuit(&self) -> Option<(CircuitState, Option<Duration>)> {
+        None
+    }
+
+    async fn health_check(&self) -> Result<(), String> {
+        self.call_method("core.playback.get_state", None)
+            .await
+            .map(|_| ())
+            .map_err(|err| err.to_string())
+    }
+}
+
+fn request(id: u64, method: &str, params: Option<Value>) -> Value {
+    let mut payload = Map::new();
+    payload.insert("jsonrpc".into(), Value::String("2.0".into()));
+    payload.insert("id".into(), Value::from(id));
+    payload.insert("method".into(), Value::String(method.into()));
+    if let Some(params) = params {
+        payload.insert("params".into(), params);
     }
+    Value::Object(payload)
 }
 
+/// Ergebnis einer Antwort, deren ID zum Request passen muss. Fehler ohne ID
+/// (z. B. Parse-Fehler laut JSON-RPC) werden trotzdem gemeldet.
+fn rpc_result(mut response: Value, id: u64) -> Result<Value, AppError> {
+    let error = response.get("error").filter(|error| !error.is_null());
+    let response_id = response.get("id").unwrap_or(&Value::Null);
+    if response_id.as_u64() != Some(id) && !(response_id.is_null() && error.is_some()) {
+        return Err(AppError::upstream(format!(
+            "Mopidy response id {response_id} does not match request id {id}"
+        )));
+    }
+    if let Some(error) = error {
+        let message = error
+            .get("message")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown Mopidy error");
+        return Err(AppError::upstream(message));
+    }
+
+    response
+        .get_mut("result")
+        .map(Value::take)
+        .ok_or_else(|| AppError::upstream("Mopidy response missing result"))
+}
+
+/// JSON-RPC über HTTP mit Timeouts, Retries (nur lesende Methoden) und
+/// Circuit Breaker; ein Reload baut Client und Breaker neu.
 #[derive(Clone)]
 pub struct HttpMopidyClient {
     client: reqwest::Client,
     url: Url,
+    next_id: Arc<AtomicU64>,
+    retries: u32,
+    retry_backoff: Duration,
+    breaker: Arc<CircuitBreaker>,
 }
 
 impl HttpMopidyClient {
+    /// Mit Standardwerten für Retries und Breaker; Timeouts bringt `client` mit.
     #[must_use]
     pub fn new(client: reqwest::Client, url: Url) -> Self {
-        Self { client, url }
+        Self::with_client(client, url, &MopidyConfig::default())
+    }
+
+    #[must_use]
+    pub fn from_config(config: &MopidyConfig, url: Url) -> Self {
+        let client = reqwest::Client::builder()
+            .connect_timeout(config.connect_timeout)
+            .timeout(config.request_timeout)
+            .build()
+            // Scheitert wie `Client::new` nur, wenn das TLS-Backend fehlt.
+            .expect("HTTP client can be built");
+        Self::with_client(client, url, config)
+    }
+
+    fn with_client(client: reqwest::Client, url: Url, config: &MopidyConfig) -> Self {
+        Self {
+            client,
+            url,
+            next_id: Arc::new(AtomicU64::new(1)),
+            retries: config.retries,
+            retry_backoff: config.retry_backoff,
+            breaker: Arc::new(CircuitBreaker::new(
+                config.breaker_threshold,
+                config.breaker_cooldown,
+            )),
+        }
     }
 }
 
 #[async_trait]
 impl MopidyClient for HttpMopidyClient {
     async fn proxy(&self, payload: Value) -> Result<Value, AppError> {
-        send_rpc(&self.client, &self.url, payload).await
+        let label = match &payload {
+            Value::Array(_) => "batch",
+            other => other
+                .get("method")
+                .and_then(Value::as_str)
+                .unwrap_or("unknown"),
+        }
+        .to_string();
+        let permit = self.breaker.acquire().map_err(|wait| {
+            AppError::upstream(format!(
+                "Mopidy unavailable (circuit open, next attempt in {}s)",
+                wait.as_secs() + u64::from(wait.subsec_nanos() > 0)
+            ))
+        })?;
+        let retries = if is_idempotent(&payload) {
+            self.retries
+        } else {
+            0
+        };
+
+        let mut attempt = 0;
+        let result = loop {
+            let started = Instant::now();
+            let result = send_rpc(&self.client, &self.url, &payload).await;
+            for (method, failed) in observations(&payload, result.as_ref().ok()) {
+                METRICS.observe_mopidy(method, started, failed);
+            }
+
+            match result {
+                Err(failure) if failure.transient && attempt < retries => {
+                    attempt += 1;
+                    let delay = backoff(self.retry_backoff, attempt);
+                    debug!("retrying {label} in {delay:?} ({})", failure.error);
+                    for (method, _) in observations(&payload, None) {
+                        METRICS.observe_mopidy_retry(method);
+                    }
+                    time::sleep(delay).await;
+                }
+                result => break result,
+            }
+        };
+
+        // Nur Transportfehler sprechen gegen Mopidy; RPC-Fehler nicht.
+        match &result {
+            Err(failure) if failure.transient => permit.failure(),
+            _ => permit.success(),
+        }
+        result.map_err(|failure| failure.error)
+    }
+
+    fn next_id(&self) -> u64 {
+        self.next_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn circuit(&self) -> Option<(CircuitState, Option<Duration>)> {
+        Some(self.breaker.state())
     }
 }
 
-async fn send_rpc(client: &reqwest::Client, url: &Url, payload: Value) -> Result<Value, AppError> {
+/// Fehlschlag eines Versuchs; `transient` = Mopidy nicht (richtig) erreichbar.
+struct RpcFailure {
+    error: AppError,
+    transient: bool,
+}
+
+impl RpcFailure {
+    fn transient(message: String) -> Self {
+        Self {
+            error: AppError::upstream(message),
+            transient: true,
+        }
+    }
+
+    fn permanent(message: String) -> Self {
+        Self {
+            error: AppError::upstream(message),
+            transient: false,
+        }
+    }
+}
+
+async fn send_rpc(
+    client: &reqwest::Client,
+    url: &Url,
+    payload: &Value,
+) -> Result<Value, RpcFailure> {
     let response = client
         .post(url.as_str())
-        .json(&payload)
+        .json(payload)
         .send()
         .await
-        .map_err(|err| AppError::upstream(format!("failed to reach Mopidy: {err}")))?;
+        .map_err(|err| {
+            let reason = if err.is_timeout() {
+                "timed out"
+            } else {
+                "failed"
+            };
+            RpcFailure::transient(format!("request to Mopidy {reason}: {err}"))
+        })?;
 
     let status = response.status();
     let bytes = response
         .bytes()
         .await
-        .map_err(|err| AppError::upstream(format!("failed to read Mopidy response: {err}")))?;
+        .map_err(|err| RpcFailure::transient(format!("failed to read Mopidy response: {err}")))?;
 
     if !status.is_success() {
         let body = String::from_utf8_lossy(&bytes);
-        return Err(AppError::upstream(format!(
-            "Mopidy returned {status}: {body}"
-        )));
+        let message = format!("Mopidy returned {status}: {body}");
+        return Err(if status.is_server_error() {
+            RpcFailure::transient(message)
+        } else {
+            RpcFailure::permanent(message)
+        });
     }
 
+    // Reine Notifications beantwortet Mopidy ohne Body.
+    if bytes.iter().all(u8::is_ascii_whitespace) {
+        return Ok(Value::Null);
+    }
     serde_json::from_slice::<Value>(&bytes)
-        .map_err(|err| AppError::upstream(format!("invalid Mopidy JSON response: {err}")))
+        .map_err(|err| RpcFailure::permanent(format!("invalid Mopidy JSON response: {err}")))
+}
+
+/// Methode und Fehlschlag je Aufruf für die Metriken; Batches zählen je Eintrag.
+/// Ohne Antwort (Transportfehler) gilt jeder Eintrag als fehlgeschlagen, im
+/// Batch auch ein Eintrag ohne Antwort mit seiner ID.
+fn observations<'a>(payload: &'a Value, response: Option<&Value>) -> Vec<(&'a str, bool)> {
+    let method = |entry: &'a Value| {
+        entry
+            .get("method")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown")
+    };
+    let failed = |response: Option<&Value>| {
+        response.is_none_or(|response| response.get("error").is_some_and(|error| !error.is_null()))
+    };
+    match (payload, response) {
+        (Value::Array(entries), Some(Value::Array(responses))) => entries
+            .iter()
+            .map(|entry| {
+                let response = responses
+                    .iter()
+                    .find(|response| response.get("id") == entry.get("id"));
+                (method(entry), failed(response))
+            })
+            .collect(),
+        // Eine Einzelantwort auf einen Batch ist ein Fehler für alle Einträge.
+        (Value::Array(entries), _) => entries.iter().map(|entry| (method(entry), true)).collect(),
+        (entry, response) => vec![(method(entry), failed(response))],
+    }
+}
+
+/// Lesende Methoden, die gefahrlos wiederholt werden können; Batches nur,
+/// wenn alle Einträge lesend sind.
+fn is_idempotent(payload: &Value) -> bool {
+    if let Value::Array(entries) = payload {
+        return !entries.is_empty() && entries.iter().all(is_idempotent);
+    }
+    let Some(method) = payload.get("method").and_then(Value::as_str) else {
+        return false;
+    };
+    let name = method.rsplit_once('.').map_or(method, |(_, name)| name);
+    name.starts_with("get_")
+        || matches!(
+            method,
+            "core.describe"
+                | "core.library.browse"
+                | "core.library.lookup"
+                | "core.library.search"
+                | "core.playlists.as_list"
+                | "core.playlists.lookup"
+        )
+}
+
+/// Exponentiell ab `base`, davon zufällig 50–100 % (Jitter gegen Gleichtakt).
+fn backoff(base: Duration, attempt: u32) -> Duration {
+    let ceiling = base.saturating_mul(1 << attempt.saturating_sub(1).min(16));
+    // `RandomState` ist je Instanz zufällig geseedet; genügt als Jitter-Quelle.
+    let random = RandomState::new().build_hasher().finish();
+    let fraction = 0.5 + (random % 1000) as f64 / 2000.0;
+    ceiling.mul_f64(fraction)
 }
 
 #[cfg(test)]
@@ -160,6 +686,14 @@ mod tests {
     #[async_trait]
     impl MopidyClient for StubClient {
         async fn proxy(&self, payload: Value) -> Result<Value, AppError> {
+            if let Value::Array(entries) = payload {
+                let mut responses = Vec::new();
+                // Umgekehrt beantworten: Zuordnung muss über die ID laufen.
+                for entry in entries.into_iter().rev() {
+                    responses.push(self.proxy(entry).await?);
+                }
+                return Ok(Value::Array(responses));
+            }
             let method = payload
                 .get("method")
                 .and_then(Value::as_str)
@@ -167,12 +701,15 @@ mod tests {
                 .to_string();
             self.calls.lock().unwrap().push(method.clone());
 
-            self.responses
+            let mut response = self
+                .responses
                 .lock()
                 .unwrap()
                 .get(&method)
                 .cloned()
-                .ok_or_else(|| AppError::internal(format!("unexpected method {method}")))
+                .ok_or_else(|| AppError::internal(format!("unexpected method {method}")))?;
+            response["id"] = payload["id"].clone();
+            Ok(response)
         }
     }
 
@@ -241,7 +778,7 @@ mod tests {
             .expect("result")
             .expect("track");
 
-        assert_eq!(track.get("uri").unwrap(), "track:1");
+        assert_eq!(track.uri, "track:1");
     }
 
     #[tokio::test]
@@ -263,6 +800,64 @@ mod tests {
         assert_eq!(results.len(), 1);
     }
 
+    #[tokio::test]
+    async fn playback_state_parses_result() {
+        let client = StubClient::new();
+        client.set_response(
+            "core.playback.get_state",
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": "paused",
+            }),
+        );
+
+        let state = client.playback_state().await.expect("state");
+
+        assert_eq!(state, PlaybackState::Paused);
+    }
+
+    #[tokio::test]
+    async fn current_track_maps_null_to_none() {
+        let client = StubClient::new();
+        client.set_response(
+            "core.playback.get_current_track",
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": null,
+            }),
+        );
+
+        let track = client.current_track().await.expect("result");
+
+        assert!(track.is_none());
+    }
+
+    #[tokio::test]
+    async fn tracklist_option_uses_getter_method() {
+        let client = StubClient::new();
+        client.set_response(
+            "core.tracklist.get_random",
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": true,
+            }),
+        );
+
+        let random = client
+            .tracklist_option(TracklistOption::Random)
+            .await
+            .expect("result");
+
+        assert!(random);
+        assert_eq!(
+            client.calls(),
+            vec!["core.tracklist.get_random".to_string()]
+        );
+    }
+
     #[tokio::test]
     async fn health_check_surfaces_message() {
         let client = StubClient::new();
@@ -278,4 +873,134 @@ mod tests {
         let err = client.health_check().await.expect_err("should error");
         assert_eq!(err, "offline");
     }
+
+    #[test]
+    fn only_reading_methods_are_retried() {
+        let call = |method: &str| json!({ "jsonrpc": "2.0", "id": 1, "method": method });
+        assert!(is_idempotent(&call("core.playback.get_state")));
+        assert!(is_idempotent(&call("core.library.lookup")));
+        assert!(!is_idempotent(&call("core.tracklist.add")));
+        assert!(!is_idempotent(&call("core.playback.next")));
+        assert!(is_idempotent(&json!([
+            call("core.get_version"),
+            call("core.playlists.as_list")
+        ])));
+        assert!(!is_idempotent(&json!([
+            call("core.get_version"),
+            call("core.playlists.save")
+        ])));
+    }
+
+    #[test]
+    fn batches_are_observed_per_entry() {
+        let call = |id: u64, method: &str| json!({ "jsonrpc": "2.0", "id": id, "method": method });
+        let batch = json!([
+            call(1, "core.library.search"),
+            call(2, "core.library.lookup"),
+            call(3, "core.get_version")
+        ]);
+        let responses = json!([
+            {"jsonrpc": "2.0", "id": 2, "error": {"code": -32603, "message": "boom"}},
+            {"jsonrpc": "2.0", "id": 1, "result": []}
+        ]);
+        assert_eq!(
+            observations(&batch, Some(&responses)),
+            vec![
+                ("core.library.search", false),
+                ("core.library.lookup", true),
+                ("core.get_version", true),
+            ]
+        );
+        assert!(observations(&batch, None).iter().all(|(_, failed)| *failed));
+
+        let single = call(1, "core.playback.get_state");
+        let ok = json!({"jsonrpc": "2.0", "id": 1, "result": "playing", "error": null});
+        assert_eq!(
+            observations(&single, Some(&ok)),
+            vec![("core.playback.get_state", false)]
+        );
+    }
+
+    #[test]
+    fn backoff_grows_with_jitter() {
+        let base = Duration::from_millis(100);
+        for attempt in 1..=3 {
+            let ceiling = base * (1 << (attempt - 1));
+            let delay = backoff(base, attempt);
+            assert!(delay >= ceiling / 2 && delay <= ceiling, "{delay:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn requests_use_unique_ids() {
+        let client = HttpMopidyClient::new(
+            reqwest::Client::new(),
+            Url::parse("http://127.0.0.1:1/mopidy/rpc").unwrap(),
+        );
+        let first = client.next_id();
+        assert_eq!(client.next_id(), first + 1);
+
+        let stub = StubClient::new();
+        assert_ne!(stub.next_id(), stub.next_id());
+    }
+
+    #[tokio::test]
+    async fn call_batch_returns_results_in_request_order() {
+        let client = StubClient::new();
+        client.set_response(
+            "core.playback.get_state",
+            json!({"jsonrpc": "2.0", "id": 1, "result": "playing"}),
+        );
+        client.set_response(
+            "core.playback.get_time_position",
+            json!({"jsonrpc": "2.0", "id": 1, "result": 1234}),
+        );
+
+        let results = client
+            .call_batch(&[
+                ("core.playback.get_state", None),
+                ("core.playback.get_time_position", None),
+            ])
+            .await
+            .expect("results");
+
+        assert_eq!(results, vec![json!("playing"), json!(1234)]);
+        assert!(client.call_batch(&[]).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn mismatched_response_ids_are_rejected() {
+        struct Fixed(Value);
+
+        #[async_trait]
+        impl MopidyClient for Fixed {
+            async fn proxy(&self, _payload: Value) -> Result<Value, AppError> {
+                Ok(self.0.clone())
+            }
+        }
+
+        let stale = Fixed(json!({"jsonrpc": "2.0", "id": 0, "result": "paused"}));
+        let err = stale
+            .call_method("core.playback.get_state", None)
+            .await
+            .expect_err("id mismatch");
+        assert!(err.to_string().contains("does not match request id"));
+
+        let parse_error = Fixed(json!({
+            "jsonrpc": "2.0",
+            "id": null,
+            "error": {"code": -32700, "message": "Parse error"}
+        }));
+        let err = parse_error.call_method("core.get_version", None).await;
+        assert_eq!(err.unwrap_err().to_string(), "Parse error");
+
+        let batch = Fixed(json!([
+            {"jsonrpc": "2.0", "id": 0, "result": null}
+        ]));
+        let err = batch
+            .call_batch(&[("core.get_version", None)])
+            .await
+            .expect_err("unknown id");
+        assert!(err.to_string().contains("missing"));
+    }
 }
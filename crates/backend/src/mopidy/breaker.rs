@@ -0,0 +1,191 @@
+//! Circuit Breaker für den Mopidy-Client.
+//!
+//! Nach `threshold` Transportfehlern in Folge öffnet der Breaker: Aufrufe
+//! scheitern sofort, statt auf Timeouts zu warten. Nach `cooldown` darf genau
+//! ein Probe-Aufruf durch (half-open); gelingt er, schließt der Breaker,
+//! sonst öffnet er erneut.
+
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use tracing::{info, warn};
+
+use crate::metrics::METRICS;
+use crate::models::CircuitState;
+
+#[derive(Debug)]
+enum State {
+    Closed {
+        failures: u32,
+    },
+    Open {
+        until: Instant,
+    },
+    /// Ein Probe-Aufruf läuft; weitere werden abgelehnt.
+    HalfOpen,
+}
+
+#[derive(Debug)]
+pub(crate) struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    state: Mutex<State>,
+}
+
+/// Erlaubnis für einen Aufruf. Ein abgebrochener Probe-Aufruf (ohne
+/// `success`/`failure`) gibt die Probe sofort wieder frei.
+#[derive(Debug)]
+pub(crate) struct Permit<'a> {
+    breaker: &'a CircuitBreaker,
+    done: bool,
+}
+
+impl Permit<'_> {
+    pub(crate) fn success(mut self) {
+        self.done = true;
+        self.breaker.record(true);
+    }
+
+    pub(crate) fn failure(mut self) {
+        self.done = true;
+        self.breaker.record(false);
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        if self.done {
+            return;
+        }
+        let mut state = self.breaker.lock();
+        if matches!(*state, State::HalfOpen) {
+            *state = State::Open {
+                until: Instant::now(),
+            };
+        }
+    }
+}
+
+impl CircuitBreaker {
+    /// `threshold == 0` schaltet den Breaker ab.
+    pub(crate) fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            threshold,
+            cooldown,
+            state: Mutex::new(State::Closed { failures: 0 }),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    /// Zustand für `/health`; bei offenem Breaker mit Restzeit bis zum nächsten Versuch.
+    pub(crate) fn state(&self) -> (CircuitState, Option<Duration>) {
+        match *self.lock() {
+            State::Closed { .. } => (CircuitState::Closed, None),
+            State::HalfOpen => (CircuitState::HalfOpen, None),
+            State::Open { until } => {
+                let remaining = until.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    (CircuitState::HalfOpen, None)
+                } else {
+                    (CircuitState::Open, Some(remaining))
+                }
+            }
+        }
+    }
+
+    /// `Err` mit Restzeit, solange der Breaker Aufrufe ablehnt.
+    pub(crate) fn acquire(&self) -> Result<Permit<'_>, Duration> {
+        let mut state = self.lock();
+        match *state {
+            State::Closed { .. } => {}
+            State::HalfOpen => return Err(Duration::ZERO),
+            State::Open { until } => {
+                let remaining = until.saturating_duration_since(Instant::now());
+                if !remaining.is_zero() {
+                    return Err(remaining);
+                }
+                info!("Mopidy circuit half-open; probing");
+                *state = State::HalfOpen;
+                METRICS.set_mopidy_circuit(CircuitState::HalfOpen);
+            }
+        }
+        Ok(Permit {
+            breaker: self,
+            done: false,
+        })
+    }
+
+    fn record(&self, success: bool) {
+        if self.threshold == 0 {
+            return;
+        }
+        let mut state = self.lock();
+        let next = match (&*state, success) {
+            (State::Closed { failures: 0 }, true) => return,
+            (_, true) => {
+                if !matches!(*state, State::Closed { .. }) {
+                    info!("Mopidy reachable again; circuit closed");
+                }
+                State::Closed { failures: 0 }
+            }
+            (State::Closed { failures }, false) if failures + 1 < self.threshold => State::Closed {
+                failures: failures + 1,
+            },
+            // Ein später Fehlschlag ändert einen bereits offenen Breaker nicht.
+            (State::Open { .. }, false) => return,
+            (_, false) => {
+                warn!("Mopidy unavailable; circuit open for {:?}", self.cooldown);
+                State::Open {
+                    until: Instant::now() + self.cooldown,
+                }
+            }
+        };
+        METRICS.set_mopidy_circuit(match next {
+            State::Closed { .. } => CircuitState::Closed,
+            State::Open { .. } => CircuitState::Open,
+            State::HalfOpen => CircuitState::HalfOpen,
+        });
+        *state = next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opens_after_threshold_and_probes_once() {
+        let breaker = CircuitBreaker::new(2, Duration::from_millis(20));
+        breaker.acquire().unwrap().failure();
+        assert_eq!(breaker.state().0, CircuitState::Closed);
+        breaker.acquire().unwrap().failure();
+        assert_eq!(breaker.state().0, CircuitState::Open);
+        assert!(breaker.acquire().is_err());
+
+        std::thread::sleep(Duration::from_millis(30));
+        let probe = breaker.acquire().unwrap();
+        assert_eq!(breaker.acquire().unwrap_err(), Duration::ZERO);
+        // Abgebrochene Probe: der nächste Aufruf darf sofort erneut proben.
+        drop(probe);
+        breaker.acquire().unwrap().failure();
+        assert_eq!(breaker.state().0, CircuitState::Open);
+
+        std::thread::sleep(Duration::from_millis(30));
+        breaker.acquire().unwrap().success();
+        assert_eq!(breaker.state(), (CircuitState::Closed, None));
+    }
+
+    #[test]
+    fn disabled_breaker_never_opens() {
+        let breaker = CircuitBreaker::new(0, Duration::from_secs(60));
+        for _ in 0..10 {
+            breaker.acquire().unwrap().failure();
+        }
+        assert!(breaker.acquire().is_ok());
+    }
+}
@@ -0,0 +1,524 @@
+//! Typisierte Mopidy-Modelle (`Track`, `Album`, `Artist`, `Ref`, …).
+//!
+//! Mopidy serialisiert Modelle als JSON mit `__model__`-Feld. Beim Lesen ist
+//! das Feld optional, muss aber zum Typ passen; beim Schreiben wird es immer
+//! gesetzt, damit Mopidy die Parameter (z. B. für `core.playlists.save`)
+//! wieder als Modell erkennt. Die Dekodierung ist nachsichtig: fehlende Felder
+//! bekommen Defaults, Zahlen dürfen als String kommen, unbekannte Felder
+//! landen in `extra` und werden beim Zurückschreiben erhalten.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{self, DeserializeOwned, Deserializer};
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::Value;
+use tracing::warn;
+
+/// Name im `__model__`-Feld.
+pub trait ModelName {
+    const NAME: &'static str;
+}
+
+/// Das `__model__`-Feld eines Modells; wird immer mit dem Typnamen geschrieben.
+pub struct ModelTag<T>(PhantomData<T>);
+
+impl<T> Default for ModelTag<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T> Clone for ModelTag<T> {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl<T> PartialEq for ModelTag<T> {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl<T: ModelName> fmt::Debug for ModelTag<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(T::NAME)
+    }
+}
+
+impl<T: ModelName> Serialize for ModelTag<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(T::NAME)
+    }
+}
+
+impl<'de, T: ModelName> Deserialize<'de> for ModelTag<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        if name == T::NAME {
+            Ok(Self::default())
+        } else {
+            Err(de::Error::custom(format!(
+                "expected {} model, got {name}",
+                T::NAME
+            )))
+        }
+    }
+}
+
+/// Unbekannte Felder eines Modells.
+pub type Extra = BTreeMap<String, Value>;
+
+macro_rules! model_name {
+    ($($model:ident),*) => {
+        $(impl ModelName for $model {
+            const NAME: &'static str = stringify!($model);
+        })*
+    };
+}
+
+model_name!(
+    Artist,
+    Album,
+    Track,
+    Ref,
+    TlTrack,
+    SearchResult,
+    Playlist,
+    Image
+);
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Artist {
+    #[serde(rename = "__model__", default)]
+    pub model: ModelTag<Artist>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uri: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sortname: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub musicbrainz_id: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Album {
+    #[serde(rename = "__model__", default)]
+    pub model: ModelTag<Album>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uri: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "lenient_list",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub artists: Vec<Artist>,
+    #[serde(
+        default,
+        deserialize_with = "lenient_number",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub num_tracks: Option<u32>,
+    #[serde(
+        default,
+        deserialize_with = "lenient_number",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub num_discs: Option<u32>,
+    #[serde(
+        default,
+        deserialize_with = "lenient_string",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub date: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub musicbrainz_id: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Track {
+    #[serde(rename = "__model__", default)]
+    pub model: ModelTag<Track>,
+    pub uri: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "lenient_list",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub artists: Vec<Artist>,
+    #[serde(
+        default,
+        deserialize_with = "lenient_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub album: Option<Album>,
+    #[serde(
+        default,
+        deserialize_with = "lenient_list",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub composers: Vec<Artist>,
+    #[serde(
+        default,
+        deserialize_with = "lenient_list",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub performers: Vec<Artist>,
+    #[serde(
+        default,
+        deserialize_with = "lenient_string",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub genre: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "lenient_number",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub track_no: Option<u32>,
+    #[serde(
+        default,
+        deserialize_with = "lenient_number",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub disc_no: Option<u32>,
+    #[serde(
+        default,
+        deserialize_with = "lenient_string",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub date: Option<String>,
+    /// Dauer in Millisekunden.
+    #[serde(
+        default,
+        deserialize_with = "lenient_number",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub length: Option<u64>,
+    /// kbit/s.
+    #[serde(
+        default,
+        deserialize_with = "lenient_number",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub bitrate: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub musicbrainz_id: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "lenient_number",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub last_modified: Option<u64>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+impl Track {
+    /// Minimaler Track, z. B. für Playlist-Einträge.
+    #[must_use]
+    pub fn from_uri(uri: impl Into<String>) -> Self {
+        Self {
+            uri: uri.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Namen der Track-Künstler (ohne leere).
+    pub fn artist_names(&self) -> impl Iterator<Item = &str> {
+        names(&self.artists)
+    }
+}
+
+/// Art eines Browse-/Playlist-Eintrags.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum RefType {
+    Album,
+    Artist,
+    Directory,
+    Playlist,
+    #[default]
+    Track,
+    /// Von neueren Mopidy-Versionen eingeführte Typen; der Wert bleibt erhalten.
+    Other(String),
+}
+
+impl RefType {
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        match self {
+            RefType::Album => "album",
+            RefType::Artist => "artist",
+            RefType::Directory => "directory",
+            RefType::Playlist => "playlist",
+            RefType::Track => "track",
+            RefType::Other(raw) => raw,
+        }
+    }
+}
+
+impl From<String> for RefType {
+    fn from(raw: String) -> Self {
+        match raw.as_str() {
+            "album" => RefType::Album,
+            "artist" => RefType::Artist,
+            "directory" => RefType::Directory,
+            "playlist" => RefType::Playlist,
+            "track" => RefType::Track,
+            _ => RefType::Other(raw),
+        }
+    }
+}
+
+impl Serialize for RefType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for RefType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(RefType::from)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Ref {
+    #[serde(rename = "__model__", default)]
+    pub model: ModelTag<Ref>,
+    pub uri: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(rename = "type", default)]
+    pub kind: RefType,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TlTrack {
+    #[serde(rename = "__model__", default)]
+    pub model: ModelTag<TlTrack>,
+    pub tlid: u64,
+    pub track: Track,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// Treffer eines Backends für `core.library.search`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SearchResult {
+    #[serde(rename = "__model__", default)]
+    pub model: ModelTag<SearchResult>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uri: Option<String>,
+    #[serde(default, deserialize_with = "lenient_list")]
+    pub tracks: Vec<Track>,
+    #[serde(default, deserialize_with = "lenient_list")]
+    pub albums: Vec<Album>,
+    #[serde(default, deserialize_with = "lenient_list")]
+    pub artists: Vec<Artist>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Playlist {
+    #[serde(rename = "__model__", default)]
+    pub model: ModelTag<Playlist>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uri: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, deserialize_with = "lenient_list")]
+    pub tracks: Vec<Track>,
+    #[serde(
+        default,
+        deserialize_with = "lenient_number",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub last_modified: Option<u64>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Image {
+    #[serde(rename = "__model__", default)]
+    pub model: ModelTag<Image>,
+    pub uri: String,
+    #[serde(
+        default,
+        deserialize_with = "lenient_number",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub width: Option<u32>,
+    #[serde(
+        default,
+        deserialize_with = "lenient_number",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub height: Option<u32>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// Nicht-leere Namen einer Künstlerliste.
+pub fn names(artists: &[Artist]) -> impl Iterator<Item = &str> {
+    artists
+        .iter()
+        .filter_map(|artist| artist.name.as_deref())
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+}
+
+/// Dekodiert ein Modell; `None` (mit Warnung), wenn es nicht passt.
+pub fn decode<T: DeserializeOwned + ModelName>(value: Value) -> Option<T> {
+    if value.is_null() {
+        return None;
+    }
+    match serde_json::from_value::<T>(value) {
+        Ok(model) => Some(model),
+        Err(err) => {
+            warn!("skipping malformed Mopidy {}: {err}", T::NAME);
+            None
+        }
+    }
+}
+
+/// Dekodiert eine Liste; fehlerhafte Einträge werden übersprungen statt die
+/// ganze Antwort zu verwerfen.
+pub fn decode_list<T: DeserializeOwned + ModelName>(value: Value) -> Vec<T> {
+    match value {
+        Value::Array(entries) => entries.into_iter().filter_map(decode).collect(),
+        Value::Null => Vec::new(),
+        other => {
+            warn!("expected a list of Mopidy {} models, got {other}", T::NAME);
+            Vec::new()
+        }
+    }
+}
+
+/// Dekodiert Antworten der Form `{uri: [model, …]}` (`lookup`, `get_images`).
+pub fn decode_map<T: DeserializeOwned + ModelName>(value: Value) -> BTreeMap<String, Vec<T>> {
+    match value {
+        Value::Object(entries) => entries
+            .into_iter()
+            .map(|(uri, models)| (uri, decode_list(models)))
+            .collect(),
+        Value::Null => BTreeMap::new(),
+        other => {
+            warn!("expected a map of Mopidy {} models, got {other}", T::NAME);
+            BTreeMap::new()
+        }
+    }
+}
+
+fn lenient_list<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned + ModelName,
+{
+    Ok(decode_list(Value::deserialize(deserializer)?))
+}
+
+fn lenient_option<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned + ModelName,
+{
+    Ok(decode(Value::deserialize(deserializer)?))
+}
+
+/// Zahl oder numerischer String; alles andere wird zu `None`.
+fn lenient_number<'de, D, N>(deserializer: D) -> Result<Option<N>, D::Error>
+where
+    D: Deserializer<'de>,
+    N: TryFrom<u64>,
+{
+    let number = match Value::deserialize(deserializer)? {
+        Value::Number(number) => number
+            .as_u64()
+            .or_else(|| number.as_f64().filter(|n| *n >= 0.0).map(|n| n as u64)),
+        Value::String(raw) => raw.trim().parse::<u64>().ok(),
+        _ => None,
+    };
+    Ok(number.and_then(|number| N::try_from(number).ok()))
+}
+
+/// String oder Zahl (manche Backends liefern `date` als Jahreszahl).
+fn lenient_string<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match Value::deserialize(deserializer)? {
+        Value::String(raw) if !raw.trim().is_empty() => Some(raw),
+        Value::Number(number) => Some(number.to_string()),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn track_decodes_leniently_and_keeps_unknown_fields() {
+        let track: Track = decode(json!({
+            "__model__": "Track",
+            "uri": "qobuz:track:1",
+            "name": "One",
+            "artists": [{"__model__": "Artist", "name": "A"}, {"__model__": "Album"}],
+            "album": {"__model__": "Album", "name": "Record", "date": 1999},
+            "track_no": "3",
+            "length": 215000,
+            "bitrate": null,
+            "date": "1999-05-01",
+            "x_qobuz_hires": true
+        }))
+        .expect("track");
+
+        assert_eq!(track.artist_names().collect::<Vec<_>>(), ["A"]);
+        assert_eq!(track.album.as_ref().unwrap().date.as_deref(), Some("1999"));
+        assert_eq!(track.track_no, Some(3));
+        assert_eq!(track.length, Some(215_000));
+        assert_eq!(track.bitrate, None);
+        assert_eq!(track.extra["x_qobuz_hires"], json!(true));
+
+        let written = serde_json::to_value(&track).unwrap();
+        assert_eq!(written["__model__"], "Track");
+        assert_eq!(written["album"]["__model__"], "Album");
+        assert_eq!(written["x_qobuz_hires"], json!(true));
+        assert!(written.get("composers").is_none());
+    }
+
+    #[test]
+    fn model_tag_must_match_when_present() {
+        assert!(decode::<Track>(json!({"__model__": "Album", "uri": "x"})).is_none());
+        assert!(decode::<Track>(json!({"uri": "x"})).is_some());
+
+        let refs: Vec<Ref> = decode_list(json!([
+            {"__model__": "Ref", "uri": "m3u:a.m3u8", "name": "A", "type": "playlist"},
+            {"name": "missing uri"},
+            {"uri": "x:y", "type": "podcast"}
+        ]));
+        assert_eq!(refs.len(), 2);
+        assert_eq!(refs[0].kind, RefType::Playlist);
+        assert_eq!(refs[1].kind, RefType::Other("podcast".into()));
+        assert_eq!(serde_json::to_value(&refs[1]).unwrap()["type"], "podcast");
+    }
+}
@@ -0,0 +1,214 @@
+use serde_json::Value;
+use tracing::instrument;
+
+use crate::error::AppError;
+use crate::models::{
+    PlaylistAppendRequest, PlaylistDeleteResponse, PlaylistRequest, PlaylistResponse,
+};
+use crate::mopidy::types::{decode_list, Playlist, Ref, Track};
+use crate::mopidy::MopidyClient;
+use crate::validation;
+
+/// Legt eine Playlist an bzw. ersetzt die gleichnamige (`replace`), analog zu
+/// `scripts/playlist-from-list`, aber über den konfigurierten Mopidy-Client.
+#[instrument(skip(mopidy, request), fields(name = %request.name))]
+pub async fn from_list(
+    mopidy: &dyn MopidyClient,
+    request: &PlaylistRequest,
+) -> Result<PlaylistResponse, AppError> {
+    let name = request.name.trim();
+    if name.is_empty() {
+        return Err(AppError::bad_request("playlist name must not be empty"));
+    }
+    let (accepted, rejected_uris) = partition_uris(&request.uris)?;
+
+    let existing = find_by_name(mopidy, name, request.scheme.as_deref()).await?;
+    let (mut playlist, replaced) = match existing {
+        Some(_) if !request.replace => {
+            return Err(AppError::conflict(format!(
+                "playlist '{name}' already exists; set replace to overwrite it"
+            )));
+        }
+        Some(uri) => {
+            let playlist = mopidy
+                .playlist_lookup(&uri)
+                .await?
+                .ok_or_else(|| AppError::upstream(format!("playlist lookup failed for {uri}")))?;
+            (playlist, true)
+        }
+        None => (
+            mopidy
+                .playlist_create(name, request.scheme.as_deref())
+                .await?,
+            false,
+        ),
+    };
+
+    playlist.tracks = accepted.iter().map(Track::from_uri).collect();
+    save(
+        mopidy,
+        playlist,
+        name,
+        0,
+        accepted.len(),
+        rejected_uris,
+        replaced,
+    )
+    .await
+}
+
+#[instrument(skip(mopidy, request), fields(uri = %request.uri))]
+pub async fn append(
+    mopidy: &dyn MopidyClient,
+    request: &PlaylistAppendRequest,
+) -> Result<PlaylistResponse, AppError> {
+    if request.uri.trim().is_empty() {
+        return Err(AppError::bad_request("playlist uri must not be empty"));
+    }
+    let (accepted, rejected_uris) = partition_uris(&request.uris)?;
+
+    let mut playlist = mopidy
+        .playlist_lookup(&request.uri)
+        .await?
+        .ok_or_else(|| AppError::bad_request(format!("playlist not found: {}", request.uri)))?;
+    let name = playlist.name.clone().unwrap_or_default();
+
+    let before = playlist.tracks.len();
+    playlist.tracks.extend(accepted.iter().map(Track::from_uri));
+
+    save(
+        mopidy,
+        playlist,
+        &name,
+        before,
+        accepted.len(),
+        rejected_uris,
+        false,
+    )
+    .await
+}
+
+#[instrument(skip(mopidy))]
+pub async fn delete(
+    mopidy: &dyn MopidyClient,
+    uri: &str,
+) -> Result<PlaylistDeleteResponse, AppError> {
+    if uri.trim().is_empty() {
+        return Err(AppError::bad_request("playlist uri must not be empty"));
+    }
+    let deleted = mopidy.playlist_delete(uri).await?;
+
+    Ok(PlaylistDeleteResponse {
+        uri: uri.into(),
+        deleted,
+    })
+}
+
+/// Sucht die Playlist per Namen; mit `scheme` wird im selben Batch geprüft,
+/// ob ein Playlist-Backend dieses Scheme bedient (sonst nähme Mopidy still das erste).
+async fn find_by_name(
+    mopidy: &dyn MopidyClient,
+    name: &str,
+    scheme: Option<&str>,
+) -> Result<Option<String>, AppError> {
+    let mut calls = vec![("core.playlists.as_list", None)];
+    if scheme.is_some() {
+        calls.push(("core.playlists.get_uri_schemes", None));
+    }
+    let results = mopidy.call_batch(&calls).await?;
+
+    if let (Some(scheme), Some(schemes)) = (scheme, results.get(1)) {
+        let schemes: Vec<&str> = schemes
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .collect();
+        if !schemes.contains(&scheme) {
+            return Err(AppError::bad_request(format!(
+                "unknown playlist scheme '{scheme}' (available: {})",
+                schemes.join(", ")
+            )));
+        }
+    }
+
+    let mut results = results.into_iter();
+    let playlists: Vec<Ref> = decode_list(results.next().unwrap_or_default());
+    Ok(playlists
+        .into_iter()
+        .find(|playlist| playlist.name.as_deref() == Some(name))
+        .map(|playlist| playlist.uri))
+}
+
+async fn save(
+    mopidy: &dyn MopidyClient,
+    playlist: Playlist,
+    name: &str,
+    before: usize,
+    sent: usize,
+    rejected_uris: Vec<String>,
+    replaced: bool,
+) -> Result<PlaylistResponse, AppError> {
+    let saved = mopidy
+        .playlist_save(&playlist)
+        .await?
+        .ok_or_else(|| AppError::upstream(format!("Mopidy refused to save playlist '{name}'")))?;
+
+    let tracks = saved.tracks.len();
+    let added = tracks.saturating_sub(before).min(sent);
+    // Von Mopidy verworfene Tracks zählen wie ungültige URIs als abgelehnt.
+    let rejected = rejected_uris.len() + (sent - added);
+
+    Ok(PlaylistResponse {
+        uri: saved.uri,
+        name: saved.name.unwrap_or_else(|| name.into()),
+        tracks,
+        added,
+        rejected,
+        rejected_uris,
+        replaced,
+    })
+}
+
+/// Trennt erlaubte von abgelehnten URIs; leere Zeilen und `#`-Kommentare werden
+/// wie in `playlist-from-list` ignoriert.
+fn partition_uris(uris: &[String]) -> Result<(Vec<String>, Vec<String>), AppError> {
+    let (accepted, rejected): (Vec<String>, Vec<String>) = uris
+        .iter()
+        .map(|uri| uri.trim())
+        .filter(|uri| !uri.is_empty() && !uri.starts_with('#'))
+        .map(String::from)
+        .partition(|uri| validation::is_allowed_uri(uri));
+
+    if accepted.is_empty() {
+        return Err(AppError::bad_request("no allowed track URIs in request"));
+    }
+    Ok((accepted, rejected))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partition_uris_skips_comments_and_rejects_schemes() {
+        let uris = vec![
+            "# Kommentar".to_string(),
+            " qobuz:track:1 ".to_string(),
+            String::new(),
+            "file:///etc/passwd".to_string(),
+        ];
+
+        let (accepted, rejected) = partition_uris(&uris).expect("partition");
+
+        assert_eq!(accepted, vec!["qobuz:track:1"]);
+        assert_eq!(rejected, vec!["file:///etc/passwd"]);
+    }
+
+    #[test]
+    fn partition_uris_requires_one_allowed_uri() {
+        let err = partition_uris(&["file:///tmp/x".to_string()]).expect_err("should fail");
+
+        assert!(matches!(err, AppError::BadRequest(_)));
+    }
+}
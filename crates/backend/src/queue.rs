@@ -0,0 +1,145 @@
+use tracing::instrument;
+
+use crate::discover::{build_track, track_info};
+use crate::error::AppError;
+use crate::models::{
+    QueueAddRequest, QueueEntry, QueueOptions, QueueOptionsUpdate, QueueResponse, TracklistOption,
+};
+use crate::mopidy::types::TlTrack;
+use crate::mopidy::MopidyClient;
+use crate::validation;
+
+#[instrument(skip(mopidy))]
+pub async fn snapshot(mopidy: &dyn MopidyClient) -> Result<QueueResponse, AppError> {
+    let tl_tracks = mopidy.tl_tracks().await?;
+    let options = options(mopidy).await?;
+
+    Ok(QueueResponse {
+        tracks: tl_tracks.iter().map(build_entry).collect(),
+        options,
+    })
+}
+
+pub async fn options(mopidy: &dyn MopidyClient) -> Result<QueueOptions, AppError> {
+    let mut options = QueueOptions::default();
+    for option in TracklistOption::ALL {
+        options.set(option, mopidy.tracklist_option(option).await?);
+    }
+    Ok(options)
+}
+
+/// Setzt nur die übergebenen Schalter und liefert danach den vollständigen Stand.
+#[instrument(skip(mopidy))]
+pub async fn update_options(
+    mopidy: &dyn MopidyClient,
+    update: &QueueOptionsUpdate,
+) -> Result<QueueOptions, AppError> {
+    for option in TracklistOption::ALL {
+        if let Some(value) = update.get(option) {
+            mopidy.set_tracklist_option(option, value).await?;
+        }
+    }
+    options(mopidy).await
+}
+
+/// Führt `uris` und `tracks[].uri` zusammen (Reihenfolge bleibt, Duplikate bleiben
+/// erlaubt) und lehnt den gesamten Request ab, sobald eine URI nicht erlaubt ist.
+pub fn collect_uris(request: &QueueAddRequest) -> Result<Vec<String>, AppError> {
+    let uris: Vec<String> = request
+        .uris
+        .iter()
+        .cloned()
+        .chain(request.tracks.iter().map(|track| track.uri.clone()))
+        .collect();
+
+    if uris.is_empty() {
+        return Err(AppError::bad_request("no URIs to enqueue"));
+    }
+    if let Some(rejected) = uris.iter().find(|uri| !validation::is_allowed_uri(uri)) {
+        return Err(AppError::bad_request(format!(
+            "disallowed URI scheme: {rejected}"
+        )));
+    }
+
+    Ok(uris)
+}
+
+/// Jeder Eintrag mit `tlid` bleibt sichtbar, damit er per `tlid` entfernt werden
+/// kann; ohne Namen (Streams, ungetaggte Dateien) steht die URI als Name.
+pub(crate) fn build_entry(tl_track: &TlTrack) -> QueueEntry {
+    let track = &tl_track.track;
+    QueueEntry {
+        tlid: tl_track.tlid,
+        track: build_track(track).unwrap_or_else(|| track_info(track, track.uri.clone())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TrackRef;
+    use crate::mopidy::types::decode;
+    use serde_json::json;
+
+    #[test]
+    fn collect_uris_merges_plain_and_track_refs() {
+        let request = QueueAddRequest {
+            uris: vec!["qobuz:track:1".into()],
+            tracks: vec![TrackRef {
+                uri: "local:track:2".into(),
+            }],
+            at_position: None,
+        };
+
+        let uris = collect_uris(&request).expect("uris");
+
+        assert_eq!(uris, vec!["qobuz:track:1", "local:track:2"]);
+    }
+
+    #[test]
+    fn collect_uris_rejects_disallowed_scheme() {
+        let request = QueueAddRequest {
+            uris: vec!["qobuz:track:1".into(), "file:///etc/passwd".into()],
+            tracks: Vec::new(),
+            at_position: None,
+        };
+
+        let err = collect_uris(&request).expect_err("should reject");
+
+        assert!(matches!(err, AppError::BadRequest(_)));
+    }
+
+    #[test]
+    fn build_entry_requires_tlid_and_track() {
+        let entry = decode(json!({
+            "__model__": "TlTrack",
+            "tlid": 3,
+            "track": {"uri": "qobuz:track:1", "name": "One", "length": "215000", "disc_no": 2}
+        }))
+        .as_ref()
+        .map(build_entry)
+        .expect("entry");
+        assert_eq!(entry.tlid, 3);
+        assert_eq!(entry.track.uri, "qobuz:track:1");
+        assert_eq!(entry.track.duration_ms, Some(215_000));
+        assert_eq!(entry.track.disc_no, Some(2));
+
+        assert!(decode::<TlTrack>(json!({"track": {"uri": "x", "name": "y"}})).is_none());
+    }
+
+    #[test]
+    fn build_entry_keeps_nameless_tracks() {
+        let tl_track: TlTrack = decode(json!({
+            "__model__": "TlTrack",
+            "tlid": 7,
+            "track": {"uri": "local:track:untagged.flac", "length": 1000}
+        }))
+        .expect("tl_track");
+
+        let entry = build_entry(&tl_track);
+
+        assert_eq!(entry.tlid, 7);
+        assert_eq!(entry.track.name, "local:track:untagged.flac");
+        assert_eq!(entry.track.duration_ms, Some(1000));
+    }
+}
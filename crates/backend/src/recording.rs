@@ -0,0 +1,736 @@
+//! Nativer Recorder-Supervisor: startet das Capture-Binary (`pw-record`) als
+//! eigenen Kindprozess und ersetzt die PID-Datei-Übergabe von `rec-start`/`rec-stop`.
+//!
+//! PID-Datei und `recording.json` werden weiterhin geschrieben, damit
+//! `rec-stop --status` und ein neu gestartetes Backend den Recorder wiederfinden.
+
+use std::fs::{self, OpenOptions};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Component, Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use nix::sys::signal::{self, Signal};
+use nix::unistd::Pid;
+use serde::{Deserialize, Serialize};
+use tokio::process::{Child, Command};
+use tokio::sync::Mutex;
+use tokio::time::{self, Instant};
+use tracing::{info, instrument, warn};
+
+use crate::config::RecorderConfig;
+use crate::error::AppError;
+use crate::models::{
+    RecordingStartRequest, RecordingStartResponse, RecordingStatus, RecordingStopRequest,
+    RecordingStopResponse, StopSignal,
+};
+
+const PID_FILE: &str = "recording.pid";
+const META_FILE: &str = "recording.json";
+const LOG_FILE: &str = "recording.log";
+
+const DEFAULT_RATE: u32 = 96_000;
+const DEFAULT_CHANNELS: u16 = 2;
+const DEFAULT_FORMAT: &str = "S24_LE";
+
+const DEFAULT_STOP_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_STOP_TIMEOUT: Duration = Duration::from_secs(30);
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Gleiches Format wie `recording.json` aus `scripts/rec-start`.
+#[derive(Debug, Serialize, Deserialize)]
+struct SessionMeta {
+    pid: u32,
+    output: String,
+    #[serde(default)]
+    command: Vec<String>,
+    #[serde(default)]
+    started_at: Option<String>,
+}
+
+struct Session {
+    meta: SessionMeta,
+    /// `None`, wenn die Aufnahme aus einer PID-Datei übernommen wurde oder
+    /// `stop` das Kind gerade zum Warten ausgeliehen hat.
+    child: Option<Child>,
+    /// Gesetzt, solange `stop` signalisiert und wartet; `refresh` lässt die
+    /// Session dann in Ruhe, das Aufräumen übernimmt `stop`.
+    stopping: bool,
+}
+
+impl Session {
+    fn is_alive(&mut self) -> bool {
+        match &mut self.child {
+            Some(child) => matches!(child.try_wait(), Ok(None)),
+            None => process_alive(self.meta.pid),
+        }
+    }
+}
+
+/// Wartet höchstens `timeout` auf das Prozessende; liefert `Some(exit_code)` bei Erfolg.
+async fn wait_exit(pid: u32, child: Option<&mut Child>, timeout: Duration) -> Option<Option<i32>> {
+    match child {
+        Some(child) => match time::timeout(timeout, child.wait()).await {
+            Ok(Ok(status)) => Some(status.code()),
+            Ok(Err(err)) => {
+                warn!("failed to wait for recorder {pid}: {err}");
+                None
+            }
+            Err(_) => None,
+        },
+        None => {
+            let deadline = Instant::now() + timeout;
+            loop {
+                if !process_alive(pid) {
+                    return Some(None);
+                }
+                if Instant::now() >= deadline {
+                    return None;
+                }
+                time::sleep(POLL_INTERVAL).await;
+            }
+        }
+    }
+}
+
+pub struct Recorder {
+    config: RecorderConfig,
+    session: Mutex<Option<Session>>,
+}
+
+impl Recorder {
+    /// Legt den Supervisor an und übernimmt einen noch laufenden Recorder aus der PID-Datei.
+    #[must_use]
+    pub fn new(config: RecorderConfig) -> Self {
+        let session = adopt_from_disk(&config.state_dir, &config.binary);
+        if let Some(session) = &session {
+            info!(
+                "adopted running recorder (pid {}) → {}",
+                session.meta.pid, session.meta.output
+            );
+        }
+        Self {
+            config,
+            session: Mutex::new(session),
+        }
+    }
+
+    #[instrument(skip(self))]
+    pub async fn start(
+        &self,
+        request: &RecordingStartRequest,
+    ) -> Result<RecordingStartResponse, AppError> {
+        let mut guard = self.session.lock().await;
+        self.refresh(&mut guard);
+        if let Some(session) = guard.as_ref() {
+            return Err(AppError::conflict(format!(
+                "recording already running (pid {})",
+                session.meta.pid
+            )));
+        }
+
+        let output = self.resolve_output(request.output.as_deref())?;
+        let args = build_args(request, &output)?;
+
+        fs::create_dir_all(&self.config.state_dir)?;
+        let log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.config.state_dir.join(LOG_FILE))?;
+
+        let mut command = Command::new(&self.config.binary);
+        command.args(&args);
+        command.stdin(Stdio::null());
+        command.stdout(Stdio::from(log.try_clone()?));
+        command.stderr(Stdio::from(log));
+        // Eigene Prozessgruppe: Ctrl-C/Signale ans Backend treffen die Aufnahme nicht.
+        command.process_group(0);
+
+        let child = command.spawn().map_err(|err| {
+            AppError::internal(format!(
+                "failed to launch {}: {err}",
+                self.config.binary.display()
+            ))
+        })?;
+        let pid = child
+            .id()
+            .ok_or_else(|| AppError::internal("recorder exited before reporting a pid"))?;
+
+        let mut command_line = vec![self.config.binary.display().to_string()];
+        command_line.extend(args);
+        let meta = SessionMeta {
+            pid,
+            output: output.display().to_string(),
+            command: command_line,
+            started_at: Some(chrono::Utc::now().to_rfc3339()),
+        };
+        self.write_state(&meta)?;
+        info!("recording started (pid {pid}) → {}", meta.output);
+
+        let response = RecordingStartResponse {
+            pid,
+            output: meta.output.clone(),
+            command: meta.command.clone(),
+            started_at: meta.started_at.clone(),
+        };
+        *guard = Some(Session {
+            meta,
+            child: Some(child),
+            stopping: false,
+        });
+        Ok(response)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn stop(
+        &self,
+        request: &RecordingStopRequest,
+    ) -> Result<RecordingStopResponse, AppError> {
+        let timeout = match request.timeout {
+            Some(secs) => {
+                if !secs.is_finite() || secs <= 0.0 || secs > MAX_STOP_TIMEOUT.as_secs_f64() {
+                    return Err(AppError::bad_request(format!(
+                        "timeout must be between 0 and {} seconds",
+                        MAX_STOP_TIMEOUT.as_secs()
+                    )));
+                }
+                Duration::from_secs_f64(secs)
+            }
+            None => DEFAULT_STOP_TIMEOUT,
+        };
+
+        // Lock nur zum Markieren halten: Signalisieren und Warten kann bis zu
+        // dreimal `timeout` dauern, `status()` soll währenddessen antworten.
+        let (pid, mut child) = {
+            let mut guard = self.session.lock().await;
+            if let Some(stale_pid) = self.refresh(&mut guard) {
+                return Ok(RecordingStopResponse {
+                    pid: stale_pid,
+                    signal: None,
+                    killed: false,
+                    stale: true,
+                    exit_code: None,
+                });
+            }
+            let Some(session) = guard.as_mut() else {
+                return Err(AppError::conflict("no recording running"));
+            };
+            if session.stopping {
+                return Err(AppError::conflict(format!(
+                    "recording is already stopping (pid {})",
+                    session.meta.pid
+                )));
+            }
+            session.stopping = true;
+            (session.meta.pid, session.child.take())
+        };
+
+        let outcome = signal_until_exit(
+            pid,
+            child.as_mut(),
+            request.signal.unwrap_or(StopSignal::Int),
+            timeout,
+        )
+        .await;
+
+        let mut guard = self.session.lock().await;
+        match outcome {
+            Ok((step, exit_code)) => {
+                info!("recorder {pid} stopped after SIG{}", step.as_str());
+                *guard = None;
+                self.clear_state();
+                Ok(RecordingStopResponse {
+                    pid,
+                    signal: Some(step),
+                    killed: step == StopSignal::Kill,
+                    stale: false,
+                    exit_code,
+                })
+            }
+            Err(err) => {
+                if let Some(session) = guard.as_mut() {
+                    session.stopping = false;
+                    session.child = child;
+                }
+                Err(err)
+            }
+        }
+    }
+
+    pub async fn status(&self) -> RecordingStatus {
+        let mut guard = self.session.lock().await;
+        self.refresh(&mut guard);
+        match guard.as_ref() {
+            Some(session) => RecordingStatus {
+                running: true,
+                stopping: session.stopping,
+                pid: Some(session.meta.pid),
+                output: Some(session.meta.output.clone()),
+                started_at: session.meta.started_at.clone(),
+            },
+            None => RecordingStatus {
+                running: false,
+                stopping: false,
+                pid: None,
+                output: None,
+                started_at: None,
+            },
+        }
+    }
+
+    /// Gleicht den Zustand ab: beendete Sessions werden aufgeräumt (PID wird
+    /// zurückgegeben), extern gestartete Recorder aus der PID-Datei übernommen.
+    fn refresh(&self, session: &mut Option<Session>) -> Option<u32> {
+        if let Some(current) = session.as_mut() {
+            if current.stopping || current.is_alive() {
+                return None;
+            }
+            let pid = current.meta.pid;
+            info!("recorder {pid} exited on its own");
+            *session = None;
+            self.clear_state();
+            return Some(pid);
+        }
+
+        *session = adopt_from_disk(&self.config.state_dir, &self.config.binary);
+        None
+    }
+
+    fn resolve_output(&self, requested: Option<&str>) -> Result<PathBuf, AppError> {
+        let path = match requested {
+            Some(raw) if raw.trim().is_empty() => {
+                return Err(AppError::bad_request("output must not be empty"));
+            }
+            Some(raw) => {
+                // Nur relative Pfade unterhalb von `record_dir`; `join` ersetzt die
+                // Basis bei absoluten Pfaden, `..` würde hinausführen.
+                let relative = Path::new(raw);
+                if !relative
+                    .components()
+                    .all(|component| matches!(component, Component::Normal(_)))
+                {
+                    return Err(AppError::bad_request(format!(
+                        "output must be a relative path inside the recording directory: {raw}"
+                    )));
+                }
+                self.config.record_dir.join(relative)
+            }
+            None => {
+                let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+                self.config
+                    .record_dir
+                    .join(format!("recording-{timestamp}.{}", self.config.extension))
+            }
+        };
+        if path.exists() {
+            return Err(AppError::conflict(format!(
+                "output file already exists: {}",
+                path.display()
+            )));
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Ok(path)
+    }
+
+    fn write_state(&self, meta: &SessionMeta) -> Result<(), AppError> {
+        let meta_json = serde_json::to_string(meta)
+            .map_err(|err| AppError::internal(format!("failed to encode recorder state: {err}")))?;
+        fs::write(
+            self.config.state_dir.join(PID_FILE),
+            format!("{}\n", meta.pid),
+        )?;
+        fs::write(self.config.state_dir.join(META_FILE), meta_json)?;
+        Ok(())
+    }
+
+    fn clear_state(&self) {
+        for name in [PID_FILE, META_FILE] {
+            let path = self.config.state_dir.join(name);
+            if let Err(err) = fs::remove_file(&path) {
+                if err.kind() != std::io::ErrorKind::NotFound {
+                    warn!("failed to remove {}: {err}", path.display());
+                }
+            }
+        }
+    }
+}
+
+fn build_args(request: &RecordingStartRequest, output: &Path) -> Result<Vec<String>, AppError> {
+    let rate = request.rate.unwrap_or(DEFAULT_RATE);
+    if rate == 0 {
+        return Err(AppError::bad_request("rate must be greater than zero"));
+    }
+    let channels = request.channels.unwrap_or(DEFAULT_CHANNELS);
+    if channels == 0 {
+        return Err(AppError::bad_request("channels must be greater than zero"));
+    }
+    let format = request.format.as_deref().unwrap_or(DEFAULT_FORMAT);
+    if format.trim().is_empty() {
+        return Err(AppError::bad_request("format must not be empty"));
+    }
+
+    let mut args = vec![
+        "--rate".into(),
+        rate.to_string(),
+        "--channels".into(),
+        channels.to_string(),
+        "--format".into(),
+        format.into(),
+    ];
+    if let Some(device) = &request.device {
+        if device.trim().is_empty() {
+            return Err(AppError::bad_request("device must not be empty"));
+        }
+        args.push("--target".into());
+        args.push(device.clone());
+    }
+    args.push(output.display().to_string());
+    Ok(args)
+}
+
+/// Signalisiert entlang der Eskalationsleiter, bis der Recorder endet.
+async fn signal_until_exit(
+    pid: u32,
+    mut child: Option<&mut Child>,
+    first: StopSignal,
+    timeout: Duration,
+) -> Result<(StopSignal, Option<i32>), AppError> {
+    for step in escalation(first) {
+        send_signal(pid, *step)?;
+        if let Some(exit_code) = wait_exit(pid, child.as_deref_mut(), timeout).await {
+            return Ok((*step, exit_code));
+        }
+        warn!("recorder {pid} ignored SIG{}", step.as_str());
+    }
+    Err(AppError::internal(format!(
+        "recorder {pid} survived SIGKILL"
+    )))
+}
+
+/// INT → TERM → KILL, beginnend beim angeforderten Signal.
+fn escalation(first: StopSignal) -> &'static [StopSignal] {
+    const LADDER: [StopSignal; 3] = [StopSignal::Int, StopSignal::Term, StopSignal::Kill];
+    match first {
+        StopSignal::Int => &LADDER,
+        StopSignal::Term => &LADDER[1..],
+        StopSignal::Kill => &LADDER[2..],
+    }
+}
+
+fn adopt_from_disk(state_dir: &Path, binary: &Path) -> Option<Session> {
+    let pid_path = state_dir.join(PID_FILE);
+    let raw = fs::read_to_string(&pid_path).ok()?;
+    let pid = raw.trim().parse::<u32>().ok().filter(|pid| *pid > 0);
+    let clear = || {
+        let _ = fs::remove_file(&pid_path);
+        let _ = fs::remove_file(state_dir.join(META_FILE));
+    };
+
+    let Some(pid) = pid.filter(|pid| process_alive(*pid)) else {
+        // Verwaiste oder kaputte PID-Datei: aufräumen wie `rec-stop`.
+        clear();
+        return None;
+    };
+
+    let meta = fs::read_to_string(state_dir.join(META_FILE))
+        .ok()
+        .and_then(|raw| serde_json::from_str::<SessionMeta>(&raw).ok())
+        .filter(|meta| meta.pid == pid)
+        .unwrap_or(SessionMeta {
+            pid,
+            output: String::new(),
+            command: Vec::new(),
+            started_at: None,
+        });
+
+    // Die PID-Datei überlebt Neustarts; eine wiederverwendete PID gehört dann
+    // einem fremden Prozess, der sonst INT → TERM → KILL abbekäme.
+    let expected = meta.command.first().map_or(binary, Path::new);
+    if !runs_binary(pid, expected) {
+        warn!(
+            "pid {pid} from {} is not {}; discarding stale recorder state",
+            pid_path.display(),
+            expected.display()
+        );
+        clear();
+        return None;
+    }
+
+    Some(Session {
+        meta,
+        child: None,
+        stopping: false,
+    })
+}
+
+/// Vergleicht den Programmnamen aus `/proc/<pid>/cmdline` mit `binary`; auch
+/// das erste Argument zählt, damit per Interpreter gestartete Skripte passen.
+fn runs_binary(pid: u32, binary: &Path) -> bool {
+    let Ok(raw) = fs::read(format!("/proc/{pid}/cmdline")) else {
+        return false;
+    };
+    let Some(expected) = binary.file_name() else {
+        return false;
+    };
+    raw.split(|byte| *byte == 0)
+        .take(2)
+        .filter(|arg| !arg.is_empty())
+        .any(|arg| Path::new(std::ffi::OsStr::from_bytes(arg)).file_name() == Some(expected))
+}
+
+fn process_alive(pid: u32) -> bool {
+    let Ok(raw) = i32::try_from(pid) else {
+        return false;
+    };
+    signal::kill(Pid::from_raw(raw), None).is_ok()
+}
+
+fn send_signal(pid: u32, step: StopSignal) -> Result<(), AppError> {
+    let raw = i32::try_from(pid).map_err(|_| AppError::internal(format!("invalid pid {pid}")))?;
+    let sig = match step {
+        StopSignal::Int => Signal::SIGINT,
+        StopSignal::Term => Signal::SIGTERM,
+        StopSignal::Kill => Signal::SIGKILL,
+    };
+    match signal::kill(Pid::from_raw(raw), sig) {
+        Ok(()) | Err(nix::errno::Errno::ESRCH) => Ok(()),
+        Err(err) => Err(AppError::internal(format!(
+            "failed to send SIG{} to recorder {pid}: {err}",
+            step.as_str()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(unix)]
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::TempDir;
+
+    fn write_fake_recorder(dir: &TempDir, body: &str) -> PathBuf {
+        let path = dir.path().join("fake-pw-record");
+        fs::write(&path, format!("#!/usr/bin/env bash\n{body}\n")).expect("write fake recorder");
+        #[cfg(unix)]
+        {
+            let mut perms = fs::metadata(&path).expect("metadata").permissions();
+            perms.set_mode(0o755);
+            fs::set_permissions(&path, perms).expect("permissions");
+        }
+        path
+    }
+
+    fn recorder_config(dir: &TempDir, binary: PathBuf) -> RecorderConfig {
+        RecorderConfig {
+            binary,
+            record_dir: dir.path().join("recordings"),
+            extension: "wav".into(),
+            state_dir: dir.path().join("state"),
+        }
+    }
+
+    #[tokio::test]
+    async fn start_status_stop_roundtrip() {
+        let dir = TempDir::new().unwrap();
+        // Schreibt die Argumente ins Log und wartet auf SIGINT
+        let binary = write_fake_recorder(&dir, "echo \"$@\"\nexec sleep 30");
+        let recorder = Recorder::new(recorder_config(&dir, binary));
+
+        let started = recorder
+            .start(&RecordingStartRequest {
+                rate: Some(48_000),
+                output: Some("take.wav".into()),
+                ..Default::default()
+            })
+            .await
+            .expect("start");
+        assert!(started.output.ends_with("recordings/take.wav"));
+        assert!(started.command.contains(&"48000".to_string()));
+
+        let pid_file = dir.path().join("state").join(PID_FILE);
+        assert_eq!(
+            fs::read_to_string(&pid_file).unwrap().trim(),
+            started.pid.to_string()
+        );
+
+        let status = recorder.status().await;
+        assert!(status.running);
+        assert_eq!(status.pid, Some(started.pid));
+
+        let err = recorder
+            .start(&RecordingStartRequest::default())
+            .await
+            .expect_err("second start must conflict");
+        assert!(matches!(err, AppError::Conflict(_)));
+
+        let stopped = recorder
+            .stop(&RecordingStopRequest::default())
+            .await
+            .expect("stop");
+        assert_eq!(stopped.pid, started.pid);
+        assert_eq!(stopped.signal, Some(StopSignal::Int));
+        assert!(!stopped.killed);
+        assert!(!pid_file.exists());
+        assert!(!recorder.status().await.running);
+    }
+
+    #[tokio::test]
+    async fn stop_escalates_to_kill() {
+        let dir = TempDir::new().unwrap();
+        let binary = write_fake_recorder(&dir, "trap '' INT TERM\nwhile true; do sleep 0.05; done");
+        let recorder = Recorder::new(recorder_config(&dir, binary));
+        recorder
+            .start(&RecordingStartRequest::default())
+            .await
+            .expect("start");
+        // Trap muss stehen, bevor wir signalisieren
+        time::sleep(Duration::from_millis(200)).await;
+
+        let stopped = recorder
+            .stop(&RecordingStopRequest {
+                signal: None,
+                timeout: Some(0.2),
+            })
+            .await
+            .expect("stop");
+        assert_eq!(stopped.signal, Some(StopSignal::Kill));
+        assert!(stopped.killed);
+    }
+
+    #[tokio::test]
+    async fn status_answers_while_stop_waits() {
+        let dir = TempDir::new().unwrap();
+        let binary = write_fake_recorder(&dir, "trap '' INT TERM\nwhile true; do sleep 0.05; done");
+        let recorder = std::sync::Arc::new(Recorder::new(recorder_config(&dir, binary)));
+        recorder
+            .start(&RecordingStartRequest::default())
+            .await
+            .expect("start");
+        time::sleep(Duration::from_millis(200)).await;
+
+        let stopping = tokio::spawn({
+            let recorder = recorder.clone();
+            async move {
+                recorder
+                    .stop(&RecordingStopRequest {
+                        signal: None,
+                        timeout: Some(1.0),
+                    })
+                    .await
+            }
+        });
+        time::sleep(Duration::from_millis(200)).await;
+
+        let status = time::timeout(Duration::from_millis(100), recorder.status())
+            .await
+            .expect("status must not wait for stop");
+        assert!(status.running);
+        assert!(status.stopping);
+
+        let err = recorder
+            .stop(&RecordingStopRequest::default())
+            .await
+            .expect_err("second stop must conflict");
+        assert!(matches!(err, AppError::Conflict(_)));
+
+        let stopped = stopping.await.unwrap().expect("stop");
+        assert!(stopped.killed);
+        let status = recorder.status().await;
+        assert!(!status.running);
+        assert!(!status.stopping);
+    }
+
+    #[tokio::test]
+    async fn adopts_running_recorder_from_pid_file() {
+        let dir = TempDir::new().unwrap();
+        let state_dir = dir.path().join("state");
+        fs::create_dir_all(&state_dir).unwrap();
+
+        let mut orphan = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .expect("spawn sleep");
+        let pid = orphan.id();
+        fs::write(state_dir.join(PID_FILE), format!("{pid}\n")).unwrap();
+        fs::write(
+            state_dir.join(META_FILE),
+            format!("{{\"pid\": {pid}, \"output\": \"/tmp/old.wav\"}}"),
+        )
+        .unwrap();
+        // Zombie vermeiden, sonst meldet kill(pid, 0) den Prozess weiter als lebendig
+        let reaper = std::thread::spawn(move || orphan.wait());
+
+        let recorder = Recorder::new(recorder_config(&dir, PathBuf::from("/usr/bin/sleep")));
+        let status = recorder.status().await;
+        assert!(status.running);
+        assert_eq!(status.pid, Some(pid));
+        assert_eq!(status.output.as_deref(), Some("/tmp/old.wav"));
+
+        let stopped = recorder
+            .stop(&RecordingStopRequest::default())
+            .await
+            .expect("stop");
+        assert_eq!(stopped.pid, pid);
+        assert!(!state_dir.join(PID_FILE).exists());
+        reaper.join().unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn refuses_to_adopt_foreign_process() {
+        let dir = TempDir::new().unwrap();
+        let state_dir = dir.path().join("state");
+        fs::create_dir_all(&state_dir).unwrap();
+
+        // Lebender, aber fremder Prozess hinter einer alten PID-Datei
+        let mut foreign = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .expect("spawn sleep");
+        let pid = foreign.id();
+        fs::write(state_dir.join(PID_FILE), format!("{pid}\n")).unwrap();
+        fs::write(
+            state_dir.join(META_FILE),
+            format!(
+                "{{\"pid\": {pid}, \"output\": \"/tmp/old.wav\", \"command\": [\"pw-record\"]}}"
+            ),
+        )
+        .unwrap();
+
+        let recorder = Recorder::new(recorder_config(&dir, PathBuf::from("pw-record")));
+        assert!(!recorder.status().await.running);
+        assert!(!state_dir.join(PID_FILE).exists());
+        assert!(!state_dir.join(META_FILE).exists());
+
+        let err = recorder
+            .stop(&RecordingStopRequest::default())
+            .await
+            .expect_err("nothing to stop");
+        assert!(matches!(err, AppError::Conflict(_)));
+        assert!(
+            foreign.try_wait().unwrap().is_none(),
+            "foreign process must survive"
+        );
+        foreign.kill().unwrap();
+        foreign.wait().unwrap();
+    }
+
+    #[tokio::test]
+    async fn clears_stale_pid_file() {
+        let dir = TempDir::new().unwrap();
+        let state_dir = dir.path().join("state");
+        fs::create_dir_all(&state_dir).unwrap();
+        fs::write(state_dir.join(PID_FILE), "not-a-pid\n").unwrap();
+
+        let recorder = Recorder::new(recorder_config(&dir, PathBuf::from("unused")));
+        assert!(!recorder.status().await.running);
+        assert!(!state_dir.join(PID_FILE).exists());
+
+        let err = recorder
+            .stop(&RecordingStopRequest::default())
+            .await
+            .expect_err("nothing to stop");
+        assert!(matches!(err, AppError::Conflict(_)));
+    }
+}
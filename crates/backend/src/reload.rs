@@ -0,0 +1,171 @@
+//! Hot Reload: `AppConfig`, Mopidy-Client und Event-Bridge-Ziel zur Laufzeit
+//! austauschen, ohne den Prozess (und damit eine laufende Aufnahme) neu zu starten.
+//!
+//! Ausgelöst per SIGHUP oder `POST /admin/reload`. Die neue Konfiguration wird
+//! erst nach `AppConfig::validate` übernommen; bei Fehlern bleibt die alte aktiv.
+//! Recorder und Bind-Adresse werden nicht neu aufgebaut.
+
+use std::sync::{Arc, RwLock};
+
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+use crate::config::{AppConfig, ConfigError};
+use crate::error::AppError;
+use crate::events::EventBridge;
+use crate::models::ReloadReport;
+use crate::mopidy::MopidyClient;
+
+type Connector = Box<dyn Fn(&AppConfig) -> Arc<dyn MopidyClient> + Send + Sync>;
+
+/// Aktuelle Konfiguration samt passendem Mopidy-Client.
+#[derive(Clone)]
+struct Snapshot {
+    config: Arc<AppConfig>,
+    mopidy: Arc<dyn MopidyClient>,
+}
+
+pub struct LiveConfig {
+    current: RwLock<Snapshot>,
+    connect: Connector,
+    /// Bleibt bestehen (SSE-Abonnenten hängen daran), folgt aber der Mopidy-Adresse.
+    events: Arc<EventBridge>,
+    /// Serialisiert Reloads (SIGHUP und HTTP gleichzeitig).
+    reloading: Mutex<()>,
+}
+
+impl LiveConfig {
+    /// `connect` baut den Mopidy-Client für eine (validierte) Konfiguration.
+    pub fn new(
+        config: AppConfig,
+        connect: impl Fn(&AppConfig) -> Arc<dyn MopidyClient> + Send + Sync + 'static,
+    ) -> Self {
+        let mopidy = connect(&config);
+        let events = Arc::new(EventBridge::new(config.mopidy_ws_url()));
+        Self {
+            current: RwLock::new(Snapshot {
+                config: Arc::new(config),
+                mopidy,
+            }),
+            connect: Box::new(connect),
+            events,
+            reloading: Mutex::new(()),
+        }
+    }
+
+    #[must_use]
+    pub fn config(&self) -> Arc<AppConfig> {
+        self.snapshot().config
+    }
+
+    #[must_use]
+    pub fn mopidy(&self) -> Arc<dyn MopidyClient> {
+        self.snapshot().mopidy
+    }
+
+    #[must_use]
+    pub fn events(&self) -> Arc<EventBridge> {
+        self.events.clone()
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        self.current
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Lädt die Konfiguration neu (gleiche Datei wie beim Start, Umgebung des Prozesses).
+    pub async fn reload(&self) -> ReloadReport {
+        let file = self.config().sources.file().map(ToOwned::to_owned);
+        self.apply(|| AppConfig::load(file.as_deref())).await
+    }
+
+    /// Übernimmt das Ergebnis von `load`, sofern es lädt und validiert.
+    pub async fn apply(
+        &self,
+        load: impl FnOnce() -> Result<AppConfig, ConfigError>,
+    ) -> ReloadReport {
+        let _guard = self.reloading.lock().await;
+        let previous = self.config();
+
+        let loaded = load()
+            .map_err(AppError::from)
+            .and_then(|config| config.validate().map(|()| config));
+        let config = match loaded {
+            Ok(config) => config,
+            Err(err) => {
+                warn!("configuration reload failed, keeping previous configuration: {err}");
+                return ReloadReport {
+                    reloaded: false,
+                    changed: Vec::new(),
+                    restart_required: Vec::new(),
+                    error: Some(err.to_string()),
+                };
+            }
+        };
+
+        let changed = changed_keys(&previous, &config);
+        let restart_required: Vec<String> = changed
+            .iter()
+            .filter(|key| *key == "bind" || key.starts_with("recorder."))
+            .cloned()
+            .collect();
+
+        let mopidy = (self.connect)(&config);
+        self.events.set_url(config.mopidy_ws_url());
+        *self
+            .current
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = Snapshot {
+            config: Arc::new(config),
+            mopidy,
+        };
+
+        if changed.is_empty() {
+            info!("configuration reloaded without changes");
+        } else {
+            info!("configuration reloaded; changed: {}", changed.join(", "));
+        }
+        if !restart_required.is_empty() {
+            warn!("restart required to apply: {}", restart_required.join(", "));
+        }
+
+        ReloadReport {
+            reloaded: true,
+            changed,
+            restart_required,
+            error: None,
+        }
+    }
+
+    /// Lädt bei jedem SIGHUP neu (Unix); muss in einer Tokio-Runtime laufen.
+    #[cfg(unix)]
+    pub fn reload_on_sighup(self: Arc<Self>) -> std::io::Result<()> {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut hangup = signal(SignalKind::hangup())?;
+        tokio::spawn(async move {
+            while hangup.recv().await.is_some() {
+                info!("SIGHUP received, reloading configuration");
+                self.reload().await;
+            }
+        });
+        Ok(())
+    }
+}
+
+/// Vergleich über die Quellen-Tabelle, damit Schlüssel wie in der TOML-Datei heißen.
+fn changed_keys(previous: &AppConfig, next: &AppConfig) -> Vec<String> {
+    let old = previous.sources.entries();
+    next.sources
+        .entries()
+        .iter()
+        .filter(|entry| {
+            old.iter()
+                .find(|candidate| candidate.key == entry.key)
+                .is_none_or(|candidate| candidate.value != entry.value)
+        })
+        .map(|entry| entry.key.clone())
+        .collect()
+}
@@ -0,0 +1,306 @@
+//! Filter für `/rpc`: prüft Methoden gegen `RpcPolicy` und die URIs einzureihender
+//! bzw. zu speichernder Tracks gegen `validation::is_allowed_uri`, bevor etwas an
+//! Mopidy geht. Abgelehnte Aufrufe erhalten JSON-RPC-Fehlerobjekte, Batches
+//! werden pro Eintrag gefiltert.
+
+use serde_json::{json, Map, Value};
+use tracing::{instrument, warn};
+
+use crate::config::RpcPolicy;
+use crate::error::AppError;
+use crate::mopidy::MopidyClient;
+use crate::validation;
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const INVALID_PARAMS: i64 = -32602;
+/// Serverdefinierter Code für Methoden außerhalb der Allowlist.
+const METHOD_NOT_ALLOWED: i64 = -32001;
+
+/// Methoden, die Tracks einreihen oder speichern, mit Mopidys Parameternamen
+/// (positionale Parameter werden darauf abgebildet).
+const TRACK_METHODS: [(&str, &[&str]); 2] = [
+    ("core.tracklist.add", &["tracks", "at_position", "uris"]),
+    ("core.playlists.save", &["playlist"]),
+];
+
+/// Liefert die Antwort für den Client; `None` bei reinen Notifications.
+#[instrument(skip(policy, mopidy, body))]
+pub async fn handle(
+    policy: &RpcPolicy,
+    mopidy: &dyn MopidyClient,
+    body: &[u8],
+) -> Result<Option<Value>, AppError> {
+    let payload = match serde_json::from_slice::<Value>(body) {
+        Ok(payload) => payload,
+        Err(err) => {
+            return Ok(Some(error_response(
+                Value::Null,
+                PARSE_ERROR,
+                "Parse error",
+                Some(json!(err.to_string())),
+            )))
+        }
+    };
+
+    match payload {
+        Value::Array(entries) if entries.is_empty() => Ok(Some(error_response(
+            Value::Null,
+            INVALID_REQUEST,
+            "Invalid Request",
+            Some(json!("empty batch")),
+        ))),
+        Value::Array(entries) => handle_batch(policy, mopidy, entries).await,
+        request => match check(policy, &request) {
+            Ok(()) => mopidy
+                .proxy(request)
+                .await
+                .map(|response| (!response.is_null()).then_some(response)),
+            Err(rejection) => Ok(is_call(&request).then_some(rejection)),
+        },
+    }
+}
+
+async fn handle_batch(
+    policy: &RpcPolicy,
+    mopidy: &dyn MopidyClient,
+    entries: Vec<Value>,
+) -> Result<Option<Value>, AppError> {
+    // Pro Eintrag: weitergeleitet (`Ok`) oder lokal abgelehnt (`Err`, `Null` = keine Antwort).
+    let mut slots: Vec<Result<Value, Value>> = Vec::with_capacity(entries.len());
+    let mut forwarded = Vec::new();
+    for entry in entries {
+        match check(policy, &entry) {
+            Ok(()) => {
+                forwarded.push(entry.clone());
+                slots.push(Ok(entry));
+            }
+            Err(rejection) => slots.push(Err(if is_call(&entry) {
+                rejection
+            } else {
+                Value::Null
+            })),
+        }
+    }
+
+    let upstream = if forwarded.is_empty() {
+        Vec::new()
+    } else {
+        match mopidy.proxy(Value::Array(forwarded)).await? {
+            Value::Array(responses) => responses,
+            Value::Null => Vec::new(),
+            single => vec![single],
+        }
+    };
+
+    // Antworten in Request-Reihenfolge zusammenführen (Zuordnung über die ID).
+    let mut responses = Vec::new();
+    for slot in slots {
+        match slot {
+            Err(Value::Null) => {}
+            Err(rejection) => responses.push(rejection),
+            Ok(request) => {
+                let Some(id) = request.get("id").filter(|id| !id.is_null()) else {
+                    continue;
+                };
+                match upstream
+                    .iter()
+                    .find(|response| response.get("id") == Some(id))
+                {
+                    Some(response) => responses.push(response.clone()),
+                    None => warn!("Mopidy sent no response for batch id {id}"),
+                }
+            }
+        }
+    }
+
+    Ok((!responses.is_empty()).then_some(Value::Array(responses)))
+}
+
+fn check(policy: &RpcPolicy, request: &Value) -> Result<(), Value> {
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let Some(method) = request
+        .as_object()
+        .and_then(|object| object.get("method"))
+        .and_then(Value::as_str)
+    else {
+        return Err(error_response(id, INVALID_REQUEST, "Invalid Request", None));
+    };
+
+    if !policy.permits(method) {
+        warn!("rejected RPC method {method}");
+        return Err(error_response(
+            id,
+            METHOD_NOT_ALLOWED,
+            "Method not allowed",
+            Some(json!({ "method": method })),
+        ));
+    }
+
+    if let Some(data) = rejected_tracks(method, request.get("params")) {
+        return Err(error_response(
+            id,
+            INVALID_PARAMS,
+            "Invalid params",
+            Some(data),
+        ));
+    }
+    Ok(())
+}
+
+/// Fehlerdaten, wenn ein Track-Aufruf eine nicht erlaubte URI enthält
+/// (`uris`, `tracks[].uri`, `playlist.tracks[].uri`); andere Methoden bleiben ungeprüft.
+fn rejected_tracks(method: &str, params: Option<&Value>) -> Option<Value> {
+    let (_, names) = TRACK_METHODS.iter().find(|(name, _)| *name == method)?;
+    let named: Map<String, Value> = match params {
+        None | Some(Value::Null) => return None,
+        Some(Value::Object(map)) => map.clone(),
+        Some(Value::Array(values)) if values.len() <= names.len() => names
+            .iter()
+            .map(|name| (*name).to_string())
+            .zip(values.iter().cloned())
+            .collect(),
+        Some(_) => {
+            return Some(json!({
+                "reason": format!("expected named params or at most {} positional params", names.len())
+            }))
+        }
+    };
+
+    let playlist_tracks = named
+        .get("playlist")
+        .and_then(|playlist| playlist.get("tracks"));
+    let track_uris = list(named.get("tracks"))
+        .iter()
+        .chain(list(playlist_tracks))
+        .map(|track| track.get("uri").unwrap_or(&Value::Null));
+
+    list(named.get("uris"))
+        .iter()
+        .chain(track_uris)
+        .find(|uri| !uri.as_str().is_some_and(validation::is_allowed_uri))
+        .map(|uri| json!({ "disallowed_uri": uri }))
+}
+
+fn list(value: Option<&Value>) -> &[Value] {
+    value
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+/// Notifications (ohne `id`) bekommen laut JSON-RPC keine Antwort.
+fn is_call(request: &Value) -> bool {
+    request.get("id").is_some_and(|id| !id.is_null()) || !request.is_object()
+}
+
+fn error_response(id: Value, code: i64, message: &str, data: Option<Value>) -> Value {
+    let mut error = json!({ "code": code, "message": message });
+    if let Some(data) = data {
+        error["data"] = data;
+    }
+    json!({ "jsonrpc": "2.0", "id": id, "error": error })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_rejects_denied_methods_and_uris() {
+        let policy = RpcPolicy::default();
+
+        let denied = check(
+            &policy,
+            &json!({"jsonrpc": "2.0", "id": 7, "method": "core.library.refresh"}),
+        )
+        .expect_err("denied");
+        assert_eq!(denied["id"], 7);
+        assert_eq!(denied["error"]["code"], METHOD_NOT_ALLOWED);
+
+        let bad_uri = check(
+            &policy,
+            &json!({
+                "jsonrpc": "2.0",
+                "id": 8,
+                "method": "core.tracklist.add",
+                "params": {"uris": ["qobuz:track:1", "file:///etc/passwd"]}
+            }),
+        )
+        .expect_err("bad uri");
+        assert_eq!(bad_uri["error"]["code"], INVALID_PARAMS);
+        assert_eq!(
+            bad_uri["error"]["data"]["disallowed_uri"],
+            "file:///etc/passwd"
+        );
+
+        assert!(check(
+            &policy,
+            &json!({"jsonrpc": "2.0", "id": 9, "method": "core.playback.play"})
+        )
+        .is_ok());
+
+        // Positionale Parameter werden auf Mopidys Namen abgebildet.
+        let positional = check(
+            &policy,
+            &json!({
+                "jsonrpc": "2.0",
+                "id": 10,
+                "method": "core.tracklist.add",
+                "params": [null, null, ["file:///etc/passwd"]]
+            }),
+        )
+        .expect_err("positional uri");
+        assert_eq!(
+            positional["error"]["data"]["disallowed_uri"],
+            "file:///etc/passwd"
+        );
+        assert!(check(
+            &policy,
+            &json!({"jsonrpc": "2.0", "id": 11, "method": "core.tracklist.add", "params": [null, 0, [], 1]})
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn check_only_validates_track_uris() {
+        let policy = RpcPolicy::default();
+        let call = |method: &str, params: Value| {
+            check(
+                &policy,
+                &json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params}),
+            )
+        };
+
+        assert!(call(
+            "core.playlists.lookup",
+            json!({"uri": "m3u:Favoriten.m3u8"})
+        )
+        .is_ok());
+        assert!(call("core.library.browse", json!({"uri": "file:///srv/music"})).is_ok());
+        assert!(call(
+            "core.playlists.save",
+            json!({"playlist": {"__model__": "Playlist", "uri": "m3u:a.m3u8", "tracks": [{"uri": "qobuz:track:1"}]}})
+        )
+        .is_ok());
+
+        let rejected = call(
+            "core.playlists.save",
+            json!([{"uri": "m3u:a.m3u8", "tracks": [{"uri": "file:///etc/passwd"}]}]),
+        )
+        .expect_err("file track");
+        assert_eq!(
+            rejected["error"]["data"]["disallowed_uri"],
+            "file:///etc/passwd"
+        );
+        assert!(call(
+            "core.tracklist.add",
+            json!({"tracks": [{"uri": "spotify:track:1"}]})
+        )
+        .is_ok());
+        assert_eq!(
+            check(&policy, &json!("nope")).expect_err("invalid")["error"]["code"],
+            INVALID_REQUEST
+        );
+    }
+}
@@ -1,5 +1,4 @@
 //! Zentrale Script-Konstanten, um Merge-Divergenzen zu vermeiden.
 pub const DEFAULT_AUDIO_MODE_CMD: &str = "./scripts/audio-mode";
 pub const DEFAULT_PLAYLIST_CMD: &str = "./scripts/playlist-from-list";
-pub const DEFAULT_REC_START_CMD: &str = "./scripts/rec-start";
-pub const DEFAULT_REC_STOP_CMD: &str = "./scripts/rec-stop";
+pub const DEFAULT_HW_MIXER_CMD: &str = "./scripts/hw-mixer";
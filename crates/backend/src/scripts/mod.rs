@@ -1,2 +1,3 @@
 pub mod constants;
+pub mod reaper;
 pub mod runner;
@@ -0,0 +1,144 @@
+//! Aufräumen abgebrochener Skripte samt Prozessgruppe.
+//!
+//! Skripte laufen in eigener Prozessgruppe (`process_group(0)`), damit bei
+//! Timeout, Abbruch oder Client-Disconnect auch Enkel wie
+//! `systemctl --user restart mopidy` beendet werden: erst SIGTERM an die
+//! Gruppe, nach `TERM_GRACE` SIGKILL. Der Exit wird im Hintergrund abgewartet
+//! und protokolliert (`hauski_script_reaped_total`).
+
+use std::time::{Duration, Instant};
+
+use nix::errno::Errno;
+use nix::sys::signal::{killpg, Signal};
+use nix::unistd::Pid;
+use tokio::process::Child;
+use tokio::time;
+use tracing::{debug, warn};
+
+use crate::metrics::METRICS;
+
+/// Frist zwischen SIGTERM und SIGKILL an die Prozessgruppe.
+pub const TERM_GRACE: Duration = Duration::from_secs(2);
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Warum ein Skript vorzeitig beendet wird (Label der Metrik).
+#[derive(Debug, Clone, Copy)]
+pub enum ReapReason {
+    Timeout,
+    Cancelled,
+    /// Der Aufrufer ist verschwunden (z. B. HTTP-Client getrennt).
+    Dropped,
+}
+
+impl ReapReason {
+    fn as_str(self) -> &'static str {
+        match self {
+            ReapReason::Timeout => "timeout",
+            ReapReason::Cancelled => "cancelled",
+            ReapReason::Dropped => "dropped",
+        }
+    }
+}
+
+/// Besitzt das laufende Skript; wird es ohne `disarm` fallen gelassen,
+/// übernimmt der Reaper die Prozessgruppe.
+pub(crate) struct ProcessGuard {
+    script: String,
+    child: Option<Child>,
+}
+
+impl ProcessGuard {
+    pub(crate) fn new(script: &str, child: Child) -> Self {
+        Self {
+            script: script.to_string(),
+            child: Some(child),
+        }
+    }
+
+    pub(crate) fn child(&mut self) -> &mut Child {
+        self.child
+            .as_mut()
+            .expect("child is present until released")
+    }
+
+    /// Skript regulär beendet; Gruppe bleibt unangetastet.
+    pub(crate) fn disarm(mut self) {
+        self.child = None;
+    }
+
+    /// Beendet die Prozessgruppe im Hintergrund.
+    pub(crate) fn reap(mut self, reason: ReapReason) {
+        if let Some(child) = self.child.take() {
+            reap(self.script.clone(), child, reason);
+        }
+    }
+}
+
+impl Drop for ProcessGuard {
+    fn drop(&mut self) {
+        if let Some(child) = self.child.take() {
+            reap(self.script.clone(), child, ReapReason::Dropped);
+        }
+    }
+}
+
+fn reap(script: String, mut child: Child, reason: ReapReason) {
+    // `id()` ist `None`, wenn der Exit schon abgeholt wurde.
+    let Some(pgid) = child.id().and_then(|pid| i32::try_from(pid).ok()) else {
+        return;
+    };
+    let pgid = Pid::from_raw(pgid);
+    signal_group(pgid, Signal::SIGTERM);
+
+    let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+        // Ohne Runtime (Shutdown) gibt es kein Warten mehr.
+        signal_group(pgid, Signal::SIGKILL);
+        return;
+    };
+    runtime.spawn(async move {
+        let started = Instant::now();
+        let mut signal = Signal::SIGTERM;
+        let mut status = None;
+        // Enkel können SIGTERM ignorieren oder den Anführer überleben: Gruppe prüfen.
+        while group_alive(pgid) {
+            if status.is_none() {
+                status = child.try_wait().ok().flatten();
+            }
+            if started.elapsed() >= TERM_GRACE {
+                signal = Signal::SIGKILL;
+                signal_group(pgid, signal);
+                break;
+            }
+            time::sleep(POLL_INTERVAL).await;
+        }
+        let status = match status {
+            Some(status) => Some(status),
+            None => child.wait().await.ok(),
+        };
+
+        let signal_label = if signal == Signal::SIGKILL {
+            "kill"
+        } else {
+            "term"
+        };
+        METRICS.observe_reaped(&script, reason.as_str(), signal_label);
+        warn!(
+            "reaped {script} ({}) after {:?} via {signal_label}: {}",
+            reason.as_str(),
+            started.elapsed(),
+            status.map_or_else(|| "unknown status".to_string(), |status| status.to_string())
+        );
+    });
+}
+
+fn signal_group(pgid: Pid, signal: Signal) {
+    match killpg(pgid, signal) {
+        Ok(()) | Err(Errno::ESRCH) => {}
+        Err(err) => debug!("failed to send {signal} to process group {pgid}: {err}"),
+    }
+}
+
+/// Signal 0 an die Gruppe: existiert noch ein Mitglied (auch als Zombie)?
+fn group_alive(pgid: Pid) -> bool {
+    killpg(pgid, None).is_ok()
+}
@@ -1,21 +1,186 @@
-use crate::config::AppConfig;
-use anyhow::{anyhow, Context, Result};
+use crate::config::{AppConfig, OnBusy, ScriptConfig};
+use crate::error::AppError;
+use crate::metrics::METRICS;
+use crate::scripts::reaper::{ProcessGuard, ReapReason};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
 use std::process::Stdio;
-use tokio::io::AsyncWriteExt;
+use std::sync::{Arc, LazyLock, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader, Split};
 use tokio::process::Command;
-use tokio::time;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::{self, Sleep};
 
+/// Semaphoren für `max_concurrency` je Programmpfad, prozessweit (überleben
+/// Reloads; bei geänderter Grenze gibt es eine neue Semaphore).
+static SLOTS: LazyLock<Mutex<HashMap<PathBuf, Slots>>> = LazyLock::new(Mutex::default);
+
+/// Grenze und zugehörige Semaphore.
+type Slots = (usize, Arc<Semaphore>);
+
+/// Ergebnis eines Skriptlaufs; stderr, Exit-Code und Laufzeit werden immer erfasst.
+///
+/// Protokoll: Ein Skript darf als Letztes ein JSON-Objekt auf stdout ausgeben
+/// (einzeilig oder eingerückt, wie `rec-start --json`); es landet in `result`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScriptOutput {
+    /// Dateiname des Skripts (auch Label der Metriken).
+    pub script: String,
+    pub stdout: String,
+    pub stderr: String,
+    /// `None`, wenn das Skript durch ein Signal beendet wurde.
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Map<String, Value>>,
+}
+
+impl ScriptOutput {
+    #[must_use]
+    pub fn success(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+
+    /// Typisierte Sicht auf das abschließende JSON-Objekt; `None` ohne Objekt.
+    pub fn parse_result<T: DeserializeOwned>(&self) -> Result<Option<T>, AppError> {
+        self.result
+            .clone()
+            .map(|map| {
+                serde_json::from_value(Value::Object(map)).map_err(|err| {
+                    AppError::internal(format!(
+                        "unexpected JSON result from {}: {err}",
+                        self.script
+                    ))
+                })
+            })
+            .transpose()
+    }
+}
+
+/// Ausgabekanal einer gestreamten Zeile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+impl OutputStream {
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutputStream::Stdout => "stdout",
+            OutputStream::Stderr => "stderr",
+        }
+    }
+}
+
+/// Programm ohne eigene Optionen (Timeout `command_timeout`).
 pub async fn run_script(
     config: &AppConfig,
     program: &str,
     args: &[&str],
     input: Option<&str>,
-) -> Result<String> {
+) -> Result<ScriptOutput, AppError> {
+    run_configured(config, &ScriptConfig::new(program), args, input).await
+}
+
+/// Konfiguriertes Skript; Timeout aus `[scripts.<name>]`, sonst `command_timeout`.
+pub async fn run_configured(
+    config: &AppConfig,
+    script: &ScriptConfig,
+    args: &[&str],
+    input: Option<&str>,
+) -> Result<ScriptOutput, AppError> {
+    run_script_streaming(
+        config,
+        script,
+        args,
+        input,
+        script.timeout.unwrap_or(config.command_timeout),
+        std::future::pending(),
+        |_, _| {},
+    )
+    .await
+}
+
+/// Wie `run_configured`, meldet aber jede Zeile sofort an `on_line`. Endet
+/// `cancel`, wird das Skript beendet (`AppError::ScriptCancelled`).
+pub async fn run_script_streaming(
+    config: &AppConfig,
+    script: &ScriptConfig,
+    args: &[&str],
+    input: Option<&str>,
+    timeout: Duration,
+    cancel: impl Future<Output = ()>,
+    on_line: impl FnMut(OutputStream, &str),
+) -> Result<ScriptOutput, AppError> {
+    let program = script.resolve_with(&config.script_workdir);
+    let name = program.file_name().map_or_else(
+        || program.display().to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    );
+    let started = Instant::now();
+    let (result, status) = execute(
+        config, &name, script, &program, args, input, timeout, cancel, on_line, started,
+    )
+    .await;
+    METRICS.observe_script(&name, &status, started);
+    result
+}
+
+/// Führt das Skript aus und liefert zusätzlich das Status-Label für die Metriken.
+#[allow(clippy::too_many_arguments)]
+async fn execute(
+    config: &AppConfig,
+    name: &str,
+    script: &ScriptConfig,
+    program: &Path,
+    args: &[&str],
+    input: Option<&str>,
+    timeout: Duration,
+    cancel: impl Future<Output = ()>,
+    mut on_line: impl FnMut(OutputStream, &str),
+    started: Instant,
+) -> (Result<ScriptOutput, AppError>, String) {
+    let io_error = |context: &str, err: std::io::Error| {
+        AppError::Command(std::io::Error::new(
+            err.kind(),
+            format!("{context} {}: {err}", program.display()),
+        ))
+    };
+
+    // Die Wartezeit auf einen freien Platz zählt zum Timeout.
+    let deadline = time::sleep(timeout);
+    tokio::pin!(deadline, cancel);
+    let slot = acquire_slot(
+        name,
+        script,
+        program,
+        timeout,
+        deadline.as_mut(),
+        cancel.as_mut(),
+    );
+    let _slot = match slot.await {
+        Ok(slot) => slot,
+        Err(failure) => return failure,
+    };
+
     let mut command = Command::new(program);
+    command.args(&script.args);
     command.args(args);
-    command.current_dir(&config.script_workdir);
+    command.envs(&script.env);
+    command.current_dir(script.workdir_with(&config.script_workdir));
     command.stdout(Stdio::piped());
     command.stderr(Stdio::piped());
+    // Eigene Prozessgruppe, damit beim Abbruch auch Enkelprozesse enden.
+    command.process_group(0);
 
     if input.is_some() {
         command.stdin(Stdio::piped());
@@ -23,40 +188,215 @@ pub async fn run_script(
         command.stdin(Stdio::null());
     }
 
-    let mut child = command
-        .spawn()
-        .with_context(|| format!("failed to spawn {program}"))?;
+    let mut child = match command.spawn() {
+        Ok(child) => ProcessGuard::new(name, child),
+        Err(err) => return (Err(io_error("failed to spawn", err)), "spawn_error".into()),
+    };
+
+    // stdin läuft als eigener `select!`-Zweig, damit Timeout und Abbruch auch
+    // greifen, wenn das Skript die Eingabe nicht (vollständig) liest.
+    let stdin = child.child().stdin.take();
+    let mut stdin_open = stdin.is_some();
+    let feed = async move {
+        if let (Some(mut stdin), Some(payload)) = (stdin, input) {
+            stdin.write_all(payload.as_bytes()).await?;
+            stdin.shutdown().await?;
+        }
+        Ok::<(), std::io::Error>(())
+    };
+    tokio::pin!(feed);
+
+    let mut stdout_lines = child
+        .child()
+        .stdout
+        .take()
+        .map(|out| BufReader::new(out).split(b'\n'));
+    let mut stderr_lines = child
+        .child()
+        .stderr
+        .take()
+        .map(|err| BufReader::new(err).split(b'\n'));
+    let mut stdout = String::new();
+    let mut stderr = String::new();
+
+    let status = loop {
+        tokio::select! {
+            written = &mut feed, if stdin_open => {
+                stdin_open = false;
+                if let Err(err) = written {
+                    return (
+                        Err(io_error("failed to write to stdin of", err)),
+                        "stdin_error".into(),
+                    );
+                }
+            }
+            line = next_line(&mut stdout_lines) => match line {
+                Some(line) => {
+                    on_line(OutputStream::Stdout, &line);
+                    stdout.push_str(&line);
+                    stdout.push('\n');
+                }
+                None => stdout_lines = None,
+            },
+            line = next_line(&mut stderr_lines) => match line {
+                Some(line) => {
+                    on_line(OutputStream::Stderr, &line);
+                    stderr.push_str(&line);
+                    stderr.push('\n');
+                }
+                None => stderr_lines = None,
+            },
+            // Erst warten, wenn beide Pipes zu sind, damit keine Zeile verloren geht.
+            status = child.child().wait(), if stdout_lines.is_none() && stderr_lines.is_none() => {
+                match status {
+                    Ok(status) => break status,
+                    Err(err) => {
+                        return (Err(io_error("failed to wait for", err)), "wait_error".into())
+                    }
+                }
+            }
+            () = &mut deadline => {
+                child.reap(ReapReason::Timeout);
+                let err = AppError::ScriptTimeout {
+                    script: name.into(),
+                    timeout,
+                };
+                return (Err(err), "timeout".into());
+            }
+            () = &mut cancel => {
+                child.reap(ReapReason::Cancelled);
+                let err = AppError::ScriptCancelled {
+                    script: name.into(),
+                };
+                return (Err(err), "cancelled".into());
+            }
+        }
+    };
+    child.disarm();
+
+    let result = ScriptOutput {
+        script: name.into(),
+        result: final_json_object(&stdout),
+        stdout,
+        stderr,
+        exit_code: status.code(),
+        duration_ms: u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX),
+    };
+
+    let status = result
+        .exit_code
+        .map_or_else(|| "signal".to_string(), |code| code.to_string());
+    if result.success() {
+        (Ok(result), status)
+    } else {
+        (Err(AppError::ScriptFailed(Box::new(result))), status)
+    }
+}
+
+type Failure = (Result<ScriptOutput, AppError>, String);
 
-    if let Some(payload) = input {
-        if let Some(mut stdin) = child.stdin.take() {
-            stdin
-                .write_all(payload.as_bytes())
-                .await
-                .with_context(|| format!("failed to write to stdin for {program}"))?;
+/// Platz für einen Lauf gemäß `max_concurrency`; `None` ohne Grenze.
+async fn acquire_slot(
+    name: &str,
+    script: &ScriptConfig,
+    program: &Path,
+    timeout: Duration,
+    deadline: Pin<&mut Sleep>,
+    cancel: Pin<&mut impl Future<Output = ()>>,
+) -> Result<Option<OwnedSemaphorePermit>, Failure> {
+    let Some(limit) = script.max_concurrency else {
+        return Ok(None);
+    };
+    let semaphore = {
+        let mut slots = SLOTS.lock().unwrap_or_else(PoisonError::into_inner);
+        let entry = slots
+            .entry(program.to_path_buf())
+            .or_insert_with(|| (limit, Arc::new(Semaphore::new(limit))));
+        if entry.0 != limit {
+            *entry = (limit, Arc::new(Semaphore::new(limit)));
         }
+        entry.1.clone()
+    };
+
+    match script.on_busy {
+        OnBusy::Reject => semaphore.try_acquire_owned().map(Some).map_err(|_| {
+            let err =
+                AppError::conflict(format!("script {name} is busy (max_concurrency {limit})"));
+            (Err(err), "busy".into())
+        }),
+        OnBusy::Queue => tokio::select! {
+            permit = semaphore.acquire_owned() => {
+                Ok(Some(permit.expect("script semaphores are never closed")))
+            }
+            () = deadline => {
+                let err = AppError::ScriptTimeout { script: name.into(), timeout };
+                Err((Err(err), "timeout".into()))
+            }
+            () = cancel => {
+                let err = AppError::ScriptCancelled { script: name.into() };
+                Err((Err(err), "cancelled".into()))
+            }
+        },
+    }
+}
+
+/// Nächste Zeile einer Pipe (ungültiges UTF-8 wird ersetzt); eine geschlossene
+/// Pipe blockiert für immer, damit `select!` sie ignoriert.
+async fn next_line<R>(lines: &mut Option<Split<BufReader<R>>>) -> Option<String>
+where
+    R: AsyncRead + Unpin,
+{
+    match lines {
+        Some(lines) => lines
+            .next_segment()
+            .await
+            .ok()
+            .flatten()
+            .map(|line| String::from_utf8_lossy(&line).into_owned()),
+        None => std::future::pending().await,
+    }
+}
+
+/// Sucht das abschließende JSON-Objekt: kürzester Suffix ab einem Zeilenanfang,
+/// der vollständig als Objekt parst.
+fn final_json_object(stdout: &str) -> Option<Map<String, Value>> {
+    let trimmed = stdout.trim_end();
+    if !trimmed.ends_with('}') {
+        return None;
     }
+    let line_starts = std::iter::once(0).chain(trimmed.match_indices('\n').map(|(i, _)| i + 1));
+    let starts: Vec<usize> = line_starts.collect();
+    starts.into_iter().rev().find_map(|start| {
+        let candidate = trimmed[start..].trim_start();
+        if !candidate.starts_with('{') {
+            return None;
+        }
+        match serde_json::from_str::<Value>(candidate) {
+            Ok(Value::Object(map)) => Some(map),
+            _ => None,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let output = time::timeout(config.command_timeout, child.wait_with_output())
-        .await
-        .map_err(|_| {
-            anyhow!(
-                "command {} timed out after {:?}",
-                program,
-                config.command_timeout
-            )
-        })
-        .and_then(|result| result.with_context(|| format!("command {program} error")))?;
-
-    if !output.status.success() {
-        return Err(anyhow!(
-            "command {} exited with status {}: stderr={}",
-            program,
-            output.status,
-            String::from_utf8_lossy(&output.stderr)
-        ));
-    }
-
-    let s = String::from_utf8(output.stdout)
-        .map_err(|e| anyhow!("script did not output valid UTF-8: {e}"))?;
-    Ok(s)
+    #[test]
+    fn final_json_object_accepts_compact_and_indented_output() {
+        let compact = "switching…\n{\"mode\": \"alsa\", \"restarted\": true}\n";
+        assert_eq!(
+            final_json_object(compact).unwrap()["mode"],
+            Value::String("alsa".into())
+        );
+
+        let indented = "log line\n{\n  \"pid\": 42,\n  \"meta\": {\n    \"rate\": 96000\n  }\n}\n";
+        let map = final_json_object(indented).unwrap();
+        assert_eq!(map["pid"], 42);
+        assert_eq!(map["meta"]["rate"], 96000);
+
+        assert!(final_json_object("plain text\n").is_none());
+        assert!(final_json_object("[1, 2]\n").is_none());
+        assert!(final_json_object("{ not json }").is_none());
+    }
 }
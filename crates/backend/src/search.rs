@@ -0,0 +1,232 @@
+//! Feldsuche über `core.library.search`, gruppiert nach Backend.
+
+use std::collections::HashSet;
+
+use serde_json::{json, Map, Value};
+use tracing::instrument;
+
+use crate::discover::build_track;
+use crate::error::AppError;
+use crate::library;
+use crate::models::{
+    SearchAlbum, SearchArtist, SearchBackend, SearchQuery, SearchResponse, SearchTotals, TrackInfo,
+};
+use crate::mopidy::types::{self, SearchResult};
+use crate::mopidy::MopidyClient;
+use crate::validation;
+
+#[instrument(skip(mopidy))]
+pub async fn search(
+    mopidy: &dyn MopidyClient,
+    params: &SearchQuery,
+) -> Result<SearchResponse, AppError> {
+    let fields = params.fields();
+    if fields.is_empty() {
+        return Err(AppError::bad_request(
+            "at least one search field is required (any, artist, album, track_name, genre, \
+             date, albumartist, composer)",
+        ));
+    }
+    let query: Map<String, Value> = fields
+        .into_iter()
+        .map(|(field, value)| (field.to_string(), json!([value])))
+        .collect();
+    let uris = parse_backends(params.uris.as_deref())?;
+    let limit = library::page_size(params.limit)?;
+
+    let results = mopidy
+        .search(Value::Object(query), &uris, params.exact)
+        .await?;
+
+    let mut groups: Vec<Group> = Vec::new();
+    for result in results {
+        let Some(backend) = backend_of(&result) else {
+            continue;
+        };
+        let position = match groups.iter().position(|group| group.backend == backend) {
+            Some(position) => position,
+            None => {
+                groups.push(Group::new(backend));
+                groups.len() - 1
+            }
+        };
+        groups[position].extend(result);
+    }
+
+    Ok(SearchResponse {
+        exact: params.exact,
+        offset: params.offset,
+        limit,
+        backends: groups
+            .into_iter()
+            .map(|group| group.page(params.offset, limit))
+            .filter(|backend| backend.total != SearchTotals::default())
+            .collect(),
+    })
+}
+
+/// Backend-Filter: Schemata (`qobuz`, `local:`) oder erlaubte URIs.
+fn parse_backends(raw: Option<&str>) -> Result<Vec<String>, AppError> {
+    let mut uris: Vec<String> = Vec::new();
+    for uri in raw
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|uri| !uri.is_empty())
+    {
+        let uri = if validation::is_allowed_scheme(uri) {
+            format!("{}:", uri.trim_end_matches(':').to_lowercase())
+        } else if validation::is_allowed_uri(uri) {
+            uri.to_string()
+        } else {
+            return Err(AppError::bad_request(format!(
+                "disallowed URI scheme: {uri}"
+            )));
+        };
+        if !uris.contains(&uri) {
+            uris.push(uri);
+        }
+    }
+    Ok(uris)
+}
+
+/// Schema des Ergebnisses (`qobuz:search` → `qobuz`), sonst des ersten Treffers.
+fn backend_of(result: &SearchResult) -> Option<String> {
+    let uri = result
+        .uri
+        .as_deref()
+        .or_else(|| result.tracks.first().map(|track| track.uri.as_str()))
+        .or_else(|| result.albums.iter().find_map(|album| album.uri.as_deref()))
+        .or_else(|| {
+            result
+                .artists
+                .iter()
+                .find_map(|artist| artist.uri.as_deref())
+        })?;
+    let (scheme, _) = uri.split_once(':')?;
+    Some(scheme.to_lowercase())
+}
+
+/// Treffer eines Backends; URIs werden über alle Kategorien nur einmal aufgenommen.
+struct Group {
+    backend: String,
+    seen: HashSet<String>,
+    tracks: Vec<TrackInfo>,
+    albums: Vec<SearchAlbum>,
+    artists: Vec<SearchArtist>,
+}
+
+impl Group {
+    fn new(backend: String) -> Self {
+        Self {
+            backend,
+            seen: HashSet::new(),
+            tracks: Vec::new(),
+            albums: Vec::new(),
+            artists: Vec::new(),
+        }
+    }
+
+    /// Nur erlaubte, noch unbekannte URIs.
+    fn admit(&mut self, uri: &str) -> bool {
+        validation::is_allowed_uri(uri) && self.seen.insert(uri.to_string())
+    }
+
+    fn extend(&mut self, result: SearchResult) {
+        for track in &result.tracks {
+            if let Some(track) = build_track(track) {
+                if self.admit(&track.uri) {
+                    self.tracks.push(track);
+                }
+            }
+        }
+        for album in result.albums {
+            let (Some(uri), Some(name)) = (album.uri, album.name) else {
+                continue;
+            };
+            if self.admit(&uri) {
+                self.albums.push(SearchAlbum {
+                    uri,
+                    name,
+                    artists: types::names(&album.artists).map(Into::into).collect(),
+                    date: album.date,
+                    num_tracks: album.num_tracks,
+                });
+            }
+        }
+        for artist in result.artists {
+            let (Some(uri), Some(name)) = (artist.uri, artist.name) else {
+                continue;
+            };
+            if self.admit(&uri) {
+                self.artists.push(SearchArtist { uri, name });
+            }
+        }
+    }
+
+    fn page(self, offset: usize, limit: usize) -> SearchBackend {
+        SearchBackend {
+            total: SearchTotals {
+                tracks: self.tracks.len(),
+                albums: self.albums.len(),
+                artists: self.artists.len(),
+            },
+            backend: self.backend,
+            tracks: self.tracks.into_iter().skip(offset).take(limit).collect(),
+            albums: self.albums.into_iter().skip(offset).take(limit).collect(),
+            artists: self.artists.into_iter().skip(offset).take(limit).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_backends_normalizes_schemes() {
+        assert_eq!(
+            parse_backends(Some("Qobuz, local:,qobuz:,local:directory")).unwrap(),
+            vec!["qobuz:", "local:", "local:directory"]
+        );
+        assert!(parse_backends(None).unwrap().is_empty());
+        assert!(matches!(
+            parse_backends(Some("file:")),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn groups_deduplicate_and_paginate() {
+        let result: SearchResult = types::decode(json!({
+            "__model__": "SearchResult",
+            "uri": "qobuz:search",
+            "tracks": [
+                {"uri": "qobuz:track:1", "name": "One"},
+                {"uri": "qobuz:track:1", "name": "One"},
+                {"uri": "qobuz:track:2", "name": "Two"},
+                {"uri": "file:///tmp/x.flac", "name": "Outside"}
+            ],
+            "albums": [{"uri": "qobuz:album:1", "name": "Record", "artists": [{"name": "A"}]}],
+            "artists": [{"uri": "qobuz:artist:1", "name": "A"}, {"name": "No URI"}]
+        }))
+        .unwrap();
+        assert_eq!(backend_of(&result).as_deref(), Some("qobuz"));
+
+        let mut group = Group::new("qobuz".into());
+        group.extend(result.clone());
+        group.extend(result);
+        let page = group.page(1, 1);
+
+        assert_eq!(
+            page.total,
+            SearchTotals {
+                tracks: 2,
+                albums: 1,
+                artists: 1
+            }
+        );
+        assert_eq!(page.tracks[0].uri, "qobuz:track:2");
+        assert!(page.albums.is_empty());
+    }
+}
@@ -6,11 +6,20 @@ use std::sync::LazyLock;
 static URI_RE: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"^(?i:(qobuz|spotify|local))[:/].+").unwrap());
 
+/// Nur das Schema (`qobuz`, `local:`), z. B. als Backend-Filter der Suche.
+static SCHEME_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?i:(qobuz|spotify|local)):?$").unwrap());
+
 #[must_use]
 pub fn is_allowed_uri(uri: &str) -> bool {
     URI_RE.is_match(uri)
 }
 
+#[must_use]
+pub fn is_allowed_scheme(scheme: &str) -> bool {
+    SCHEME_RE.is_match(scheme)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -27,4 +36,11 @@ mod tests {
         assert!(!is_allowed_uri("file:///tmp/x")); // nicht freigeschaltet
         assert!(!is_allowed_uri("qobuz:")); // nichts dahinter
     }
+    #[test]
+    fn schemes() {
+        assert!(is_allowed_scheme("qobuz:"));
+        assert!(is_allowed_scheme("Local"));
+        assert!(!is_allowed_scheme("file:"));
+        assert!(!is_allowed_scheme("qobuz:track:1"));
+    }
 }
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::fs;
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
@@ -11,7 +12,10 @@ use tempfile::TempDir;
 use tower::ServiceExt;
 use url::Url;
 
-use hauski_backend::config::{AppConfig, ScriptConfig};
+use hauski_backend::config::{
+    AppConfig, AuthConfig, ConfigSources, MixerConfig, MopidyConfig, RecorderConfig, RpcPolicy,
+    ScriptConfig,
+};
 
 // Helper function to write a dummy executable script
 fn write_script(dir: &TempDir, name: &str, content: &str) -> std::path::PathBuf {
@@ -31,21 +35,24 @@ fn test_config(dir: &TempDir) -> AppConfig {
     AppConfig {
         bind_addr: "127.0.0.1:0".parse().unwrap(),
         mopidy_rpc_url: Url::parse("http://127.0.0.1:6680/mopidy/rpc").unwrap(),
-        audio_mode_script: ScriptConfig {
-            program: dir.path().join("audio-mode"),
-        },
-        playlist_script: ScriptConfig {
-            program: dir.path().join("playlist-from-list"),
-        },
-        rec_start_script: ScriptConfig {
-            program: dir.path().join("rec-start"),
-        },
-        rec_stop_script: ScriptConfig {
-            program: dir.path().join("rec-stop"),
-        },
+        audio_mode_script: ScriptConfig::new(dir.path().join("audio-mode")),
+        playlist_script: ScriptConfig::new(dir.path().join("playlist-from-list")),
         script_workdir: dir.path().to_path_buf(),
         command_timeout: Duration::from_secs(2),
+        job_timeout: Duration::from_secs(10),
         check_mopidy_health: false,
+        mopidy: MopidyConfig::default(),
+        recorder: RecorderConfig {
+            binary: dir.path().join("fake-pw-record"),
+            record_dir: dir.path().join("recordings"),
+            extension: "wav".into(),
+            state_dir: dir.path().join("state"),
+        },
+        auth: AuthConfig::default(),
+        rpc: RpcPolicy::default(),
+        actions: BTreeMap::new(),
+        mixer: MixerConfig::default(),
+        sources: ConfigSources::default(),
     }
 }
 
@@ -55,8 +62,6 @@ async fn rejects_empty_seed() {
     // Create dummy scripts so config validation passes
     write_script(&dir, "audio-mode", "");
     write_script(&dir, "playlist-from-list", "");
-    write_script(&dir, "rec-start", "");
-    write_script(&dir, "rec-stop", "");
 
     let app = hauski_backend::build_router(test_config(&dir));
 
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::fs;
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
@@ -8,6 +9,7 @@ use async_trait::async_trait;
 use axum::{
     body::Body,
     http::{Request, StatusCode},
+    Router,
 };
 use http_body_util::BodyExt;
 use serde_json::{json, Map, Value};
@@ -15,7 +17,11 @@ use tempfile::TempDir;
 use tower::ServiceExt;
 use url::Url;
 
-use hauski_backend::config::{AppConfig, ScriptConfig};
+use hauski_backend::client::BackendClient;
+use hauski_backend::config::{
+    ApiToken, AppConfig, AuthConfig, ConfigSources, MixerConfig, MopidyConfig, RecorderConfig,
+    RpcPolicy, ScriptConfig,
+};
 use hauski_backend::{AppError, AudioMode, MopidyClient};
 
 fn write_script(dir: &TempDir, name: &str, content: &str) -> std::path::PathBuf {
@@ -30,6 +36,32 @@ fn write_script(dir: &TempDir, name: &str, content: &str) -> std::path::PathBuf
     path
 }
 
+/// Schickt `request` an eine Kopie des Routers; Body als JSON, sonst als
+/// String (z. B. SSE), leer als `Null`.
+async fn send(app: &Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let body = if body.is_empty() {
+        Value::Null
+    } else {
+        serde_json::from_slice(&body)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&body).into_owned()))
+    };
+    (status, body)
+}
+
+fn build_request(method: &str, path: &str, body: Option<Value>) -> Request<Body> {
+    let builder = Request::builder().method(method).uri(path);
+    match body {
+        Some(body) => builder
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string())),
+        None => builder.body(Body::empty()),
+    }
+    .unwrap()
+}
+
 fn test_config(dir: &TempDir) -> AppConfig {
     test_config_with(dir, Url::parse("http://127.0.0.1:6680/mopidy/rpc").unwrap())
 }
@@ -38,21 +70,24 @@ fn test_config_with(dir: &TempDir, mopidy_rpc_url: Url) -> AppConfig {
     AppConfig {
         bind_addr: "127.0.0.1:0".parse().unwrap(),
         mopidy_rpc_url,
-        audio_mode_script: ScriptConfig {
-            program: dir.path().join("audio-mode"),
-        },
-        playlist_script: ScriptConfig {
-            program: dir.path().join("playlist-from-list"),
-        },
-        rec_start_script: ScriptConfig {
-            program: dir.path().join("rec-start"),
-        },
-        rec_stop_script: ScriptConfig {
-            program: dir.path().join("rec-stop"),
-        },
+        audio_mode_script: ScriptConfig::new(dir.path().join("audio-mode")),
+        playlist_script: ScriptConfig::new(dir.path().join("playlist-from-list")),
         script_workdir: dir.path().to_path_buf(),
         command_timeout: Duration::from_secs(2),
+        job_timeout: Duration::from_secs(10),
         check_mopidy_health: false,
+        mopidy: MopidyConfig::default(),
+        recorder: RecorderConfig {
+            binary: dir.path().join("fake-pw-record"),
+            record_dir: dir.path().join("recordings"),
+            extension: "wav".into(),
+            state_dir: dir.path().join("state"),
+        },
+        auth: AuthConfig::default(),
+        rpc: RpcPolicy::default(),
+        actions: BTreeMap::new(),
+        mixer: MixerConfig::default(),
+        sources: ConfigSources::default(),
     }
 }
 
@@ -61,6 +96,11 @@ struct FakeMopidy {
     lookup: Value,
     search: Value,
     health_error: Option<String>,
+    playlists: Value,
+    /// `core.library.browse`-Einträge je Verzeichnis-URI (`""` = Wurzel).
+    library: Value,
+    /// Verlauf der gesetzten Lautstärken (letzter Wert = aktuell) und Mute.
+    mixer: Arc<Mutex<(Vec<u8>, bool)>>,
 }
 
 impl FakeMopidy {
@@ -70,9 +110,27 @@ impl FakeMopidy {
             lookup,
             search,
             health_error: None,
+            playlists: json!([]),
+            library: json!({}),
+            mixer: Arc::new(Mutex::new((vec![40], false))),
         }
     }
 
+    fn with_playlists(mut self, playlists: Value) -> Self {
+        self.playlists = playlists;
+        self
+    }
+
+    fn with_library(mut self, library: Value) -> Self {
+        self.library = library;
+        self
+    }
+
+    fn with_mixer(mut self, mixer: Arc<Mutex<(Vec<u8>, bool)>>) -> Self {
+        self.mixer = mixer;
+        self
+    }
+
     fn with_health_error(mut self, error: impl Into<String>) -> Self {
         self.health_error = Some(error.into());
         self
@@ -82,6 +140,14 @@ impl FakeMopidy {
 #[async_trait]
 impl MopidyClient for FakeMopidy {
     async fn proxy(&self, payload: Value) -> Result<Value, AppError> {
+        if let Value::Array(entries) = payload {
+            let mut responses = Vec::new();
+            for entry in entries {
+                responses.push(self.proxy(entry).await?);
+            }
+            return Ok(Value::Array(responses));
+        }
+
         // Method extrahieren, ohne Lock zu halten
         let method = payload
             .get("method")
@@ -102,11 +168,159 @@ impl MopidyClient for FakeMopidy {
 
         match method.as_str() {
             "core.library.lookup" => {
-                response.insert("result".into(), self.lookup.clone());
+                let result = match payload["params"]["uris"].as_array() {
+                    // Mehrfach-Lookup: Tracks je URI
+                    Some(uris) => uris
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .map(|uri| {
+                            let tracks: Vec<Value> = self
+                                .lookup
+                                .as_array()
+                                .into_iter()
+                                .flatten()
+                                .filter(|track| track["uri"].as_str() == Some(uri))
+                                .cloned()
+                                .collect();
+                            (uri.to_string(), Value::Array(tracks))
+                        })
+                        .collect::<Map<_, _>>()
+                        .into(),
+                    None => self.lookup.clone(),
+                };
+                response.insert("result".into(), result);
+            }
+            "core.library.browse" => {
+                let uri = payload["params"]["uri"].as_str().unwrap_or_default();
+                let refs = self.library.get(uri).cloned().unwrap_or(json!([]));
+                response.insert("result".into(), refs);
+            }
+            "core.library.get_images" => {
+                let images: Map<String, Value> = payload["params"]["uris"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Value::as_str)
+                    .map(|uri| {
+                        let image = json!({
+                            "__model__": "Image",
+                            "uri": format!("https://img.example/{}.jpg", uri.replace(':', "_")),
+                            "width": 600,
+                            "height": 600
+                        });
+                        (uri.to_string(), json!([image]))
+                    })
+                    .collect();
+                response.insert("result".into(), Value::Object(images));
             }
             "core.library.search" => {
                 response.insert("result".into(), self.search.clone());
             }
+            "core.playback.play"
+            | "core.playback.pause"
+            | "core.playback.resume"
+            | "core.playback.stop"
+            | "core.playback.next"
+            | "core.playback.previous" => {
+                response.insert("result".into(), Value::Null);
+            }
+            "core.playback.seek" => {
+                response.insert("result".into(), Value::Bool(true));
+            }
+            "core.playback.get_current_track" => {
+                let track = self.lookup.get(0).cloned().unwrap_or(Value::Null);
+                response.insert("result".into(), track);
+            }
+            "core.playback.get_time_position" => {
+                response.insert("result".into(), Value::from(42_000));
+            }
+            "core.tracklist.add" => {
+                let uris = payload["params"]["uris"]
+                    .as_array()
+                    .cloned()
+                    .unwrap_or_default();
+                let tl_tracks: Vec<Value> = uris
+                    .into_iter()
+                    .enumerate()
+                    .map(|(idx, uri)| json!({"tlid": idx + 1, "track": {"uri": uri, "name": "Queued"}}))
+                    .collect();
+                response.insert("result".into(), Value::Array(tl_tracks));
+            }
+            "core.tracklist.get_tl_tracks" => {
+                let tl_tracks: Vec<Value> = self
+                    .lookup
+                    .as_array()
+                    .cloned()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .enumerate()
+                    .map(|(idx, track)| json!({"tlid": idx + 1, "track": track}))
+                    .collect();
+                response.insert("result".into(), Value::Array(tl_tracks));
+            }
+            "core.tracklist.get_consume"
+            | "core.tracklist.get_random"
+            | "core.tracklist.get_repeat"
+            | "core.tracklist.get_single" => {
+                response.insert("result".into(), Value::Bool(method.ends_with("repeat")));
+            }
+            "core.tracklist.set_consume"
+            | "core.tracklist.set_random"
+            | "core.tracklist.set_repeat"
+            | "core.tracklist.set_single"
+            | "core.tracklist.clear" => {
+                response.insert("result".into(), Value::Null);
+            }
+            "core.mixer.get_volume" => {
+                let volume = self.mixer.lock().unwrap().0.last().copied();
+                response.insert("result".into(), json!(volume));
+            }
+            "core.mixer.set_volume" => {
+                let volume = payload["params"]["volume"].as_u64().unwrap();
+                let accepted = volume <= 100;
+                if accepted {
+                    self.mixer.lock().unwrap().0.push(volume as u8);
+                }
+                response.insert("result".into(), Value::Bool(accepted));
+            }
+            "core.mixer.get_mute" => {
+                response.insert("result".into(), json!(self.mixer.lock().unwrap().1));
+            }
+            "core.mixer.set_mute" => {
+                self.mixer.lock().unwrap().1 = payload["params"]["mute"].as_bool().unwrap();
+                response.insert("result".into(), Value::Bool(true));
+            }
+            "core.playlists.as_list" => {
+                response.insert("result".into(), self.playlists.clone());
+            }
+            "core.playlists.create" => {
+                let name = payload["params"]["name"].clone();
+                response.insert(
+                    "result".into(),
+                    json!({"__model__": "Playlist", "uri": "m3u:created.m3u8", "name": name}),
+                );
+            }
+            "core.playlists.lookup" => {
+                let uri = payload["params"]["uri"].clone();
+                response.insert(
+                    "result".into(),
+                    json!({
+                        "__model__": "Playlist",
+                        "uri": uri,
+                        "name": "Existing",
+                        "tracks": [{"__model__": "Track", "uri": "qobuz:track:old"}]
+                    }),
+                );
+            }
+            "core.playlists.get_uri_schemes" => {
+                response.insert("result".into(), json!(["m3u"]));
+            }
+            "core.playlists.save" => {
+                response.insert("result".into(), payload["params"]["playlist"].clone());
+            }
+            "core.playlists.delete" => {
+                response.insert("result".into(), Value::Bool(true));
+            }
             "core.playback.get_state" => {
                 if let Some(error) = &self.health_error {
                     let mut error_obj = Map::new();
@@ -130,8 +344,6 @@ async fn health_endpoint_ok_without_mopidy() {
     write_script(&dir, "audio-mode", audio_script);
     let playlist_script = "#!/usr/bin/env bash\nset -euo pipefail\necho \"playlist:$1\"\ncat -\n";
     write_script(&dir, "playlist-from-list", playlist_script);
-    write_script(&dir, "rec-start", "");
-    write_script(&dir, "rec-stop", "");
 
     let app = hauski_backend::build_router(test_config(&dir));
 
@@ -159,8 +371,6 @@ async fn health_endpoint_reports_mopidy_error() {
     write_script(&dir, "audio-mode", audio_script);
     let playlist_script = "#!/usr/bin/env bash\nset -euo pipefail\necho \"playlist:$1\"\ncat -\n";
     write_script(&dir, "playlist-from-list", playlist_script);
-    write_script(&dir, "rec-start", "");
-    write_script(&dir, "rec-stop", "");
 
     let calls = Arc::new(Mutex::new(Vec::new()));
     let mopidy_stub: Arc<dyn MopidyClient> = Arc::new(
@@ -194,16 +404,29 @@ async fn health_endpoint_reports_mopidy_error() {
     assert_eq!(recorded, vec!["core.playback.get_state".to_string()]);
 }
 
+/// Fake `audio-mode`: merkt sich den Output in `mode.state`; `alsa` schreibt `$ALSA_OUTPUT`.
+fn write_mode_script(dir: &TempDir, alsa_output: &str) {
+    let script = format!(
+        r#"#!/usr/bin/env bash
+set -euo pipefail
+state="$(dirname "$0")/mode.state"
+echo "$1" >> "$(dirname "$0")/mode.calls"
+case "$1" in
+  show) cat "$state" ;;
+  alsa) echo "{alsa_output}" > "$state" ;;
+  pulse) echo "pulsesink" > "$state" ;;
+esac
+"#
+    );
+    write_script(dir, "audio-mode", &script);
+    fs::write(dir.path().join("mode.state"), "pulsesink\n").unwrap();
+}
+
 #[tokio::test]
 async fn mode_endpoints_invoke_script() {
     let dir = TempDir::new().unwrap();
-    let audio_script = "#!/usr/bin/env bash\nset -euo pipefail\nif [[ \"$1\" == \"show\" ]]; then\n  echo \"pulsesink\"\nelse\n  echo \"mode:$1\"\nfi\n";
-    write_script(&dir, "audio-mode", audio_script);
-    // Erwarte Aufruf: --input - -- <NAME>
-    let playlist_script = "#!/usr/bin/env bash\nset -euo pipefail\nif [[ \"$3\" != \"--\" ]]; then echo \"missing --\" >&2; exit 1; fi\necho \"playlist:$4\"\ncat -\n";
-    write_script(&dir, "playlist-from-list", playlist_script);
-    write_script(&dir, "rec-start", "");
-    write_script(&dir, "rec-stop", "");
+    write_mode_script(&dir, "alsasink device=hw:1,0");
+    write_script(&dir, "playlist-from-list", "");
 
     let app = hauski_backend::build_router(test_config(&dir));
 
@@ -245,25 +468,64 @@ async fn mode_endpoints_invoke_script() {
         .unwrap()
         .to_bytes();
     let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
-    assert_eq!(json["stdout"], "mode:alsa");
+    assert_eq!(json["previous_mode"], "pulse");
+    assert_eq!(json["mode"], "alsa");
+    assert_eq!(json["output"], "alsasink device=hw:1,0");
+    assert_eq!(json["changed"], true);
+    assert_eq!(
+        json["verification"],
+        json!({"ok": true, "output_matches": true})
+    );
+    assert_eq!(json["rolled_back"], false);
 }
 
 #[tokio::test]
-async fn playlist_endpoint_streams_uris() {
+async fn mode_switch_rolls_back_when_verification_fails() {
     let dir = TempDir::new().unwrap();
-    let audio_script = "#!/usr/bin/env bash\nset -euo pipefail\nif [[ \"$1\" == \"show\" ]]; then\n  echo \"pulsesink\"\nelse\n  echo \"mode:$1\"\nfi\n";
-    write_script(&dir, "audio-mode", audio_script);
-    // Erwarte Aufruf: --input - -- <NAME>
-    let playlist_script = "#!/usr/bin/env bash\nset -euo pipefail\nif [[ \"$3\" != \"--\" ]]; then echo \"missing --\" >&2; exit 1; fi\necho \"playlist:$4\"\ncat -\n";
-    write_script(&dir, "playlist-from-list", playlist_script);
-    write_script(&dir, "rec-start", "");
-    write_script(&dir, "rec-stop", "");
+    write_mode_script(&dir, "fakesink");
+    write_script(&dir, "playlist-from-list", "");
 
     let app = hauski_backend::build_router(test_config(&dir));
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/mode")
+                .header("content-type", "application/json")
+                .body(Body::from(json!({"mode": "alsa"}).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["requested_mode"], "alsa");
+    assert_eq!(json["verification"]["output_matches"], false);
+    assert_eq!(json["rolled_back"], true);
+    assert_eq!(json["mode"], "pulse");
+    assert_eq!(json["output"], "pulsesink");
+    assert!(json["error"].as_str().unwrap().contains("fakesink"));
+
+    let calls = fs::read_to_string(dir.path().join("mode.calls")).unwrap();
+    assert_eq!(
+        calls.lines().collect::<Vec<_>>(),
+        ["show", "alsa", "show", "pulse", "show"]
+    );
+}
+
+#[tokio::test]
+async fn playlist_endpoint_creates_playlist_natively() {
+    let dir = TempDir::new().unwrap();
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let mopidy_stub: Arc<dyn MopidyClient> =
+        Arc::new(FakeMopidy::new(calls.clone(), json!([]), json!([])));
+
+    let app = hauski_backend::build_router_with_mopidy(test_config(&dir), mopidy_stub);
     let payload = serde_json::json!({
         "name": "Test",
-        "uris": ["qobuz:track:1", "qobuz:track:2"],
-        "replace": true,
+        "uris": ["qobuz:track:1", "qobuz:track:2", "file:///etc/passwd"],
     });
 
     let response = app
@@ -281,27 +543,75 @@ async fn playlist_endpoint_streams_uris() {
     assert_eq!(response.status(), StatusCode::OK);
     let body = response.into_body().collect().await.unwrap().to_bytes();
     let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
-    let stdout = json["stdout"].as_str().unwrap();
-    assert!(stdout.contains("playlist:Test"), "Stdout was: {}", stdout);
-    assert!(json["stdout"].as_str().unwrap().contains("qobuz:track:1"));
+    assert_eq!(json["uri"], "m3u:created.m3u8");
+    assert_eq!(json["name"], "Test");
+    assert_eq!(json["added"], 2);
+    assert_eq!(json["rejected"], 1);
+    assert_eq!(json["rejected_uris"], json!(["file:///etc/passwd"]));
+    assert_eq!(json["replaced"], false);
+    assert_eq!(
+        calls.lock().unwrap().clone(),
+        vec![
+            "core.playlists.as_list".to_string(),
+            "core.playlists.create".to_string(),
+            "core.playlists.save".to_string(),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn playlist_endpoint_checks_scheme_in_same_batch() {
+    let dir = TempDir::new().unwrap();
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let mopidy_stub: Arc<dyn MopidyClient> =
+        Arc::new(FakeMopidy::new(calls.clone(), json!([]), json!([])));
+    let app = hauski_backend::build_router_with_mopidy(test_config(&dir), mopidy_stub);
+    let create = |scheme: &str| {
+        Request::builder()
+            .method("POST")
+            .uri("/playlists/from-list")
+            .header("content-type", "application/json")
+            .body(Body::from(
+                json!({"name": "Night", "uris": ["qobuz:track:1"], "scheme": scheme}).to_string(),
+            ))
+            .unwrap()
+    };
+
+    let response = app.clone().oneshot(create("spotify")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert!(json["error"]
+        .as_str()
+        .unwrap()
+        .contains("unknown playlist scheme 'spotify' (available: m3u)"));
+
+    let response = app.oneshot(create("m3u")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        calls.lock().unwrap().clone(),
+        vec![
+            "core.playlists.as_list",
+            "core.playlists.get_uri_schemes",
+            "core.playlists.as_list",
+            "core.playlists.get_uri_schemes",
+            "core.playlists.create",
+            "core.playlists.save",
+        ]
+    );
 }
 
 #[tokio::test]
 async fn playlist_endpoint_handles_dashed_names() {
     let dir = TempDir::new().unwrap();
-    let audio_script = "#!/usr/bin/env bash\nset -euo pipefail\n";
-    write_script(&dir, "audio-mode", audio_script);
-    // Erwarte Aufruf: --input - -- <NAME>
-    let playlist_script = "#!/usr/bin/env bash\nset -euo pipefail\nif [[ \"$3\" != \"--\" ]]; then echo \"missing --\" >&2; exit 1; fi\necho \"playlist:$4\"\ncat -\n";
-    write_script(&dir, "playlist-from-list", playlist_script);
-    write_script(&dir, "rec-start", "");
-    write_script(&dir, "rec-stop", "");
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let mopidy_stub: Arc<dyn MopidyClient> =
+        Arc::new(FakeMopidy::new(calls.clone(), json!([]), json!([])));
 
-    let app = hauski_backend::build_router(test_config(&dir));
+    let app = hauski_backend::build_router_with_mopidy(test_config(&dir), mopidy_stub);
     let payload = serde_json::json!({
         "name": "-Dashboard",
-        "uris": ["track:1"],
-        "replace": true,
+        "uris": ["qobuz:track:1"],
     });
 
     let response = app
@@ -319,10 +629,76 @@ async fn playlist_endpoint_handles_dashed_names() {
     assert_eq!(response.status(), StatusCode::OK);
     let body = response.into_body().collect().await.unwrap().to_bytes();
     let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
-    assert!(json["stdout"]
-        .as_str()
-        .unwrap()
-        .contains("playlist:-Dashboard"));
+    assert_eq!(json["name"], "-Dashboard");
+}
+
+#[tokio::test]
+async fn playlist_endpoint_replaces_only_when_requested() {
+    let dir = TempDir::new().unwrap();
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let mopidy_stub: Arc<dyn MopidyClient> = Arc::new(
+        FakeMopidy::new(calls.clone(), json!([]), json!([]))
+            .with_playlists(json!([{"uri": "m3u:Existing.m3u8", "name": "Existing"}])),
+    );
+
+    let app = hauski_backend::build_router_with_mopidy(test_config(&dir), mopidy_stub);
+
+    let conflict = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/playlists/from-list")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    json!({"name": "Existing", "uris": ["qobuz:track:1"]}).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(conflict.status(), StatusCode::CONFLICT);
+
+    let replaced = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/playlists/from-list")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    json!({"name": "Existing", "uris": ["qobuz:track:1"], "replace": true})
+                        .to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(replaced.status(), StatusCode::OK);
+    let body = replaced.into_body().collect().await.unwrap().to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["uri"], "m3u:Existing.m3u8");
+    assert_eq!(json["tracks"], 1);
+    assert_eq!(json["replaced"], true);
+
+    let appended = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/playlists/append")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    json!({"uri": "m3u:Existing.m3u8", "uris": ["qobuz:track:2"]}).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(appended.status(), StatusCode::OK);
+    let body = appended.into_body().collect().await.unwrap().to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["tracks"], 2);
+    assert_eq!(json["added"], 1);
 }
 
 #[tokio::test]
@@ -332,8 +708,6 @@ async fn discover_similar_returns_tracks() {
     write_script(&dir, "audio-mode", audio_script);
     let playlist_script = "#!/usr/bin/env bash\nset -euo pipefail\necho \"playlist:$1\"\ncat -\n";
     write_script(&dir, "playlist-from-list", playlist_script);
-    write_script(&dir, "rec-start", "");
-    write_script(&dir, "rec-stop", "");
     let calls = Arc::new(Mutex::new(Vec::new()));
     let mopidy_stub: Arc<dyn MopidyClient> = Arc::new(FakeMopidy::new(
         calls.clone(),
@@ -391,13 +765,17 @@ async fn discover_similar_returns_tracks() {
     let tracks = json["tracks"].as_array().unwrap();
     assert_eq!(tracks.len(), 1);
     assert_eq!(tracks[0]["uri"], "qobuz:track:1");
+    assert_eq!(tracks[0]["scores"], json!({"artist": 1.0, "query": 0.3}));
 
+    // Artist-, Album- und Freitextsuche; Genre/Co-Artist fehlen am Seed.
     let captured_calls = calls.lock().unwrap().clone();
     assert_eq!(
         captured_calls,
         vec![
             "core.library.lookup".to_string(),
             "core.library.search".to_string(),
+            "core.library.search".to_string(),
+            "core.library.search".to_string(),
         ]
     );
 }
@@ -409,8 +787,6 @@ async fn discover_similar_rejects_bad_schemes() {
     write_script(&dir, "audio-mode", audio_script);
     let playlist_script = "#!/usr/bin/env bash\nset -euo pipefail\necho \"playlist:$1\"\ncat -\n";
     write_script(&dir, "playlist-from-list", playlist_script);
-    write_script(&dir, "rec-start", "");
-    write_script(&dir, "rec-stop", "");
 
     let app = hauski_backend::build_router(test_config(&dir));
 
@@ -428,28 +804,1743 @@ async fn discover_similar_rejects_bad_schemes() {
     assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 }
 
-#[test]
-fn health_is_ok() {
-    let shared = Mutex::new(Vec::new());
-    {
-        let mut guard = shared.lock().unwrap();
-        guard.push("start".to_string());
-        assert_eq!(guard.as_slice(), ["start"]);
+#[tokio::test]
+async fn playback_status_reports_state_and_track() {
+    let dir = TempDir::new().unwrap();
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let mopidy_stub: Arc<dyn MopidyClient> = Arc::new(FakeMopidy::new(
+        calls.clone(),
+        json!([
+            {
+                "__model__": "Track",
+                "uri": "qobuz:track:1",
+                "name": "Now Playing",
+                "artists": [{"name": "Artist"}],
+                "album": {"__model__": "Album", "name": "Album", "date": "1999"},
+                "length": 215000,
+                "track_no": "4",
+                "disc_no": 1,
+                "bitrate": 1411,
+                "x_backend_hint": "kept"
+            }
+        ]),
+        json!([]),
+    ));
+
+    let app = hauski_backend::build_router_with_mopidy(test_config(&dir), mopidy_stub);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/playback")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["state"], "stopped");
+    assert_eq!(json["track"]["uri"], "qobuz:track:1");
+    assert_eq!(json["track"]["album"], "Album");
+    assert_eq!(json["track"]["duration_ms"], 215_000);
+    assert_eq!(json["track"]["track_no"], 4);
+    assert_eq!(json["track"]["disc_no"], 1);
+    assert_eq!(json["track"]["date"], "1999");
+    assert_eq!(json["track"]["bitrate"], 1411);
+    assert_eq!(json["time_position"], 42_000);
+}
+
+#[tokio::test]
+async fn playback_commands_call_typed_methods() {
+    let dir = TempDir::new().unwrap();
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let mopidy_stub: Arc<dyn MopidyClient> =
+        Arc::new(FakeMopidy::new(calls.clone(), json!([]), json!([])));
+
+    let app = hauski_backend::build_router_with_mopidy(test_config(&dir), mopidy_stub);
+
+    let requests = [
+        ("/playback/play", Some(json!({"tlid": 7}))),
+        ("/playback/pause", None),
+        ("/playback/resume", None),
+        ("/playback/next", None),
+        ("/playback/previous", None),
+        ("/playback/seek", Some(json!({"position": 30_000}))),
+        ("/playback/stop", None),
+    ];
+    for (uri, payload) in requests {
+        let mut builder = Request::builder().method("POST").uri(uri);
+        let body = match payload {
+            Some(payload) => {
+                builder = builder.header("content-type", "application/json");
+                Body::from(payload.to_string())
+            }
+            None => Body::empty(),
+        };
+        let response = app
+            .clone()
+            .oneshot(builder.body(body).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT, "{uri}");
     }
-    assert_eq!(shared.lock().unwrap().as_slice(), ["start"]);
+
+    let captured_calls = calls.lock().unwrap().clone();
+    assert_eq!(
+        captured_calls,
+        vec![
+            "core.playback.play".to_string(),
+            "core.playback.pause".to_string(),
+            "core.playback.resume".to_string(),
+            "core.playback.next".to_string(),
+            "core.playback.previous".to_string(),
+            "core.playback.seek".to_string(),
+            "core.playback.stop".to_string(),
+        ]
+    );
 }
 
-#[test]
-fn shared_survives_other_panics() {
-    let shared = Mutex::new(Vec::new());
-    let result = std::panic::catch_unwind(|| {
-        {
-            let mut guard = shared.lock().unwrap();
-            guard.push("before-panic".to_string());
-        }
-        panic!("simulated failure after releasing the lock");
+#[tokio::test]
+async fn queue_add_accepts_similar_tracks_output() {
+    let dir = TempDir::new().unwrap();
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let mopidy_stub: Arc<dyn MopidyClient> =
+        Arc::new(FakeMopidy::new(calls.clone(), json!([]), json!([])));
+
+    let app = hauski_backend::build_router_with_mopidy(test_config(&dir), mopidy_stub);
+    // Form wie das `tracks`-Array aus /discover/similar
+    let payload = json!({
+        "uris": ["qobuz:track:1"],
+        "tracks": [
+            {"uri": "qobuz:track:2", "name": "Two", "artists": ["Artist"]}
+        ],
     });
 
-    assert!(result.is_err());
-    assert_eq!(shared.lock().unwrap().as_slice(), ["before-panic"]);
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/queue")
+                .header("content-type", "application/json")
+                .body(Body::from(payload.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let added = json["added"].as_array().unwrap();
+    assert_eq!(added.len(), 2);
+    assert_eq!(added[1]["tlid"], 2);
+    assert_eq!(added[1]["track"]["uri"], "qobuz:track:2");
+    assert_eq!(
+        calls.lock().unwrap().clone(),
+        vec!["core.tracklist.add".to_string()]
+    );
+}
+
+#[tokio::test]
+async fn queue_add_rejects_bad_schemes() {
+    let dir = TempDir::new().unwrap();
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let mopidy_stub: Arc<dyn MopidyClient> =
+        Arc::new(FakeMopidy::new(calls.clone(), json!([]), json!([])));
+
+    let app = hauski_backend::build_router_with_mopidy(test_config(&dir), mopidy_stub);
+    let payload = json!({"uris": ["qobuz:track:1", "file:///etc/passwd"]});
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/queue")
+                .header("content-type", "application/json")
+                .body(Body::from(payload.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(calls.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn queue_listing_and_options() {
+    let dir = TempDir::new().unwrap();
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let mopidy_stub: Arc<dyn MopidyClient> = Arc::new(FakeMopidy::new(
+        calls.clone(),
+        json!([
+            {"uri": "qobuz:track:1", "name": "One"},
+            {"uri": "qobuz:track:2", "name": "Two"}
+        ]),
+        json!([]),
+    ));
+
+    let app = hauski_backend::build_router_with_mopidy(test_config(&dir), mopidy_stub);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/queue")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["tracks"].as_array().unwrap().len(), 2);
+    assert_eq!(json["tracks"][1]["track"]["name"], "Two");
+    assert_eq!(json["options"]["repeat"], true);
+    assert_eq!(json["options"]["random"], false);
+
+    calls.lock().unwrap().clear();
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("PUT")
+                .uri("/queue/options")
+                .header("content-type", "application/json")
+                .body(Body::from(json!({"random": true}).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let recorded = calls.lock().unwrap().clone();
+    assert_eq!(recorded[0], "core.tracklist.set_random");
+    assert!(!recorded.iter().any(|m| m == "core.tracklist.set_consume"));
+}
+
+#[tokio::test]
+async fn recording_endpoints_supervise_capture_binary() {
+    let dir = TempDir::new().unwrap();
+    write_script(&dir, "audio-mode", "");
+    write_script(&dir, "playlist-from-list", "");
+    // Steht für pw-record: läuft, bis ein Signal kommt
+    write_script(
+        &dir,
+        "fake-pw-record",
+        "#!/usr/bin/env bash\nexec sleep 30\n",
+    );
+
+    let app = hauski_backend::build_router(test_config(&dir));
+    let payload = json!({
+        "rate": 192000,
+        "channels": 2,
+        "device": "-motu",
+        "output": "take.wav",
+    });
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/recording/start")
+                .header("content-type", "application/json")
+                .body(Body::from(payload.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let started: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let output = dir.path().join("recordings").join("take.wav");
+    assert_eq!(started["output"], output.display().to_string());
+    assert_eq!(
+        started["command"],
+        json!([
+            dir.path().join("fake-pw-record").display().to_string(),
+            "--rate",
+            "192000",
+            "--channels",
+            "2",
+            "--format",
+            "S24_LE",
+            "--target",
+            "-motu",
+            output.display().to_string()
+        ])
+    );
+
+    let status_response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/recording")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(status_response.status(), StatusCode::OK);
+    let body = status_response
+        .into_body()
+        .collect()
+        .await
+        .unwrap()
+        .to_bytes();
+    let status: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(status["running"], true);
+    assert_eq!(status["pid"], started["pid"]);
+
+    let conflict = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/recording/start")
+                .header("content-type", "application/json")
+                .body(Body::from("{}"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(conflict.status(), StatusCode::CONFLICT);
+
+    let stop_response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/recording/stop")
+                .header("content-type", "application/json")
+                .body(Body::from(json!({"signal": "TERM"}).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(stop_response.status(), StatusCode::OK);
+    let body = stop_response
+        .into_body()
+        .collect()
+        .await
+        .unwrap()
+        .to_bytes();
+    let stopped: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(stopped["pid"], started["pid"]);
+    assert_eq!(stopped["signal"], "TERM");
+    assert_eq!(stopped["killed"], false);
+}
+
+#[tokio::test]
+async fn recording_start_rejects_zero_rate() {
+    let dir = TempDir::new().unwrap();
+    write_script(&dir, "audio-mode", "");
+    write_script(&dir, "playlist-from-list", "");
+
+    let app = hauski_backend::build_router(test_config(&dir));
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/recording/start")
+                .header("content-type", "application/json")
+                .body(Body::from(json!({"rate": 0}).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn recording_start_rejects_output_outside_record_dir() {
+    let dir = TempDir::new().unwrap();
+    let app = hauski_backend::build_router(test_config(&dir));
+    let outside = dir.path().join("outside").join("take.wav");
+
+    for output in [
+        outside.to_string_lossy().into_owned(),
+        "../../x/take.wav".into(),
+    ] {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/recording/start")
+                    .header("content-type", "application/json")
+                    .body(Body::from(json!({ "output": output }).to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{output}");
+    }
+    assert!(!dir.path().join("outside").exists());
+    assert!(!dir.path().parent().unwrap().join("x/take.wav").exists());
+}
+
+#[tokio::test]
+async fn recording_stop_without_session_conflicts() {
+    let dir = TempDir::new().unwrap();
+    write_script(&dir, "audio-mode", "");
+    write_script(&dir, "playlist-from-list", "");
+
+    let app = hauski_backend::build_router(test_config(&dir));
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/recording/stop")
+                .header("content-type", "application/json")
+                .body(Body::from("{}"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn events_endpoint_streams_mopidy_events() {
+    use futures_util::SinkExt;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (tcp, _) = listener.accept().await.unwrap();
+        let mut ws = tokio_tungstenite::accept_async(tcp).await.unwrap();
+        let message = json!({"event": "volume_changed", "volume": 42}).to_string();
+        ws.send(Message::Text(message.into())).await.unwrap();
+        // Verbindung offen halten, bis der Test fertig ist
+        tokio::time::sleep(Duration::from_secs(10)).await;
+    });
+
+    let dir = TempDir::new().unwrap();
+    let config = test_config_with(
+        &dir,
+        Url::parse(&format!("http://{addr}/mopidy/rpc")).unwrap(),
+    );
+    let app = hauski_backend::build_router(config);
+
+    // Router (und damit die Bridge) lebt, solange der Stream gelesen wird.
+    let response = app
+        .clone()
+        .oneshot(Request::get("/events").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()["content-type"].to_str().unwrap(),
+        "text/event-stream"
+    );
+
+    let mut body = response.into_body();
+    let mut received = String::new();
+    while !received.contains("event: volume_changed") {
+        let frame = tokio::time::timeout(Duration::from_secs(5), body.frame())
+            .await
+            .expect("frame in time")
+            .expect("stream open")
+            .unwrap();
+        if let Some(data) = frame.data_ref() {
+            received.push_str(std::str::from_utf8(data).unwrap());
+        }
+    }
+    assert!(received.contains(r#"data: {"event":"volume_changed","volume":42}"#));
+}
+
+#[tokio::test]
+async fn auth_layer_enforces_tokens_and_scopes() {
+    let dir = TempDir::new().unwrap();
+    let mut config = test_config(&dir);
+    config.auth = AuthConfig {
+        tokens: vec![
+            ApiToken::parse("viewer=read").unwrap(),
+            ApiToken::parse("player=read,playback").unwrap(),
+        ],
+    };
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let mopidy_stub: Arc<dyn MopidyClient> =
+        Arc::new(FakeMopidy::new(calls.clone(), json!([]), json!([])));
+    let app = hauski_backend::build_router_with_mopidy(config, mopidy_stub);
+
+    let pause = |auth: Option<(&str, &str)>| {
+        let mut builder = Request::post("/playback/pause");
+        if let Some((name, value)) = auth {
+            builder = builder.header(name, value);
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+
+    let (status, _) = send(&app, build_request("GET", "/health", None)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let missing = app.clone().oneshot(pause(None)).await.unwrap();
+    assert_eq!(missing.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(missing.headers()["www-authenticate"], "Bearer");
+
+    let (status, _) = send(&app, pause(Some(("authorization", "Bearer nope")))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, json) = send(&app, pause(Some(("authorization", "Bearer viewer")))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(json["error"], "token lacks scope 'playback'");
+
+    let (status, _) = send(&app, pause(Some(("x-api-key", "player")))).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert_eq!(
+        calls.lock().unwrap().as_slice(),
+        ["core.playback.pause".to_string()]
+    );
+}
+
+#[tokio::test]
+async fn rpc_filters_methods_and_batches() {
+    let dir = TempDir::new().unwrap();
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let mopidy_stub: Arc<dyn MopidyClient> =
+        Arc::new(FakeMopidy::new(calls.clone(), json!([]), json!([])));
+    let app = hauski_backend::build_router_with_mopidy(test_config(&dir), mopidy_stub);
+
+    let rpc = |payload: Value| {
+        let app = app.clone();
+        async move {
+            let response = app
+                .oneshot(
+                    Request::post("/rpc")
+                        .header("content-type", "application/json")
+                        .body(Body::from(payload.to_string()))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            let status = response.status();
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            (
+                status,
+                serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null),
+            )
+        }
+    };
+
+    let (status, json) = rpc(json!({
+        "jsonrpc": "2.0", "id": 1, "method": "core.library.refresh"
+    }))
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["id"], 1);
+    assert_eq!(json["error"]["code"], -32001);
+    assert!(calls.lock().unwrap().is_empty());
+
+    let (status, json) = rpc(json!([
+        {"jsonrpc": "2.0", "id": 1, "method": "core.playback.pause"},
+        {"jsonrpc": "2.0", "id": 2, "method": "core.library.refresh"},
+        {"jsonrpc": "2.0", "id": 3, "method": "core.tracklist.add",
+         "params": {"uris": ["file:///etc/passwd"]}},
+        {"jsonrpc": "2.0", "method": "core.library.refresh"},
+        {"jsonrpc": "2.0", "id": 4, "method": "core.playback.get_state"}
+    ]))
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let responses = json.as_array().unwrap();
+    let ids: Vec<_> = responses.iter().map(|r| r["id"].clone()).collect();
+    assert_eq!(ids, vec![json!(1), json!(2), json!(3), json!(4)]);
+    assert!(responses[0].get("error").is_none());
+    assert_eq!(responses[1]["error"]["code"], -32001);
+    assert_eq!(responses[2]["error"]["code"], -32602);
+    assert_eq!(responses[3]["result"], "stopped");
+    assert_eq!(
+        calls.lock().unwrap().as_slice(),
+        ["core.playback.pause", "core.playback.get_state"]
+    );
+
+    let (status, _) = rpc(json!({"jsonrpc": "2.0", "method": "core.library.refresh"})).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let response = app
+        .clone()
+        .oneshot(Request::post("/rpc").body(Body::from("{oops")).unwrap())
+        .await
+        .unwrap();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["error"]["code"], -32700);
+}
+
+#[tokio::test]
+async fn metrics_endpoint_reports_routes_scripts_and_mopidy() {
+    let dir = TempDir::new().unwrap();
+    write_mode_script(&dir, "alsasink device=hw:1,0");
+    // Freien Port belegen und wieder freigeben: Mopidy ist dort nicht erreichbar.
+    let unreachable = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let config = test_config_with(
+        &dir,
+        Url::parse(&format!("http://{unreachable}/mopidy/rpc")).unwrap(),
+    );
+    let app = hauski_backend::build_router(config);
+
+    for uri in ["/mode", "/playback"] {
+        app.clone()
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+    }
+    // Methodennamen aus `/rpc` landen nicht ungefiltert in den Labels.
+    app.clone()
+        .oneshot(
+            Request::post("/rpc")
+                .body(Body::from(
+                    json!({"jsonrpc": "2.0", "id": 1, "method": "core.made_up.x1"}).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let response = app
+        .oneshot(Request::get("/metrics").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let text = String::from_utf8(body.to_vec()).unwrap();
+
+    for expected in [
+        r#"hauski_http_requests_total{method="GET",route="/mode",status="200"}"#,
+        r#"hauski_http_requests_total{method="GET",route="/playback",status="502"}"#,
+        r#"hauski_http_request_duration_seconds_bucket{method="GET",route="/mode""#,
+        r#"hauski_script_runs_total{script="audio-mode",status="0"}"#,
+        r#"hauski_mopidy_errors_total{method="core.playback.get_state"}"#,
+        // Gauges sind prozessweit; parallele Tests ändern die Werte.
+        r#"hauski_audio_mode{mode="pulse"}"#,
+        "hauski_recording_active ",
+    ] {
+        assert!(text.contains(expected), "missing {expected} in:\n{text}");
+    }
+    assert!(text.contains(r#"hauski_mopidy_calls_total{method="other"}"#));
+    assert!(!text.contains("core.made_up"));
+}
+
+#[tokio::test]
+async fn admin_reload_swaps_config_and_keeps_old_on_error() {
+    use futures_util::SinkExt;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let dir = TempDir::new().unwrap();
+    fs::create_dir(dir.path().join("scripts")).unwrap();
+    for name in ["audio-mode", "playlist-from-list"] {
+        write_script(&dir, &format!("scripts/{name}"), "#!/bin/sh\nexit 0\n");
+    }
+    let config_path = dir.path().join("backend.toml");
+    let write_config = |deny: &str, extra: &str| {
+        fs::write(
+            &config_path,
+            format!(
+                "script_workdir = {workdir:?}\ncheck_mopidy_health = false\n{extra}\n\
+                 [recorder]\nstate_dir = {state:?}\n\n[rpc]\ndeny = [{deny}]\n",
+                workdir = dir.path().display().to_string(),
+                state = dir.path().join("state").display().to_string(),
+            ),
+        )
+        .unwrap();
+    };
+    write_config(r#""core.library.refresh""#, "");
+
+    let config = AppConfig::load(Some(&config_path)).expect("config loads");
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let mopidy_stub: Arc<dyn MopidyClient> =
+        Arc::new(FakeMopidy::new(calls.clone(), json!([]), json!([])));
+    let app = hauski_backend::build_router_with_mopidy(config, mopidy_stub);
+
+    let clear = || {
+        Request::post("/rpc")
+            .body(Body::from(
+                json!({"jsonrpc": "2.0", "id": 1, "method": "core.tracklist.clear"}).to_string(),
+            ))
+            .unwrap()
+    };
+    let reload = || Request::post("/admin/reload").body(Body::empty()).unwrap();
+
+    let (_, json) = send(&app, clear()).await;
+    assert!(json.get("error").is_none(), "{json}");
+
+    write_config(r#""core.library.refresh", "core.tracklist.clear""#, "");
+    let (status, report) = send(&app, reload()).await;
+    assert_eq!(status, StatusCode::OK, "{report}");
+    assert_eq!(report["reloaded"], true);
+    assert_eq!(report["changed"], json!(["rpc.deny"]));
+    assert_eq!(report["restart_required"], json!([]));
+
+    let (_, json) = send(&app, clear()).await;
+    assert_eq!(json["error"]["code"], -32001);
+
+    // Ungültige Datei: alter Stand bleibt aktiv.
+    write_config(r#""core.library.refresh""#, "command_timeout_ms = \"soon\"");
+    let (status, report) = send(&app, reload()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(report["reloaded"], false);
+    assert!(report["error"]
+        .as_str()
+        .unwrap()
+        .contains("command_timeout_ms"));
+
+    let (_, json) = send(&app, clear()).await;
+    assert_eq!(json["error"]["code"], -32001);
+    assert_eq!(calls.lock().unwrap().as_slice(), ["core.tracklist.clear"]);
+
+    // Neue Mopidy-Adresse: der offene SSE-Stream folgt ohne Neustart.
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (tcp, _) = listener.accept().await.unwrap();
+        let mut ws = tokio_tungstenite::accept_async(tcp).await.unwrap();
+        let message = json!({"event": "volume_changed", "volume": 42}).to_string();
+        ws.send(Message::Text(message.into())).await.unwrap();
+        tokio::time::sleep(Duration::from_secs(10)).await;
+    });
+    let response = app
+        .clone()
+        .oneshot(Request::get("/events").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    write_config(
+        r#""core.library.refresh""#,
+        &format!("mopidy_rpc_url = \"http://{addr}/mopidy/rpc\""),
+    );
+    let (status, report) = send(&app, reload()).await;
+    assert_eq!(status, StatusCode::OK, "{report}");
+    assert_eq!(report["changed"], json!(["mopidy_rpc_url", "rpc.deny"]));
+    assert_eq!(report["restart_required"], json!([]));
+
+    let mut body = response.into_body();
+    let mut received = String::new();
+    while !received.contains("event: volume_changed") {
+        let frame = tokio::time::timeout(Duration::from_secs(5), body.frame())
+            .await
+            .expect("frame in time")
+            .expect("stream open")
+            .unwrap();
+        if let Some(data) = frame.data_ref() {
+            received.push_str(std::str::from_utf8(data).unwrap());
+        }
+    }
+}
+
+#[tokio::test]
+async fn backend_client_in_process_and_over_http() {
+    let dir = TempDir::new().unwrap();
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let mopidy_stub: Arc<dyn MopidyClient> =
+        Arc::new(FakeMopidy::new(calls.clone(), json!([]), json!([])));
+    let mut config = test_config(&dir);
+    config.auth = AuthConfig {
+        tokens: vec![ApiToken::parse("cli-token=read,playback").unwrap()],
+    };
+    let app = hauski_backend::build_router_with_mopidy(config, mopidy_stub);
+
+    let local = BackendClient::from_router(app.clone());
+    let response = local
+        .post("/queue", json!({"uris": ["local:track:a.flac"]}))
+        .await
+        .unwrap();
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        response.error_message(),
+        "missing bearer token or X-API-Key"
+    );
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    let base = Url::parse(&format!("http://{addr}/")).unwrap();
+
+    let remote = BackendClient::http(base.clone(), Some("cli-token".into()));
+    let response = remote
+        .post("/queue", json!({"uris": ["local:track:a.flac"]}))
+        .await
+        .unwrap();
+    assert!(response.is_success(), "{response:?}");
+    assert_eq!(
+        response.body["added"][0]["track"]["uri"],
+        "local:track:a.flac"
+    );
+
+    let response = remote.post("/playback/play", json!({})).await.unwrap();
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+    assert!(response.body.is_null());
+
+    let forbidden = BackendClient::http(base, Some("cli-token".into()))
+        .post("/mode", json!({"mode": "alsa"}))
+        .await
+        .unwrap();
+    assert_eq!(forbidden.status, StatusCode::FORBIDDEN);
+    assert_eq!(
+        calls.lock().unwrap().as_slice(),
+        ["core.tracklist.add", "core.playback.play"]
+    );
+}
+
+#[tokio::test]
+async fn run_script_captures_output_and_distinguishes_failures() {
+    use hauski_backend::scripts::runner::run_script;
+
+    let dir = TempDir::new().unwrap();
+    let mut config = test_config(&dir);
+    config.command_timeout = Duration::from_millis(300);
+
+    let ok = write_script(
+        &dir,
+        "structured",
+        "#!/bin/sh\necho 'working…'\necho 'warning: slow disk' >&2\nprintf '{\\n  \"pid\": 42\\n}\\n'\n",
+    );
+    let output = run_script(&config, ok.to_str().unwrap(), &[], None)
+        .await
+        .unwrap();
+    assert_eq!(output.exit_code, Some(0));
+    assert_eq!(output.stderr, "warning: slow disk\n");
+    assert_eq!(output.result.as_ref().unwrap()["pid"], 42);
+    #[derive(serde::Deserialize)]
+    struct Started {
+        pid: u32,
+    }
+    assert_eq!(output.parse_result::<Started>().unwrap().unwrap().pid, 42);
+
+    let failing = write_script(&dir, "failing", "#!/bin/sh\necho 'no device' >&2\nexit 3\n");
+    match run_script(&config, failing.to_str().unwrap(), &[], None).await {
+        Err(AppError::ScriptFailed(output)) => {
+            assert_eq!(output.exit_code, Some(3));
+            assert_eq!(output.stderr.trim(), "no device");
+        }
+        other => panic!("expected ScriptFailed, got {other:?}"),
+    }
+
+    let slow = write_script(&dir, "slow", "#!/bin/sh\nsleep 5\n");
+    assert!(matches!(
+        run_script(&config, slow.to_str().unwrap(), &[], None).await,
+        Err(AppError::ScriptTimeout { .. })
+    ));
+}
+
+#[tokio::test]
+async fn script_options_apply_args_env_workdir_timeout_and_limits() {
+    use hauski_backend::config::OnBusy;
+    use hauski_backend::scripts::runner::run_configured;
+
+    let dir = TempDir::new().unwrap();
+    let config = test_config(&dir);
+    std::fs::create_dir(dir.path().join("work")).unwrap();
+
+    let echo = write_script(
+        &dir,
+        "echo-env",
+        "#!/bin/sh\necho \"$* $HAUSKI_TEST_SINK $(basename \"$PWD\")\"\n",
+    );
+    let mut script = ScriptConfig::new(&echo);
+    script.args = vec!["--fixed".into()];
+    script
+        .env
+        .insert("HAUSKI_TEST_SINK".into(), "hw:1,0".into());
+    script.workdir = Some("work".into());
+    let output = run_configured(&config, &script, &["show"], None)
+        .await
+        .unwrap();
+    assert_eq!(output.stdout, "--fixed show hw:1,0 work\n");
+
+    // Eigener Timeout schlägt `command_timeout` (2 s).
+    let slow = write_script(&dir, "slow", "#!/bin/sh\nsleep 5\n");
+    let mut script = ScriptConfig::new(&slow);
+    script.timeout = Some(Duration::from_millis(200));
+    let started = std::time::Instant::now();
+    assert!(matches!(
+        run_configured(&config, &script, &[], None).await,
+        Err(AppError::ScriptTimeout { .. })
+    ));
+    assert!(started.elapsed() < Duration::from_secs(1));
+
+    // Wechselseitiger Ausschluss: `mkdir` schlägt fehl, wenn zwei Läufe überlappen.
+    let exclusive = write_script(
+        &dir,
+        "exclusive",
+        "#!/bin/sh\nmkdir \"$0.lock\" || exit 1\nsleep 0.3\nrmdir \"$0.lock\"\n",
+    );
+    let mut script = ScriptConfig::new(&exclusive);
+    script.max_concurrency = Some(1);
+    let (first, second) = tokio::join!(
+        run_configured(&config, &script, &[], None),
+        run_configured(&config, &script, &[], None)
+    );
+    assert!(first.is_ok() && second.is_ok(), "{first:?} / {second:?}");
+
+    script.on_busy = OnBusy::Reject;
+    let (first, second) = tokio::join!(run_configured(&config, &script, &[], None), async {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        run_configured(&config, &script, &[], None).await
+    });
+    assert!(first.is_ok(), "{first:?}");
+    assert!(matches!(second, Err(AppError::Conflict(_))), "{second:?}");
+}
+
+#[tokio::test]
+async fn actions_validate_arguments_against_schema() {
+    use hauski_backend::config::ActionConfig;
+
+    let dir = TempDir::new().unwrap();
+    let dac = write_script(&dir, "dac-power", "#!/bin/sh\necho \"dac $1\"\n");
+    let say = write_script(&dir, "say", "#!/bin/sh\nprintf '%s\\n' \"$@\"\n");
+    let mut config = test_config(&dir);
+    config.actions.insert(
+        "dac_power".into(),
+        ActionConfig::new(
+            ScriptConfig::new(&dac),
+            Some("DAC ein/aus".into()),
+            json!({
+                "type": "array",
+                "items": { "enum": ["on", "off"] },
+                "minItems": 1,
+                "maxItems": 1
+            }),
+        )
+        .unwrap(),
+    );
+    config.actions.insert(
+        "say".into(),
+        ActionConfig::new(
+            ScriptConfig::new(&say),
+            None,
+            json!({ "type": "array", "items": { "type": "string" } }),
+        )
+        .unwrap(),
+    );
+    let app = hauski_backend::build_router(config);
+
+    let run = |name: &str, body: Value| {
+        let app = app.clone();
+        let request = Request::post(format!("/actions/{name}"))
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        async move {
+            let response = app.oneshot(request).await.unwrap();
+            let status = response.status();
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            (status, serde_json::from_slice::<Value>(&body).unwrap())
+        }
+    };
+
+    let response = app
+        .clone()
+        .oneshot(Request::get("/actions").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let listed: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(listed[0]["name"], "dac_power");
+    assert_eq!(listed[0]["description"], "DAC ein/aus");
+    assert_eq!(listed[0]["schema"]["items"]["enum"][1], "off");
+    assert_eq!(listed[1]["name"], "say");
+
+    let (status, body) = run("dac_power", json!({ "args": ["on"] })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["stdout"], "dac on\n");
+
+    let (status, body) = run("dac_power", json!({ "args": ["on; reboot"] })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"]
+        .as_str()
+        .unwrap()
+        .contains("invalid arguments for action 'dac_power'"));
+    let (status, _) = run("dac_power", json!({})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Ohne Shell kommen Sonderzeichen unverändert beim Programm an.
+    let marker = dir.path().join("pwned");
+    let payload = format!("$(touch {})", marker.display());
+    let (status, body) = run("say", json!({ "args": [payload, "a b"] })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["stdout"], format!("{payload}\na b\n"));
+    assert!(!marker.exists());
+
+    let (status, _) = run("shutdown", json!({ "args": [] })).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+/// Läuft der Prozess noch (Zombies zählen als beendet)?
+fn process_running(pid: &str) -> bool {
+    std::fs::read_to_string(format!("/proc/{}/stat", pid.trim()))
+        .ok()
+        .and_then(|stat| {
+            stat.rsplit_once(") ")
+                .map(|(_, rest)| !rest.starts_with('Z'))
+        })
+        .unwrap_or(false)
+}
+
+async fn wait_until_stopped(pid: &str, within: Duration) -> bool {
+    let deadline = std::time::Instant::now() + within;
+    while std::time::Instant::now() < deadline {
+        if !process_running(pid) {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    false
+}
+
+#[tokio::test]
+async fn script_timeout_and_disconnect_kill_the_process_group() {
+    use hauski_backend::scripts::reaper::TERM_GRACE;
+    use hauski_backend::scripts::runner::run_script;
+
+    let dir = TempDir::new().unwrap();
+    let mut config = test_config(&dir);
+    config.command_timeout = Duration::from_millis(300);
+
+    // Enkel im Hintergrund, Skript selbst blockiert im Vordergrund.
+    let spawner = write_script(
+        &dir,
+        "spawner",
+        "#!/bin/sh\nsleep 30 >/dev/null 2>&1 &\necho $! > \"$0.pid\"\nsleep 30\n",
+    );
+    let pid_file = dir.path().join("spawner.pid");
+    assert!(matches!(
+        run_script(&config, spawner.to_str().unwrap(), &[], None).await,
+        Err(AppError::ScriptTimeout { .. })
+    ));
+    let grandchild = std::fs::read_to_string(&pid_file).unwrap();
+    assert!(
+        wait_until_stopped(&grandchild, TERM_GRACE).await,
+        "grandchild {grandchild} survived SIGTERM"
+    );
+
+    // Ignoriert der Enkel SIGTERM, folgt nach der Frist SIGKILL.
+    let stubborn = write_script(
+        &dir,
+        "stubborn",
+        "#!/bin/sh\n(trap '' TERM; sleep 30) >/dev/null 2>&1 &\necho $! > \"$0.pid\"\nsleep 30\n",
+    );
+    assert!(run_script(&config, stubborn.to_str().unwrap(), &[], None)
+        .await
+        .is_err());
+    let grandchild = std::fs::read_to_string(dir.path().join("stubborn.pid")).unwrap();
+    assert!(process_running(&grandchild));
+    assert!(
+        wait_until_stopped(&grandchild, TERM_GRACE * 3).await,
+        "grandchild {grandchild} survived SIGKILL"
+    );
+
+    // Client getrennt: Der Future wird fallen gelassen, der Reaper räumt auf.
+    config.command_timeout = Duration::from_secs(30);
+    std::fs::remove_file(&pid_file).unwrap();
+    let dropped = tokio::time::timeout(
+        Duration::from_millis(300),
+        run_script(&config, spawner.to_str().unwrap(), &[], None),
+    )
+    .await;
+    assert!(dropped.is_err());
+    let grandchild = std::fs::read_to_string(&pid_file).unwrap();
+    assert!(
+        wait_until_stopped(&grandchild, TERM_GRACE).await,
+        "grandchild {grandchild} survived client disconnect"
+    );
+}
+
+#[tokio::test]
+async fn mode_switch_rolls_back_when_verification_errors() {
+    let dir = TempDir::new().unwrap();
+    // `show` scheitert, solange der neue (alsa) Modus aktiv ist
+    write_script(
+        &dir,
+        "audio-mode",
+        r#"#!/usr/bin/env bash
+set -euo pipefail
+state="$(dirname "$0")/mode.state"
+echo "$1" >> "$(dirname "$0")/mode.calls"
+case "$1" in
+  show) grep -q alsasink "$state" && { echo "show broke" >&2; exit 1; }; cat "$state" ;;
+  alsa) echo "alsasink" > "$state" ;;
+  pulse) echo "pulsesink" > "$state" ;;
+esac
+"#,
+    );
+    fs::write(dir.path().join("mode.state"), "pulsesink\n").unwrap();
+    write_script(&dir, "playlist-from-list", "");
+
+    let app = hauski_backend::build_router(test_config(&dir));
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/mode")
+                .header("content-type", "application/json")
+                .body(Body::from(json!({"mode": "alsa"}).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["changed"], true);
+    assert_eq!(json["rolled_back"], true);
+    assert_eq!(json["mode"], "pulse");
+    assert!(json["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("verifying alsa failed"));
+
+    let calls = fs::read_to_string(dir.path().join("mode.calls")).unwrap();
+    assert_eq!(
+        calls.lines().collect::<Vec<_>>(),
+        ["show", "alsa", "show", "pulse", "show"]
+    );
+}
+
+#[tokio::test]
+async fn mode_switch_failure_reports_script_details() {
+    let dir = TempDir::new().unwrap();
+    write_script(
+        &dir,
+        "audio-mode",
+        "#!/bin/sh\nif [ \"$1\" = show ]; then echo pulsesink; exit 0; fi\necho 'card busy' >&2\nexit 2\n",
+    );
+    let app = hauski_backend::build_router(test_config(&dir));
+
+    let response = app
+        .oneshot(
+            Request::post("/mode")
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"mode": "alsa"}"#))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let report: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(report["changed"], false);
+    assert_eq!(report["script"]["exit_code"], 2);
+    assert_eq!(report["script"]["stderr"], "card busy\n");
+    assert!(report["script"]["duration_ms"].is_u64());
+    assert!(report["error"].as_str().unwrap().contains("card busy"));
+}
+
+#[tokio::test]
+async fn jobs_stream_output_and_can_be_cancelled() {
+    let dir = TempDir::new().unwrap();
+    write_script(
+        &dir,
+        "playlist-from-list",
+        "#!/bin/sh\n[ \"$1\" = Slow ] && exec sleep 30\necho \"import $1\"\necho 'skipped 1 uri' >&2\nsleep 0.2\necho '{\"created\": 3}'\n",
+    );
+    let app = hauski_backend::build_router(test_config(&dir));
+    let start = |script: &str| {
+        build_request(
+            "POST",
+            "/jobs",
+            Some(json!({"script": script, "args": ["Focus"]})),
+        )
+    };
+
+    let (status, job) = send(&app, start("playlist_from_list")).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert_eq!(job["state"], "running");
+    let id = job["id"].as_u64().unwrap();
+
+    // Der Stream endet nach `finished`; Zeilen davor kommen live bzw. aus dem Puffer.
+    let (status, sse) = send(
+        &app,
+        build_request("GET", &format!("/jobs/{id}/stream"), None),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let sse = sse.as_str().unwrap();
+    assert!(sse.contains("event: stdout\ndata: import Focus\n"), "{sse}");
+    assert!(
+        sse.contains("event: stderr\ndata: skipped 1 uri\n"),
+        "{sse}"
+    );
+    assert!(sse.contains("event: finished\n"), "{sse}");
+
+    let (_, job) = send(&app, build_request("GET", &format!("/jobs/{id}"), None)).await;
+    assert_eq!(job["state"], "succeeded");
+    assert_eq!(job["exit_code"], 0);
+    assert_eq!(job["result"]["created"], 3);
+    assert_eq!(job["lines"], 3);
+
+    // Das Skript liest stdin nie; der Abbruch greift trotzdem.
+    let (_, job) = send(
+        &app,
+        build_request(
+            "POST",
+            "/jobs",
+            Some(
+                json!({"script": "playlist_from_list", "args": ["Slow"], "input": "x".repeat(1 << 20)}),
+            ),
+        ),
+    )
+    .await;
+    let id = job["id"].as_u64().unwrap();
+    let started = std::time::Instant::now();
+    let (status, job) = send(&app, build_request("DELETE", &format!("/jobs/{id}"), None)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(job["state"], "cancelled");
+    assert!(started.elapsed() < Duration::from_secs(5));
+
+    let (status, _) = send(&app, start("rm_rf")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    // Skripte mit eigenem Endpunkt und gefährliche Optionen bleiben gesperrt.
+    let (status, body) = send(&app, start("hw_mixer")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+    for args in [
+        json!(["Focus", "--input", "/etc/passwd"]),
+        json!(["Focus", "--rpc-url=http://example.com/rpc"]),
+        json!(["Focus", "Other"]),
+    ] {
+        let (status, body) = send(
+            &app,
+            build_request(
+                "POST",
+                "/jobs",
+                Some(json!({"script": "playlist_from_list", "args": args})),
+            ),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{args}: {body}");
+    }
+    let (status, _) = send(&app, build_request("GET", "/jobs/999", None)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn jobs_switch_audio_mode_with_verification_and_rollback() {
+    let dir = TempDir::new().unwrap();
+    write_mode_script(&dir, "alsasink device=hw:1,0");
+    let app = hauski_backend::build_router(test_config(&dir));
+    let start = |args: Value| {
+        build_request(
+            "POST",
+            "/jobs",
+            Some(json!({"script": "audio_mode", "args": args})),
+        )
+    };
+    let finished = |id: u64| {
+        let app = &app;
+        async move {
+            // Der Stream endet erst mit `finished`.
+            send(
+                app,
+                build_request("GET", &format!("/jobs/{id}/stream"), None),
+            )
+            .await;
+            send(app, build_request("GET", &format!("/jobs/{id}"), None))
+                .await
+                .1
+        }
+    };
+
+    let (status, job) = send(&app, start(json!(["ALSA"]))).await;
+    assert_eq!(status, StatusCode::ACCEPTED, "{job}");
+    let job = finished(job["id"].as_u64().unwrap()).await;
+    assert_eq!(job["state"], "succeeded", "{job}");
+    assert_eq!(job["exit_code"], 0);
+    assert_eq!(job["result"]["mode"], "alsa");
+    assert_eq!(job["result"]["verification"]["ok"], true);
+    assert_eq!(job["result"]["rolled_back"], false);
+
+    // Nicht verifizierter Wechsel: Rollback, Job schlägt fehl.
+    write_mode_script(&dir, "fakesink");
+    let (_, job) = send(&app, start(json!(["alsa"]))).await;
+    let job = finished(job["id"].as_u64().unwrap()).await;
+    assert_eq!(job["state"], "failed", "{job}");
+    assert_eq!(job["result"]["rolled_back"], true);
+    assert_eq!(job["result"]["mode"], "pulse");
+    assert!(job["error"].as_str().unwrap().contains("fakesink"), "{job}");
+    let calls = fs::read_to_string(dir.path().join("mode.calls")).unwrap();
+    assert!(
+        calls.ends_with("show\nalsa\nshow\npulse\nshow\n"),
+        "{calls}"
+    );
+
+    // Laufende Moduswechsel lassen sich nicht abbrechen.
+    write_script(
+        &dir,
+        "audio-mode",
+        "#!/bin/sh\n[ \"$1\" = show ] && echo pulsesink\nexec sleep 1\n",
+    );
+    let (_, job) = send(&app, start(json!(["alsa"]))).await;
+    let id = job["id"].as_u64().unwrap();
+    let (status, body) = send(&app, build_request("DELETE", &format!("/jobs/{id}"), None)).await;
+    assert_eq!(status, StatusCode::CONFLICT, "{body}");
+
+    for args in [json!([]), json!(["hdmi"]), json!(["alsa", "--restart"])] {
+        let (status, body) = send(&app, start(args.clone())).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{args}: {body}");
+    }
+}
+
+#[test]
+fn health_is_ok() {
+    let shared = Mutex::new(Vec::new());
+    {
+        let mut guard = shared.lock().unwrap();
+        guard.push("start".to_string());
+        assert_eq!(guard.as_slice(), ["start"]);
+    }
+    assert_eq!(shared.lock().unwrap().as_slice(), ["start"]);
+}
+
+#[test]
+fn shared_survives_other_panics() {
+    let shared = Mutex::new(Vec::new());
+    let result = std::panic::catch_unwind(|| {
+        {
+            let mut guard = shared.lock().unwrap();
+            guard.push("before-panic".to_string());
+        }
+        panic!("simulated failure after releasing the lock");
+    });
+
+    assert!(result.is_err());
+    assert_eq!(shared.lock().unwrap().as_slice(), ["before-panic"]);
+}
+
+#[tokio::test]
+async fn volume_and_mute_use_mopidy_mixer() {
+    let dir = TempDir::new().unwrap();
+    let mixer = Arc::new(Mutex::new((vec![40], false)));
+    let mopidy = FakeMopidy::new(Arc::new(Mutex::new(Vec::new())), json!([]), json!([]))
+        .with_mixer(mixer.clone());
+    let app = hauski_backend::build_router_with_mopidy(test_config(&dir), Arc::new(mopidy));
+
+    let (status, body) = send(&app, build_request("GET", "/volume", None)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        body,
+        json!({ "mixer": "mopidy", "volume": 40, "mute": false })
+    );
+
+    let (_, body) = send(
+        &app,
+        build_request("PUT", "/volume", Some(json!({ "volume": 70 }))),
+    )
+    .await;
+    assert_eq!(body["volume"], 70);
+    let (_, body) = send(
+        &app,
+        build_request("PUT", "/volume", Some(json!({ "step": "down" }))),
+    )
+    .await;
+    assert_eq!(body["volume"], 65);
+    let (_, body) = send(
+        &app,
+        build_request("PUT", "/volume", Some(json!({ "step": 50 }))),
+    )
+    .await;
+    assert_eq!(body["volume"], 100);
+
+    // Rampe: mehrere Zwischenschritte, monoton fallend bis zum Ziel.
+    let (_, body) = send(
+        &app,
+        build_request(
+            "PUT",
+            "/volume",
+            Some(json!({ "volume": 60, "ramp_ms": 100 })),
+        ),
+    )
+    .await;
+    assert_eq!(body["volume"], 60);
+    let history = mixer.lock().unwrap().0.clone();
+    let ramp = &history[history.iter().position(|v| *v == 100).unwrap()..];
+    assert!(ramp.len() > 2, "{history:?}");
+    assert!(ramp.windows(2).all(|pair| pair[0] > pair[1]), "{history:?}");
+
+    for invalid in [
+        json!({ "volume": 101 }),
+        json!({ "volume": 10, "step": "up" }),
+        json!({}),
+        json!({ "volume": 10, "ramp_ms": 60_000 }),
+    ] {
+        let (status, _) = send(&app, build_request("PUT", "/volume", Some(invalid.clone()))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{invalid}");
+    }
+
+    let (status, body) = send(
+        &app,
+        build_request("PUT", "/mute", Some(json!({ "mute": true }))),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!({ "mixer": "mopidy", "mute": true }));
+    let (_, body) = send(&app, build_request("GET", "/mute", None)).await;
+    assert_eq!(body["mute"], true);
+}
+
+#[tokio::test]
+async fn volume_uses_hardware_mixer_in_alsa_mode() {
+    use hauski_backend::config::{MixerMode, VolumeCurve};
+
+    let dir = TempDir::new().unwrap();
+    write_script(
+        &dir,
+        "audio-mode",
+        "#!/bin/sh\necho 'alsasink device=hw:M2'\n",
+    );
+    let state = dir.path().join("mixer-state");
+    fs::write(&state, "-30.00 false").unwrap();
+    let hw_mixer = write_script(
+        &dir,
+        "hw-mixer",
+        &format!(
+            r#"#!/bin/sh
+state="{}"
+read db mute < "$state"
+case "$1" in
+  set-db) db="$2" ;;
+  mute) [ "$2" = on ] && mute=true || mute=false ;;
+esac
+echo "$db $mute" > "$state"
+echo "{{\"db\": $db, \"mute\": $mute}}"
+"#,
+            state.display()
+        ),
+    );
+    let mut config = test_config(&dir);
+    config.mixer = MixerConfig {
+        mode: MixerMode::Auto,
+        script: ScriptConfig::new(hw_mixer),
+        curve: VolumeCurve::Linear,
+        min_db: -60.0,
+        max_db: 0.0,
+        step: 10,
+    };
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let mopidy = FakeMopidy::new(calls.clone(), json!([]), json!([]));
+    let app = hauski_backend::build_router_with_mopidy(config, Arc::new(mopidy));
+
+    let (status, body) = send(&app, build_request("GET", "/volume", None)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        body,
+        json!({ "mixer": "hardware", "volume": 50, "mute": false, "db": -30.0 })
+    );
+
+    let (_, body) = send(
+        &app,
+        build_request("PUT", "/volume", Some(json!({ "step": "up" }))),
+    )
+    .await;
+    assert_eq!(body["volume"], 60);
+    assert_eq!(body["db"], -24.0);
+    assert_eq!(fs::read_to_string(&state).unwrap().trim(), "-24.00 false");
+
+    let (_, body) = send(
+        &app,
+        build_request("PUT", "/mute", Some(json!({ "mute": true }))),
+    )
+    .await;
+    assert_eq!(body, json!({ "mixer": "hardware", "mute": true }));
+
+    // Mopidys Software-Mixer bleibt im Bitperfect-Modus unangetastet.
+    assert!(!calls
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|method| method.starts_with("core.mixer")));
+}
+
+#[tokio::test]
+async fn mopidy_client_retries_times_out_and_opens_circuit() {
+    use axum::routing::post;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// `failures` Antworten mit 503, danach normal; `hang` blockiert jede Anfrage.
+    #[derive(Default)]
+    struct Upstream {
+        requests: AtomicUsize,
+        failures: AtomicUsize,
+        hang: std::sync::atomic::AtomicBool,
+    }
+
+    let upstream = Arc::new(Upstream::default());
+    let server = axum::Router::new()
+        .route(
+            "/mopidy/rpc",
+            post(
+                |axum::extract::State(upstream): axum::extract::State<Arc<Upstream>>,
+                 axum::Json(payload): axum::Json<Value>| async move {
+                    upstream.requests.fetch_add(1, Ordering::SeqCst);
+                    if upstream.hang.load(Ordering::SeqCst) {
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                    }
+                    let failing = upstream
+                        .failures
+                        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                        .is_ok();
+                    if failing {
+                        return (StatusCode::SERVICE_UNAVAILABLE, axum::Json(Value::Null));
+                    }
+                    let result = match payload["method"].as_str() {
+                        Some("core.playback.get_state") => json!("stopped"),
+                        _ => Value::Null,
+                    };
+                    (
+                        StatusCode::OK,
+                        axum::Json(
+                            json!({ "jsonrpc": "2.0", "id": payload["id"], "result": result }),
+                        ),
+                    )
+                },
+            ),
+        )
+        .with_state(upstream.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, server).await.unwrap() });
+
+    let dir = TempDir::new().unwrap();
+    let mut config = test_config_with(
+        &dir,
+        Url::parse(&format!("http://{addr}/mopidy/rpc")).unwrap(),
+    );
+    config.check_mopidy_health = true;
+    config.mopidy = MopidyConfig {
+        request_timeout: Duration::from_millis(200),
+        retries: 2,
+        retry_backoff: Duration::from_millis(10),
+        breaker_threshold: 2,
+        breaker_cooldown: Duration::from_millis(400),
+        ..MopidyConfig::default()
+    };
+    let app = hauski_backend::build_router(config);
+
+    // Zwei 503 werden für lesende Methoden durch Retries aufgefangen.
+    upstream.failures.store(2, Ordering::SeqCst);
+    let (_, body) = send(&app, build_request("GET", "/health", None)).await;
+    assert_eq!(body["status"], "ok", "{body}");
+    assert_eq!(body["mopidy"]["circuit"], "closed");
+    assert_eq!(upstream.requests.load(Ordering::SeqCst), 3);
+
+    // Schreibende Methoden werden nicht wiederholt; hängendes Mopidy → Timeout.
+    upstream.hang.store(true, Ordering::SeqCst);
+    let started = std::time::Instant::now();
+    let (status, body) = send(&app, build_request("POST", "/playback/next", None)).await;
+    assert_eq!(status, StatusCode::BAD_GATEWAY);
+    assert!(
+        body["error"].as_str().unwrap().contains("timed out"),
+        "{body}"
+    );
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert_eq!(upstream.requests.load(Ordering::SeqCst), 4);
+
+    // Zweiter Fehlschlag in Folge öffnet den Breaker.
+    let (_, body) = send(&app, build_request("GET", "/health", None)).await;
+    assert_eq!(body["status"], "degraded");
+    assert_eq!(body["mopidy"]["circuit"], "open");
+    assert!(body["mopidy"]["retry_after_ms"].as_u64().unwrap() > 0);
+    assert_eq!(upstream.requests.load(Ordering::SeqCst), 7);
+
+    // Offen: sofortige Ablehnung ohne Anfrage an Mopidy.
+    let (status, body) = send(&app, build_request("GET", "/playback", None)).await;
+    assert_eq!(status, StatusCode::BAD_GATEWAY);
+    assert!(
+        body["error"].as_str().unwrap().contains("circuit open"),
+        "{body}"
+    );
+    assert_eq!(upstream.requests.load(Ordering::SeqCst), 7);
+
+    // Nach dem Cooldown schließt eine gelungene Probe den Breaker wieder.
+    upstream.hang.store(false, Ordering::SeqCst);
+    tokio::time::sleep(Duration::from_millis(450)).await;
+    let (_, body) = send(&app, build_request("GET", "/health", None)).await;
+    assert_eq!(body["status"], "ok", "{body}");
+    assert_eq!(body["mopidy"]["circuit"], "closed");
+}
+
+#[tokio::test]
+async fn library_browse_lookup_and_images() {
+    let dir = TempDir::new().unwrap();
+    let favourites: Vec<Value> = (1..=5)
+        .map(|i| json!({"__model__": "Ref", "type": "album", "uri": format!("qobuz:album:{i}"), "name": format!("Album {i}")}))
+        .collect();
+    let mopidy = FakeMopidy::new(
+        Arc::new(Mutex::new(Vec::new())),
+        json!([
+            {"__model__": "Track", "uri": "qobuz:track:1", "name": "One", "length": 180000, "track_no": 1},
+            {"__model__": "Track", "uri": "local:track:a.flac", "name": "A"}
+        ]),
+        json!([]),
+    )
+    .with_library(json!({
+        "": [
+            {"__model__": "Ref", "type": "directory", "uri": "qobuz:directory", "name": "Qobuz"},
+            {"__model__": "Ref", "type": "directory", "uri": "file:///srv/music", "name": "Files"},
+            {"__model__": "Ref", "type": "directory", "uri": "local:directory", "name": "Local"}
+        ],
+        "qobuz:directory:favourites": favourites
+    }));
+    let app = hauski_backend::build_router_with_mopidy(test_config(&dir), Arc::new(mopidy));
+
+    // Wurzel: nicht freigeschaltete Backends (file:) fehlen.
+    let (status, body) = send(&app, build_request("GET", "/library/browse", None)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["total"], 2);
+    assert_eq!(
+        body["items"][0],
+        json!({"uri": "qobuz:directory", "name": "Qobuz", "type": "directory"})
+    );
+
+    let (_, body) = send(
+        &app,
+        build_request(
+            "GET",
+            "/library/browse?uri=qobuz:directory:favourites&offset=1&limit=2",
+            None,
+        ),
+    )
+    .await;
+    assert_eq!(body["total"], 5);
+    assert_eq!(body["limit"], 2);
+    let uris: Vec<&str> = body["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["uri"].as_str().unwrap())
+        .collect();
+    assert_eq!(uris, ["qobuz:album:2", "qobuz:album:3"]);
+    assert_eq!(body["items"][0]["type"], "album");
+
+    let (status, _) = send(
+        &app,
+        build_request("GET", "/library/browse?uri=file:///etc", None),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = send(
+        &app,
+        build_request(
+            "GET",
+            "/library/lookup?uris=qobuz:track:1,local:track:a.flac",
+            None,
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["tracks"]["qobuz:track:1"][0]["duration_ms"], 180_000);
+    assert_eq!(body["tracks"]["local:track:a.flac"][0]["name"], "A");
+
+    let (status, _) = send(
+        &app,
+        build_request(
+            "GET",
+            "/library/lookup?uris=qobuz:track:1,file:///etc/passwd",
+            None,
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = send(
+        &app,
+        build_request("GET", "/library/images?uris=qobuz:album:1", None),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(
+        body["images"]["qobuz:album:1"],
+        json!([{"uri": "https://img.example/qobuz_album_1.jpg", "width": 600, "height": 600}])
+    );
+}
+
+#[tokio::test]
+async fn search_groups_typed_results_by_backend() {
+    let dir = TempDir::new().unwrap();
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let mopidy = FakeMopidy::new(
+        calls.clone(),
+        json!([]),
+        json!([
+            {
+                "__model__": "SearchResult",
+                "uri": "qobuz:search",
+                "tracks": [
+                    {"__model__": "Track", "uri": "qobuz:track:1", "name": "One", "length": 200000, "disc_no": 1},
+                    {"__model__": "Track", "uri": "qobuz:track:2", "name": "Two"}
+                ],
+                "albums": [{"__model__": "Album", "uri": "qobuz:album:1", "name": "Record", "date": "2001"}]
+            },
+            {
+                "__model__": "SearchResult",
+                "uri": "qobuz:search:extra",
+                "tracks": [{"__model__": "Track", "uri": "qobuz:track:1", "name": "One"}]
+            },
+            {
+                "__model__": "SearchResult",
+                "uri": "local:search",
+                "artists": [{"__model__": "Artist", "uri": "local:artist:a", "name": "Artist"}]
+            }
+        ]),
+    );
+    let app = hauski_backend::build_router_with_mopidy(test_config(&dir), Arc::new(mopidy));
+
+    let (status, body) = send(
+        &app,
+        build_request(
+            "GET",
+            "/search?artist=Artist&track_name=One&exact=true&uris=qobuz,local:&limit=1",
+            None,
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["exact"], true);
+    let qobuz = &body["backends"][0];
+    assert_eq!(qobuz["backend"], "qobuz");
+    assert_eq!(
+        qobuz["total"],
+        json!({"tracks": 2, "albums": 1, "artists": 0})
+    );
+    assert_eq!(qobuz["tracks"].as_array().unwrap().len(), 1);
+    assert!(qobuz["tracks"][0].get("score").is_none());
+    assert_eq!(qobuz["tracks"][0]["duration_ms"], 200_000);
+    assert_eq!(
+        qobuz["albums"][0],
+        json!({"uri": "qobuz:album:1", "name": "Record", "date": "2001"})
+    );
+    assert_eq!(body["backends"][1]["backend"], "local");
+    assert_eq!(body["backends"][1]["artists"][0]["name"], "Artist");
+
+    let (_, body) = send(&app, build_request("GET", "/search?any=One&offset=1", None)).await;
+    assert_eq!(body["backends"][0]["tracks"][0]["uri"], "qobuz:track:2");
+
+    let (status, _) = send(&app, build_request("GET", "/search?exact=true", None)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = send(
+        &app,
+        build_request("GET", "/search?artist=A&uris=file:", None),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        calls.lock().unwrap().as_slice(),
+        ["core.library.search", "core.library.search"]
+    );
 }
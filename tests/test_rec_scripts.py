@@ -110,6 +110,18 @@ def test_rec_start_force_clears_running_process(home: Path) -> None:
             proc.kill()
 
 
+def test_rec_start_places_relative_output_in_record_dir(home: Path) -> None:
+    """Verify that relative `--output` paths land under AUDIO_RECORD_DIR."""
+    result = run_script(
+        "rec-start",
+        ["--dry-run", "--json", "--output", "sessions/take.wav"],
+        home,
+    )
+    assert result.returncode == 0, result.stderr
+    payload = json.loads(result.stdout)
+    assert payload["output"] == str(home / "recordings" / "sessions" / "take.wav")
+
+
 def test_rec_start_rejects_existing_output(home: Path) -> None:
     """Verify that `rec-start` aborts if the output file already exists."""
     target = home / "recordings" / "exists.wav"
@@ -152,3 +164,37 @@ def test_rec_stop_requires_pid_file(home: Path) -> None:
     result = run_script("rec-stop", [], home)
     assert result.returncode == 1
     assert "No recorder PID state found" in result.stdout
+
+
+def test_rec_stop_status_json_reports_running_recorder(home: Path) -> None:
+    """Verify that `rec-stop --status --json` reports PID and output."""
+    proc = subprocess.Popen(["sleep", "5"])  # noqa: S607
+    state_dir = home / ".cache" / "hauski-audio"
+    state_dir.mkdir(parents=True)
+    (state_dir / "recording.pid").write_text(f"{proc.pid}\n")
+    (state_dir / "recording.json").write_text(
+        json.dumps({"pid": proc.pid, "output": "/tmp/take.wav"}),
+    )
+    try:
+        result = run_script("rec-stop", ["--status", "--json"], home)
+        assert result.returncode == 0, result.stderr
+        payload = json.loads(result.stdout)
+        assert payload["running"] is True
+        assert payload["pid"] == proc.pid
+        assert payload["output"] == "/tmp/take.wav"
+        assert (state_dir / "recording.pid").exists()
+    finally:
+        proc.terminate()
+        try:
+            proc.wait(timeout=1)
+        except subprocess.TimeoutExpired:
+            proc.kill()
+
+
+def test_rec_stop_status_json_without_recorder(home: Path) -> None:
+    """Verify that `rec-stop --status --json` succeeds when idle."""
+    result = run_script("rec-stop", ["--status", "--json"], home)
+    assert result.returncode == 0, result.stderr
+    payload = json.loads(result.stdout)
+    assert payload["running"] is False
+    assert payload["pid"] is None